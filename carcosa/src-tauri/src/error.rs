@@ -32,13 +32,57 @@ pub enum Error {
     #[strum(props(code = "net.id_parse"))]
     NetIdParse(#[from] iroh::KeyParsingError),
 
+    #[error("Invalid signature: {0:?}")]
+    #[strum(props(code = "net.signature"))]
+    Signature(#[from] iroh::SignatureError),
+
     #[error("JSON encoding/decoding error: {0:?}")]
     #[strum(props(code = "validation.json"))]
     JsonDecode(#[from] serde_json::error::Error),
 
     #[error("Tauri framework error: {0:?}")]
     #[strum(props(code = "sys.tauri"))]
-    Tauri(#[from] tauri::Error)
+    Tauri(#[from] tauri::Error),
+
+    #[error("Failed to initialize logging: {0:?}")]
+    #[strum(props(code = "sys.logging"))]
+    Logging(#[from] log::SetLoggerError),
+
+    #[error("Failed to decode collaborative document data: {0:?}")]
+    #[strum(props(code = "document.decode"))]
+    DocumentDecode(#[from] yrs::encoding::read::Error),
+
+    #[error("Failed to apply collaborative document update: {0:?}")]
+    #[strum(props(code = "document.update"))]
+    DocumentUpdate(#[from] yrs::error::UpdateError),
+
+    #[error("Failed to process image: {0:?}")]
+    #[strum(props(code = "map.image"))]
+    Image(#[from] image::ImageError),
+
+    #[error("Network error: {0}")]
+    #[strum(props(code = "net.connection"))]
+    Network(String),
+
+    #[error("Sync is not running for the current project")]
+    #[strum(props(code = "net.sync_stopped"))]
+    SyncNotRunning,
+
+    #[error("No project is currently open")]
+    #[strum(props(code = "project.none"))]
+    NoActiveProject,
+
+    #[error("No {kind} exists with ID {id}")]
+    #[strum(props(code = "data.not_found"))]
+    NotFound { kind: String, id: String },
+
+    #[error("Migration to schema version {version} failed: {reason}")]
+    #[strum(props(code = "database.migration"))]
+    Migration { version: u32, reason: String },
+
+    #[error("The database operation was cancelled")]
+    #[strum(props(code = "database.cancelled"))]
+    Cancelled,
 }
 
 macro_rules! db_errs {
@@ -78,6 +122,24 @@ impl Error {
             reason: reason.into(),
         }
     }
+
+    pub fn not_found(kind: impl Into<String>, id: impl ToString) -> Self {
+        Self::NotFound {
+            kind: kind.into(),
+            id: id.to_string(),
+        }
+    }
+
+    pub fn network(err: impl std::fmt::Display) -> Self {
+        Self::Network(err.to_string())
+    }
+
+    pub fn migration(version: u32, reason: impl Into<String>) -> Self {
+        Self::Migration {
+            version,
+            reason: reason.into(),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -89,9 +151,8 @@ pub struct MetaError {
     message: String,
 }
 
-impl<T: Into<Error>> From<T> for MetaError {
-    fn from(value: T) -> Self {
-        let err = value.into();
+impl From<&Error> for MetaError {
+    fn from(err: &Error) -> Self {
         Self {
             code: err.get_str("code").unwrap_or("unknown").to_string(),
             message: err.to_string(),
@@ -99,6 +160,12 @@ impl<T: Into<Error>> From<T> for MetaError {
     }
 }
 
+impl<T: Into<Error>> From<T> for MetaError {
+    fn from(value: T) -> Self {
+        Self::from(&value.into())
+    }
+}
+
 impl MetaError {
     pub fn operation(code: impl Into<String>, message: impl Into<String>) -> Self {
         Self {
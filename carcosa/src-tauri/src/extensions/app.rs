@@ -1,6 +1,34 @@
+use std::path::{Path, PathBuf};
+
+use platform_dirs::AppDirs;
 use tauri::{Manager, Runtime};
 
-use crate::types::{ActiveProject, ApplicationState, ApplicationStateWrapper, ProjectSettings};
+use crate::{
+    extensions::{
+        DatabasesExt, HistoryExt, InvitesExt, LoggingExt, RecentProjectsExt, SyncExt,
+        PROJECT_MIGRATIONS,
+    },
+    procedures::{AppEvent, AppEventExt},
+    types::{
+        ActiveProject, AppSettings, ApplicationState, ApplicationStateWrapper, ProjectSettings,
+        PROJECT_DATABASE,
+    },
+};
+
+/// Carcosa's per-user platform directories.
+pub fn app_dirs() -> crate::Result<AppDirs> {
+    AppDirs::new(Some("carcosa"), false).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Could not determine platform directories",
+        )
+        .into()
+    })
+}
+
+fn app_settings_path() -> crate::Result<PathBuf> {
+    Ok(app_dirs()?.config_dir.join("settings.json"))
+}
 
 pub trait ApplicationExt<R: Runtime> {
     fn get_app_state(&self) -> ApplicationState;
@@ -9,6 +37,21 @@ pub trait ApplicationExt<R: Runtime> {
         updater: impl FnOnce(ApplicationState) -> crate::Result<ApplicationState>,
     ) -> crate::Result<ApplicationState>;
     fn set_active_project(&self, project: ActiveProject) -> crate::Result<ApplicationState>;
+    fn open_project_databases(&self, project: impl AsRef<Path>) -> crate::Result<()>;
+    fn update_project_settings(
+        &self,
+        updater: impl FnOnce(ProjectSettings) -> crate::Result<ProjectSettings>,
+    ) -> crate::Result<ProjectSettings>;
+    fn update_app_settings(
+        &self,
+        updater: impl FnOnce(AppSettings) -> crate::Result<AppSettings>,
+    ) -> crate::Result<AppSettings>;
+
+    /// Loads the saved app settings into the app state.
+    fn load_app_settings(&self) -> crate::Result<AppSettings>;
+
+    /// Reopens the last active project, if it still exists.
+    fn restore_last_project(&self) -> crate::Result<()>;
 }
 
 impl<R: Runtime, T: Manager<R>> ApplicationExt<R> for T {
@@ -28,18 +71,131 @@ impl<R: Runtime, T: Manager<R>> ApplicationExt<R> for T {
     }
 
     fn set_active_project(&self, project: ActiveProject) -> crate::Result<ApplicationState> {
-        match project {
+        if let Some(node) = self.app_handle().sync_state().write().take() {
+            tauri::async_runtime::spawn(node.shutdown());
+        }
+        self.clear_databases();
+        self.app_handle().reset_history();
+        let state = match project.clone() {
             ActiveProject::None => self.update_app_state(|state| {
                 Ok(state
                     .with_active_project(ActiveProject::None)
                     .with_project_settings(None))
             }),
-            ActiveProject::Local { path } => self.update_app_state(|state| {
-                let project_settings = ProjectSettings::load(path.clone())?;
-                Ok(state
-                    .with_active_project(ActiveProject::Local { path })
-                    .with_project_settings(Some(project_settings)))
-            }),
+            ActiveProject::Local { path } | ActiveProject::Remote { path, .. } => {
+                let opened = ProjectSettings::load(path.clone()).and_then(|settings| {
+                    self.open_project_databases(path.clone())?;
+                    Ok(settings)
+                });
+                match opened {
+                    Ok(project_settings) => self.update_app_state(|state| {
+                        Ok(state
+                            .with_active_project(project.clone())
+                            .with_project_settings(Some(project_settings)))
+                    }),
+                    Err(err) => {
+                        self.clear_databases();
+                        self.update_app_state(|state| {
+                            Ok(state
+                                .with_active_project(ActiveProject::None)
+                                .with_project_settings(None))
+                        })?;
+                        Err(err)
+                    }
+                }
+            }
+        }?;
+
+        self.set_log_project(state.project_settings().map(|settings| settings.name()));
+        if let Some(settings) = state.project_settings() {
+            if let Err(err) = self.remember_project(&project, settings.name()) {
+                log::warn!("Failed to update recent projects: {err}");
+            }
+        }
+        if let Err(err) = self.update_app_settings(|settings| {
+            Ok(AppSettings {
+                last_project: state.active_project(),
+                ..settings
+            })
+        }) {
+            log::warn!("Failed to save app settings: {err}");
+        }
+        self.emit_event(AppEvent::ActivatedProject { project })?;
+        Ok(state)
+    }
+
+    fn open_project_databases(&self, project: impl AsRef<Path>) -> crate::Result<()> {
+        std::fs::create_dir_all(ProjectSettings::data_dir(project.as_ref()))?;
+        // Undo history doesn't outlive the session, so neither do the savepoints backing it.
+        self.open_database(
+            PROJECT_DATABASE,
+            ProjectSettings::database_path(project.as_ref(), PROJECT_DATABASE),
+            PROJECT_MIGRATIONS,
+        )?
+        .clear_savepoints()
+    }
+
+    fn update_project_settings(
+        &self,
+        updater: impl FnOnce(ProjectSettings) -> crate::Result<ProjectSettings>,
+    ) -> crate::Result<ProjectSettings> {
+        let state = self.update_app_state(|state| {
+            let path = state
+                .active_project()
+                .path()
+                .ok_or(crate::Error::NoActiveProject)?;
+            let current = state
+                .project_settings()
+                .ok_or(crate::Error::NoActiveProject)?;
+            let updated = updater(current)?.save(path)?;
+            Ok(state.with_project_settings(Some(updated)))
+        })?;
+        state
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)
+    }
+
+    fn update_app_settings(
+        &self,
+        updater: impl FnOnce(AppSettings) -> crate::Result<AppSettings>,
+    ) -> crate::Result<AppSettings> {
+        let state = self.update_app_state(|state| {
+            let updated = updater(state.settings())?.save(app_settings_path()?)?;
+            Ok(state.with_settings(updated))
+        })?;
+        self.set_log_level(state.settings().log_level);
+        Ok(state.settings())
+    }
+
+    fn load_app_settings(&self) -> crate::Result<AppSettings> {
+        let settings = AppSettings::load(app_settings_path()?)?;
+        let state = self.update_app_state(|state| Ok(state.with_settings(settings)))?;
+        Ok(state.settings())
+    }
+
+    fn restore_last_project(&self) -> crate::Result<()> {
+        let last_project = self.get_app_state().settings().last_project;
+        match last_project.path() {
+            Some(path) if path.join("project.json").is_file() => {}
+            Some(path) => {
+                log::info!("Last project at {} no longer exists", path.display());
+                return Ok(());
+            }
+            None => return Ok(()),
+        }
+        match last_project {
+            ActiveProject::Remote { host, .. } => {
+                let app = self.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(err) = app.open_remote_project(host).await {
+                        log::warn!("Failed to reopen the last project: {err}");
+                    }
+                });
+            }
+            project => {
+                let _ = self.set_active_project(project)?;
+            }
         }
+        Ok(())
     }
 }
@@ -0,0 +1,414 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+use redb::{
+    MultimapTableDefinition, ReadTransaction, ReadableMultimapTable, TableError, WriteTransaction,
+};
+use tauri::{Manager, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{
+        history::HistoryAction, maps, remove_record, sync::SyncMessage, table,
+        world::ensure_hosted, ApplicationExt, DatabasesExt, HistoryExt, RecordTableExt, SyncExt,
+        TypedTable, WorldExt, WriteTransactionExt,
+    },
+    types::{AssetDimensions, AssetInfo, AssetUsage, PeerIdentity},
+};
+
+table!(pub AssetTable: "assets.assets", String => AssetInfo);
+
+/// Asset hash => every entity referencing it.
+pub(crate) const ASSET_ENTITIES: MultimapTableDefinition<&str, Uuid> =
+    MultimapTableDefinition::new("assets.asset_entities");
+
+/// Entity => every asset it references.
+pub(crate) const ENTITY_ASSETS: MultimapTableDefinition<Uuid, &str> =
+    MultimapTableDefinition::new("assets.entity_assets");
+
+/// Directory inside the project folder that asset files are stored in, named by their hash.
+pub const ASSETS_DIR: &str = "assets";
+
+/// How long a new asset or file is safe from the garbage collector, so it isn't deleted before
+/// it's attached to anything.
+pub const GC_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// The largest file that can be imported as an asset.
+pub const MAX_ASSET_SIZE: u64 = 1 << 30;
+
+/// Where the asset with `hash` is stored. Files are spread over subdirectories named after the
+/// first two characters of their hash. Fails for anything that isn't a valid asset hash.
+pub fn asset_path(project: impl AsRef<Path>, hash: &str) -> crate::Result<PathBuf> {
+    AssetInfo::validate_hash(hash)?;
+    Ok(project
+        .as_ref()
+        .join(ASSETS_DIR)
+        .join(&hash[..2])
+        .join(hash))
+}
+
+fn mime_type(path: &Path) -> crate::Result<String> {
+    Ok(infer::get_from_path(path)?
+        .map(|kind| kind.mime_type().to_string())
+        .unwrap_or_else(|| "application/octet-stream".to_string()))
+}
+
+fn references_in(txn: &WriteTransaction, hash: &str) -> crate::Result<Vec<Uuid>> {
+    let table = txn.open_multimap_table(ASSET_ENTITIES)?;
+    let mut entities = Vec::new();
+    for entity in table.get(hash)? {
+        entities.push(entity?.value());
+    }
+    Ok(entities)
+}
+
+pub(crate) fn attach_in(txn: &WriteTransaction, entity: Uuid, hash: &str) -> crate::Result<()> {
+    let _ = txn
+        .open_multimap_table_mut(ASSET_ENTITIES)?
+        .insert(hash, entity)?;
+    let _ = txn
+        .open_multimap_table_mut(ENTITY_ASSETS)?
+        .insert(entity, hash)?;
+    Ok(())
+}
+
+pub(crate) fn detach_in(txn: &WriteTransaction, entity: Uuid, hash: &str) -> crate::Result<bool> {
+    let removed = txn
+        .open_multimap_table_mut(ASSET_ENTITIES)?
+        .remove(hash, entity)?;
+    let _ = txn
+        .open_multimap_table_mut(ENTITY_ASSETS)?
+        .remove(entity, hash)?;
+    Ok(removed)
+}
+
+/// Every (entity, asset hash) reference.
+pub(crate) fn references_all_in(txn: &ReadTransaction) -> crate::Result<Vec<(Uuid, String)>> {
+    let table = match txn.open_multimap_table(ENTITY_ASSETS) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut references = Vec::new();
+    for entry in table.iter()? {
+        let (entity, hashes) = entry?;
+        for hash in hashes {
+            references.push((entity.value(), hash?.value().to_string()));
+        }
+    }
+    Ok(references)
+}
+
+/// Replaces every stored asset reference with `references`.
+pub(crate) fn replace_references_in(
+    txn: &WriteTransaction,
+    references: &[(Uuid, String)],
+) -> crate::Result<()> {
+    let _ = txn.clear_multimap_table(ASSET_ENTITIES)?;
+    let _ = txn.clear_multimap_table(ENTITY_ASSETS)?;
+    for (entity, hash) in references {
+        attach_in(txn, *entity, hash)?;
+    }
+    Ok(())
+}
+
+/// Removes every asset reference held by `entity`.
+pub(crate) fn detach_all_in(txn: &WriteTransaction, entity: Uuid) -> crate::Result<()> {
+    let mut entity_assets = txn.open_multimap_table_mut(ENTITY_ASSETS)?;
+    let mut asset_entities = txn.open_multimap_table_mut(ASSET_ENTITIES)?;
+    let mut hashes = Vec::new();
+    for hash in entity_assets.get(entity)? {
+        hashes.push(hash?.value().to_string());
+    }
+    for hash in hashes {
+        let _ = asset_entities.remove(hash.as_str(), entity)?;
+    }
+    let _ = entity_assets.remove_all(entity)?;
+    Ok(())
+}
+
+pub trait AssetsExt<R: Runtime> {
+    fn assets_dir(&self) -> crate::Result<PathBuf>;
+
+    /// Copies a file into the project's asset store. Importing a file that's already stored
+    /// returns the existing asset.
+    fn import_asset(&self, source: PathBuf) -> crate::Result<AssetInfo>;
+    fn get_asset(&self, hash: &str) -> crate::Result<AssetInfo>;
+    fn read_asset(&self, hash: &str) -> crate::Result<Vec<u8>>;
+    fn asset_usage(&self, hash: &str) -> crate::Result<AssetUsage>;
+    fn entity_assets(&self, entity: Uuid) -> crate::Result<Vec<AssetInfo>>;
+    fn attach_asset(&self, entity: Uuid, hash: &str) -> crate::Result<()>;
+    fn detach_asset(&self, entity: Uuid, hash: &str) -> crate::Result<bool>;
+
+    /// Deletes every asset no entity or map references, along with any stray files in the asset
+    /// directory. Assets and files younger than [`GC_GRACE_PERIOD`] are left alone. Returns the
+    /// deleted assets.
+    fn collect_garbage(&self) -> crate::Result<Vec<AssetInfo>>;
+}
+
+impl<R: Runtime, T: Manager<R>> AssetsExt<R> for T {
+    fn assets_dir(&self) -> crate::Result<PathBuf> {
+        Ok(self
+            .get_app_state()
+            .active_project()
+            .path()
+            .ok_or(crate::Error::NoActiveProject)?
+            .join(ASSETS_DIR))
+    }
+
+    fn import_asset(&self, source: PathBuf) -> crate::Result<AssetInfo> {
+        let state = self.get_app_state();
+        let project = state
+            .active_project()
+            .path()
+            .ok_or(crate::Error::NoActiveProject)?;
+        let settings = state
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)?;
+
+        let size = fs::metadata(&source)?.len();
+        if size > MAX_ASSET_SIZE {
+            return Err(crate::Error::validation(
+                "source",
+                format!("Assets cannot be larger than {} MiB", MAX_ASSET_SIZE >> 20),
+            ));
+        }
+        let hash = blake3::Hasher::new()
+            .update_reader(File::open(&source)?)?
+            .finalize()
+            .to_hex()
+            .to_string();
+        if let Some(existing) = self.project_database()?.get::<AssetTable>(&hash)? {
+            return Ok(existing);
+        }
+
+        let target = asset_path(&project, &hash)?;
+        if !target.exists() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Copy next to the target first, so a partial copy never sits under a valid hash.
+            let partial = target.with_extension("partial");
+            let _ = fs::copy(&source, &partial)?;
+            fs::rename(&partial, &target)?;
+        }
+
+        let mime = mime_type(&target)?;
+        let dimensions = if mime.starts_with("image/") {
+            image::image_dimensions(&target)
+                .ok()
+                .map(|(width, height)| AssetDimensions { width, height })
+        } else {
+            None
+        };
+        let asset = AssetInfo {
+            hash: hash.clone(),
+            name: source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| hash.clone()),
+            mime,
+            size: size as u32,
+            dimensions,
+            uploaded_by: PeerIdentity::from(settings.identity().public_key()),
+            uploaded: Utc::now(),
+        };
+        let _ = self
+            .project_database()?
+            .insert::<AssetTable>(&hash, &asset)?;
+        // Undoing past the import would drop the asset, leaving its file to the garbage collector.
+        self.app_handle().reset_history();
+        Ok(asset)
+    }
+
+    fn get_asset(&self, hash: &str) -> crate::Result<AssetInfo> {
+        AssetInfo::validate_hash(hash)?;
+        self.project_database()?
+            .get::<AssetTable>(&hash.to_string())?
+            .ok_or_else(|| crate::Error::not_found("asset", hash))
+    }
+
+    fn read_asset(&self, hash: &str) -> crate::Result<Vec<u8>> {
+        let asset = self.get_asset(hash)?;
+        let project = self
+            .get_app_state()
+            .active_project()
+            .path()
+            .ok_or(crate::Error::NoActiveProject)?;
+        Ok(fs::read(asset_path(project, &asset.hash)?)?)
+    }
+
+    fn asset_usage(&self, hash: &str) -> crate::Result<AssetUsage> {
+        let asset = self.get_asset(hash)?;
+        let entities =
+            self.project_database()?
+                .read_transaction(|txn| -> crate::Result<Vec<Uuid>> {
+                    let table = match txn.open_multimap_table(ASSET_ENTITIES) {
+                        Ok(table) => table,
+                        Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+                        Err(err) => return Err(err.into()),
+                    };
+                    let mut entities = Vec::new();
+                    for entity in table.get(hash)? {
+                        entities.push(entity?.value());
+                    }
+                    Ok(entities)
+                })??;
+        Ok(AssetUsage { asset, entities })
+    }
+
+    fn entity_assets(&self, entity: Uuid) -> crate::Result<Vec<AssetInfo>> {
+        let hashes =
+            self.project_database()?
+                .read_transaction(|txn| -> crate::Result<Vec<String>> {
+                    let table = match txn.open_multimap_table(ENTITY_ASSETS) {
+                        Ok(table) => table,
+                        Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+                        Err(err) => return Err(err.into()),
+                    };
+                    let mut hashes = Vec::new();
+                    for hash in table.get(entity)? {
+                        hashes.push(hash?.value().to_string());
+                    }
+                    Ok(hashes)
+                })??;
+        hashes.iter().map(|hash| self.get_asset(hash)).collect()
+    }
+
+    fn attach_asset(&self, entity: Uuid, hash: &str) -> crate::Result<()> {
+        ensure_hosted::<R>(self)?;
+        let _ = self.get_entity(entity)?;
+        let asset = self.get_asset(hash)?;
+        self.app_handle().undoable(
+            format!("Attach {}", asset.name),
+            HistoryAction::AttachAsset {
+                entity,
+                hash: hash.to_string(),
+            },
+            || {
+                self.project_database()?
+                    .write_transaction(|txn| attach_in(txn, entity, hash))?
+            },
+        )?;
+        self.app_handle()
+            .broadcast_message(SyncMessage::AssetAttached { entity, asset }, None);
+        Ok(())
+    }
+
+    fn detach_asset(&self, entity: Uuid, hash: &str) -> crate::Result<bool> {
+        ensure_hosted::<R>(self)?;
+        // Checked up front, so detaching an unattached asset doesn't leave an empty undo entry.
+        let Some(asset) = self
+            .entity_assets(entity)?
+            .into_iter()
+            .find(|asset| asset.hash == hash)
+        else {
+            return Ok(false);
+        };
+        let removed = self.app_handle().undoable(
+            format!("Detach {}", asset.name),
+            HistoryAction::DetachAsset {
+                entity,
+                hash: hash.to_string(),
+            },
+            || {
+                self.project_database()?
+                    .write_transaction(|txn| detach_in(txn, entity, hash))?
+            },
+        )?;
+        if removed {
+            self.app_handle().broadcast_message(
+                SyncMessage::AssetDetached {
+                    entity,
+                    hash: hash.to_string(),
+                },
+                None,
+            );
+        }
+        Ok(removed)
+    }
+
+    fn collect_garbage(&self) -> crate::Result<Vec<AssetInfo>> {
+        ensure_hosted::<R>(self)?;
+        let project = self
+            .get_app_state()
+            .active_project()
+            .path()
+            .ok_or(crate::Error::NoActiveProject)?;
+
+        let grace = chrono::Duration::from_std(GC_GRACE_PERIOD).unwrap_or_default();
+        let (removed, kept) = self.project_database()?.write_transaction(
+            |txn| -> crate::Result<(Vec<AssetInfo>, HashSet<String>)> {
+                let assets = {
+                    let table = txn.open_table(AssetTable::definition())?;
+                    RecordTableExt::<AssetTable>::range_records(&table, ..)?
+                };
+                let map_images = maps::images_in(txn)?;
+                let mut table = txn.open_table(AssetTable::definition())?;
+                let mut removed = Vec::new();
+                let mut kept = HashSet::new();
+                for (hash, asset) in assets {
+                    // Fresh imports are usually about to be attached.
+                    let unused = references_in(txn, &hash)?.is_empty()
+                        && !map_images.contains(&hash)
+                        && Utc::now() - asset.uploaded >= grace;
+                    if unused {
+                        let _ = remove_record::<AssetTable>(&mut table, &hash)?;
+                        removed.push(asset);
+                    } else {
+                        let _ = kept.insert(hash);
+                    }
+                }
+                Ok((removed, kept))
+            },
+        )??;
+        if !removed.is_empty() {
+            // Undoing past this would bring back assets whose files are about to be deleted.
+            self.app_handle().reset_history();
+        }
+
+        // Files written recently may belong to an import or transfer that hasn't been recorded
+        // yet, and partial files of kept assets let interrupted transfers resume.
+        let recent = |path: &Path| -> crate::Result<bool> {
+            let modified = fs::metadata(path)?.modified()?;
+            Ok(modified.elapsed().unwrap_or_default() < GC_GRACE_PERIOD)
+        };
+        let root = project.join(ASSETS_DIR);
+        if root.exists() {
+            for shard in fs::read_dir(&root)? {
+                let shard = shard?.path();
+                if !shard.is_dir() {
+                    continue;
+                }
+                for file in fs::read_dir(&shard)? {
+                    let file = file?.path();
+                    let stored = file
+                        .file_stem()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    if !kept.contains(&stored) && !recent(&file)? {
+                        fs::remove_file(&file)?;
+                    }
+                }
+                if fs::read_dir(&shard)?.next().is_none() && !recent(&shard)? {
+                    fs::remove_dir(&shard)?;
+                }
+            }
+        }
+
+        if !removed.is_empty() {
+            self.app_handle().broadcast_message(
+                SyncMessage::AssetsRemoved {
+                    hashes: removed.iter().map(|asset| asset.hash.clone()).collect(),
+                },
+                None,
+            );
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,70 @@
+use tauri::{Manager, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{sync::update_shared_settings, ApplicationExt},
+    types::{Calendar, CalendarDate, DescribedDate},
+};
+
+pub trait CalendarsExt<R: Runtime> {
+    fn list_calendars(&self) -> crate::Result<Vec<Calendar>>;
+    fn get_calendar(&self, id: Uuid) -> crate::Result<Calendar>;
+    fn create_calendar(&self, calendar: Calendar) -> crate::Result<Calendar>;
+    fn update_calendar(&self, calendar: Calendar) -> crate::Result<Calendar>;
+    fn delete_calendar(&self, id: Uuid) -> crate::Result<Calendar>;
+
+    /// Converts a date in a calendar to an absolute day.
+    fn date_to_days(&self, calendar: Uuid, date: CalendarDate) -> crate::Result<i32>;
+
+    /// Describes an absolute day in a calendar.
+    fn describe_day(&self, calendar: Uuid, days: i32) -> crate::Result<DescribedDate>;
+}
+
+impl<R: Runtime, T: Manager<R>> CalendarsExt<R> for T {
+    fn list_calendars(&self) -> crate::Result<Vec<Calendar>> {
+        let settings = self
+            .get_app_state()
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)?;
+        Ok(settings.calendars().into_values().collect())
+    }
+
+    fn get_calendar(&self, id: Uuid) -> crate::Result<Calendar> {
+        self.get_app_state()
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)?
+            .calendar(id)
+            .ok_or_else(|| crate::Error::not_found("calendar", id))
+    }
+
+    fn create_calendar(&self, calendar: Calendar) -> crate::Result<Calendar> {
+        let calendar = Calendar {
+            id: Uuid::now_v7(),
+            ..calendar
+        };
+        calendar.validate()?;
+        let _ = update_shared_settings(self, |settings| settings.with_calendar(calendar.clone()))?;
+        Ok(calendar)
+    }
+
+    fn update_calendar(&self, calendar: Calendar) -> crate::Result<Calendar> {
+        let _ = self.get_calendar(calendar.id)?;
+        calendar.validate()?;
+        let _ = update_shared_settings(self, |settings| settings.with_calendar(calendar.clone()))?;
+        Ok(calendar)
+    }
+
+    fn delete_calendar(&self, id: Uuid) -> crate::Result<Calendar> {
+        let calendar = self.get_calendar(id)?;
+        let _ = update_shared_settings(self, |settings| settings.remove_calendar(id))?;
+        Ok(calendar)
+    }
+
+    fn date_to_days(&self, calendar: Uuid, date: CalendarDate) -> crate::Result<i32> {
+        self.get_calendar(calendar)?.to_days(date)
+    }
+
+    fn describe_day(&self, calendar: Uuid, days: i32) -> crate::Result<DescribedDate> {
+        self.get_calendar(calendar)?.describe(days)
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use parking_lot::RwLock;
+use tauri::{Manager, Runtime};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::{
+    extensions::{Database, TableName},
+    procedures::{AppEvent, AppEventExt},
+    types::{ChangeSubscription, TableChange},
+};
+
+/// Tables noted as changed, with the key of the changed record if it's known.
+type NotedChanges = Vec<(TableName, Option<String>)>;
+
+thread_local! {
+    /// Changes made so far by the write transaction running on this thread, if any.
+    static PENDING: RefCell<Option<NotedChanges>> = const { RefCell::new(None) };
+}
+
+/// Notes that a record of `table` changed, so subscribers can be told once the running write
+/// transaction commits. `None` marks the whole table as changed, which overrides any keys noted
+/// for it. Does nothing outside of a write transaction.
+pub(crate) fn note_change(table: TableName, key: Option<String>) {
+    PENDING.with_borrow_mut(|pending| {
+        if let Some(pending) = pending.as_mut() {
+            pending.push((table, key));
+        }
+    });
+}
+
+/// Collects the changes noted on this thread while it's alive.
+pub(crate) struct ChangeCollector(());
+
+impl ChangeCollector {
+    pub(crate) fn begin() -> Self {
+        PENDING.with_borrow_mut(|pending| *pending = Some(Vec::new()));
+        Self(())
+    }
+
+    /// Stops collecting, grouping the changes noted so far by table. Tables noted as changed as a
+    /// whole are reported without keys.
+    pub(crate) fn finish(self) -> Vec<TableChange> {
+        let noted = PENDING
+            .with_borrow_mut(|pending| pending.take())
+            .unwrap_or_default();
+        let whole = noted
+            .iter()
+            .filter(|(_, key)| key.is_none())
+            .map(|(table, _)| table.clone())
+            .collect::<HashSet<_>>();
+        let mut changes: Vec<TableChange> = Vec::new();
+        for (table, key) in noted {
+            let index = match changes.iter().position(|change| {
+                change.table == table.name() && change.multimap == table.is_multimap()
+            }) {
+                Some(index) => index,
+                None => {
+                    changes.push(TableChange {
+                        table: table.name(),
+                        multimap: table.is_multimap(),
+                        keys: Vec::new(),
+                    });
+                    changes.len() - 1
+                }
+            };
+            if let Some(key) = key.filter(|_| !whole.contains(&table)) {
+                if !changes[index].keys.contains(&key) {
+                    changes[index].keys.push(key);
+                }
+            }
+        }
+        changes
+    }
+}
+
+impl Drop for ChangeCollector {
+    fn drop(&mut self) {
+        PENDING.with_borrow_mut(|pending| *pending = None);
+    }
+}
+
+pub type ChangeFeedState = Arc<RwLock<HashMap<Uuid, ChangeSubscription>>>;
+
+pub trait ChangeFeedExt<R: Runtime> {
+    fn change_feed_state(&self) -> ChangeFeedState;
+
+    /// Subscribes the UI to changes in `tables` of `database`, which are emitted as
+    /// [`AppEvent::TablesChanged`] until unsubscribed.
+    fn subscribe_changes(
+        &self,
+        database: Option<String>,
+        tables: Vec<String>,
+    ) -> crate::Result<ChangeSubscription>;
+    fn unsubscribe_changes(&self, id: Uuid) -> bool;
+
+    /// Forwards every change committed to `database` to the matching subscriptions, for as long as
+    /// the database stays open.
+    fn watch_changes(&self, database: &Database);
+}
+
+impl<R: Runtime, T: Manager<R>> ChangeFeedExt<R> for T {
+    fn change_feed_state(&self) -> ChangeFeedState {
+        if let Some(existing) = self.try_state::<ChangeFeedState>() {
+            existing.inner().clone()
+        } else {
+            self.manage::<ChangeFeedState>(Arc::new(RwLock::new(HashMap::new())));
+            self.state::<ChangeFeedState>().inner().clone()
+        }
+    }
+
+    fn subscribe_changes(
+        &self,
+        database: Option<String>,
+        tables: Vec<String>,
+    ) -> crate::Result<ChangeSubscription> {
+        if let Some(index) = tables.iter().position(|table| table.trim().is_empty()) {
+            return Err(crate::Error::validation(
+                format!("tables.{index}"),
+                "Table name cannot be empty",
+            ));
+        }
+        let subscription = ChangeSubscription {
+            id: Uuid::now_v7(),
+            database,
+            tables,
+        };
+        let _ = self
+            .change_feed_state()
+            .write()
+            .insert(subscription.id, subscription.clone());
+        Ok(subscription)
+    }
+
+    fn unsubscribe_changes(&self, id: Uuid) -> bool {
+        self.change_feed_state().write().remove(&id).is_some()
+    }
+
+    fn watch_changes(&self, database: &Database) {
+        let app = self.app_handle().clone();
+        let name = database.name();
+        let mut receiver = database.subscribe();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                let changes = match receiver.recv().await {
+                    Ok(changes) => changes,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Change feed of {name} skipped {skipped} commits");
+                        let subscriptions = app.change_feed_state().read().clone();
+                        for subscription in subscriptions.into_values().filter(|subscription| {
+                            subscription.database.as_ref().is_none_or(|db| *db == name)
+                        }) {
+                            let _ = app.emit_event(AppEvent::ChangesMissed {
+                                subscription: subscription.id,
+                                database: name.clone(),
+                            });
+                        }
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let subscriptions = app.change_feed_state().read().clone();
+                for subscription in subscriptions.into_values() {
+                    let matching = subscription.matching(&name, &changes);
+                    if !matching.is_empty() {
+                        let _ = app.emit_event(AppEvent::TablesChanged {
+                            subscription: subscription.id,
+                            database: name.clone(),
+                            changes: matching,
+                        });
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_noted_keys_by_table() {
+        let collector = ChangeCollector::begin();
+        note_change(TableName::unique("a"), Some("1".to_owned()));
+        note_change(TableName::multimap("a"), Some("2".to_owned()));
+        note_change(TableName::unique("a"), Some("1".to_owned()));
+        note_change(TableName::unique("a"), Some("3".to_owned()));
+        let changes = collector.finish();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].keys, vec!["1", "3"]);
+        assert!(!changes[0].multimap);
+        assert_eq!(changes[1].keys, vec!["2"]);
+        assert!(changes[1].multimap);
+    }
+
+    #[test]
+    fn whole_table_changes_drop_keys() {
+        let collector = ChangeCollector::begin();
+        note_change(TableName::unique("a"), Some("1".to_owned()));
+        note_change(TableName::unique("a"), None);
+        note_change(TableName::unique("a"), Some("2".to_owned()));
+        note_change(TableName::unique("b"), Some("3".to_owned()));
+        let changes = collector.finish();
+        assert_eq!(changes[0].table, "a");
+        assert!(changes[0].keys.is_empty());
+        assert_eq!(changes[1].keys, vec!["3"]);
+    }
+
+    #[test]
+    fn ignores_changes_outside_of_a_transaction() {
+        note_change(TableName::unique("a"), None);
+        assert!(ChangeCollector::begin().finish().is_empty());
+    }
+}
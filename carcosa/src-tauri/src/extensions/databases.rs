@@ -2,17 +2,34 @@ use std::{
     collections::HashMap,
     fmt::Display,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
+use chrono::Utc;
 use getset::CloneGetters;
-use parking_lot::RwLock;
+use parking_lot::{ReentrantMutex, RwLock};
 use redb::{
-    Key, MultimapTable, MultimapTableDefinition, MultimapTableHandle, ReadOnlyMultimapTable,
-    ReadOnlyTable, ReadableDatabase, Table, TableDefinition, TableHandle, Value,
+    backends::InMemoryBackend, Durability, Key, MultimapTable, MultimapTableDefinition,
+    MultimapTableHandle, ReadOnlyMultimapTable, ReadOnlyTable, ReadTransaction, ReadableDatabase,
+    ReadableMultimapTable, ReadableTable, ReadableTableMetadata, Table, TableDefinition,
+    TableError, TableHandle, Value, WriteTransaction,
 };
 use serde::{Deserialize, Serialize};
 use tauri::{Manager, Runtime};
+use tokio::sync::broadcast;
+
+use crate::{
+    extensions::{
+        changes::{note_change, ChangeCollector},
+        ChangeFeedExt, Migration,
+    },
+    procedures::{AppEvent, AppEventExt},
+    types::{TableChange, TableRows, PROJECT_DATABASE},
+    MetaError,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 #[serde(try_from = "String", into = "String")]
@@ -72,20 +89,136 @@ impl TableName {
     }
 
     pub fn is_unique(&self) -> bool {
-        if let Self::Unique { .. } = self.clone() {
-            true
-        } else {
-            false
-        }
+        matches!(self, Self::Unique { .. })
     }
 
     pub fn is_multimap(&self) -> bool {
-        if let Self::Multimap { .. } = self.clone() {
-            true
-        } else {
-            false
+        matches!(self, Self::Multimap { .. })
+    }
+}
+
+/// Copies every row of a table from one database into another, returning how many rows were
+/// copied. A table missing from `from` copies nothing.
+pub(crate) fn copy_table_in<K: Key + 'static, V: Value + 'static>(
+    from: &ReadTransaction,
+    to: &WriteTransaction,
+    definition: TableDefinition<K, V>,
+) -> crate::Result<u64> {
+    let source = match from.open_table(definition) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+    let mut target = to.open_table(definition)?;
+    let mut copied = 0;
+    for entry in source.iter()? {
+        let (key, value) = entry?;
+        let _ = target.insert(key.value(), value.value())?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// [`copy_table_in`] for multimap tables. Every key/value pair counts as a row.
+pub(crate) fn copy_multimap_table_in<K: Key + 'static, V: Key + 'static>(
+    from: &ReadTransaction,
+    to: &WriteTransaction,
+    definition: MultimapTableDefinition<K, V>,
+) -> crate::Result<u64> {
+    let source = match from.open_multimap_table(definition) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+    let mut target = to.open_multimap_table(definition)?;
+    let mut copied = 0;
+    for entry in source.iter()? {
+        let (key, values) = entry?;
+        for value in values {
+            let _ = target.insert(key.value(), value?.value())?;
+            copied += 1;
         }
     }
+    Ok(copied)
+}
+
+/// Opens and deletes tables of a raw write transaction for the `*_in` helpers, noting each one as
+/// changed as a whole. Writes that don't go through [`insert_record`](crate::extensions::insert_record)
+/// or [`remove_record`](crate::extensions::remove_record) are reported this way, unless the helper
+/// notes the changed keys itself.
+pub(crate) trait WriteTransactionExt {
+    fn open_table_mut<K: Key + 'static, V: Value + 'static>(
+        &self,
+        definition: TableDefinition<K, V>,
+    ) -> Result<Table<'_, K, V>, TableError>;
+
+    fn open_multimap_table_mut<K: Key + 'static, V: Key + 'static>(
+        &self,
+        definition: MultimapTableDefinition<K, V>,
+    ) -> Result<MultimapTable<'_, K, V>, TableError>;
+
+    /// Deletes a table along with every row in it, returning whether it existed.
+    fn clear_table<K: Key + 'static, V: Value + 'static>(
+        &self,
+        definition: TableDefinition<K, V>,
+    ) -> Result<bool, TableError>;
+
+    /// [`WriteTransactionExt::clear_table`] for multimap tables.
+    fn clear_multimap_table<K: Key + 'static, V: Key + 'static>(
+        &self,
+        definition: MultimapTableDefinition<K, V>,
+    ) -> Result<bool, TableError>;
+}
+
+impl WriteTransactionExt for WriteTransaction {
+    fn open_table_mut<K: Key + 'static, V: Value + 'static>(
+        &self,
+        definition: TableDefinition<K, V>,
+    ) -> Result<Table<'_, K, V>, TableError> {
+        let table = self.open_table(definition)?;
+        note_change(TableName::unique(definition.name()), None);
+        Ok(table)
+    }
+
+    fn open_multimap_table_mut<K: Key + 'static, V: Key + 'static>(
+        &self,
+        definition: MultimapTableDefinition<K, V>,
+    ) -> Result<MultimapTable<'_, K, V>, TableError> {
+        let table = self.open_multimap_table(definition)?;
+        note_change(TableName::multimap(definition.name()), None);
+        Ok(table)
+    }
+
+    fn clear_table<K: Key + 'static, V: Value + 'static>(
+        &self,
+        definition: TableDefinition<K, V>,
+    ) -> Result<bool, TableError> {
+        let deleted = self.delete_table(definition)?;
+        if deleted {
+            note_change(TableName::unique(definition.name()), None);
+        }
+        Ok(deleted)
+    }
+
+    fn clear_multimap_table<K: Key + 'static, V: Key + 'static>(
+        &self,
+        definition: MultimapTableDefinition<K, V>,
+    ) -> Result<bool, TableError> {
+        let deleted = self.delete_multimap_table(definition)?;
+        if deleted {
+            note_change(TableName::multimap(definition.name()), None);
+        }
+        Ok(deleted)
+    }
+}
+
+/// Flags a blocking database task as cancelled once the future waiting on it is dropped.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
 }
 
 #[derive(Clone, Debug, CloneGetters)]
@@ -97,6 +230,13 @@ pub struct Database {
     path: PathBuf,
 
     database: Arc<RwLock<redb::Database>>,
+
+    /// Held by every write for as long as it runs. It can be taken again by the thread already
+    /// holding it, so [`Database::exclusive`] can keep other writers out of a run of writes.
+    writer: Arc<ReentrantMutex<()>>,
+
+    /// Publishes what every committed write changed.
+    changes: broadcast::Sender<Vec<TableChange>>,
 }
 
 impl Database {
@@ -108,9 +248,41 @@ impl Database {
             name,
             path,
             database: Arc::new(RwLock::new(db)),
+            writer: Arc::new(ReentrantMutex::new(())),
+            changes: broadcast::channel(256).0,
         })
     }
 
+    /// Receives the tables and keys changed by every write committed from now on. Only records
+    /// written through [`insert_record`](crate::extensions::insert_record),
+    /// [`remove_record`](crate::extensions::remove_record) or explicitly noted by the `*_in`
+    /// helpers are reported by key. Tables opened through [`Database::write`],
+    /// [`Transaction::table`] or [`WriteTransactionExt`] are reported as changed as a whole.
+    pub fn subscribe(&self) -> broadcast::Receiver<Vec<TableChange>> {
+        self.changes.subscribe()
+    }
+
+    fn publish(&self, changes: Vec<TableChange>) {
+        if !changes.is_empty() {
+            let _ = self.changes.send(changes);
+        }
+    }
+
+    /// Publishes every table as changed, for writes that replace the whole file's contents.
+    fn publish_all(&self) -> crate::Result<()> {
+        let changes = self
+            .list_tables()?
+            .into_iter()
+            .map(|table| TableChange {
+                table: table.name(),
+                multimap: table.is_multimap(),
+                keys: Vec::new(),
+            })
+            .collect();
+        self.publish(changes);
+        Ok(())
+    }
+
     pub fn list_tables(&self) -> crate::Result<Vec<TableName>> {
         let lock = self.database.read();
         let txn = lock.begin_read()?;
@@ -155,17 +327,22 @@ impl Database {
         Ok(result)
     }
 
+    /// Writes to a single raw table. Subscribers are told the whole table changed, since the
+    /// keys written aren't known.
     pub fn write<K: Key + 'static, V: Value + 'static, Output, Error: std::error::Error>(
         &self,
         table: impl Into<String>,
         transaction: impl FnOnce(Table<K, V>) -> Result<Output, Error>,
     ) -> crate::Result<Result<Output, Error>> {
+        let _writer = self.writer.lock();
         let lock = self.database.write();
         let txn = lock.begin_write()?;
-        let table = txn.open_table::<K, V>(TableDefinition::new(table.into().as_str()))?;
+        let collector = ChangeCollector::begin();
+        let table = txn.open_table_mut::<K, V>(TableDefinition::new(table.into().as_str()))?;
         match transaction(table) {
             Ok(out) => {
                 txn.commit()?;
+                self.publish(collector.finish());
                 Ok(Ok(out))
             }
             Err(err) => {
@@ -175,18 +352,235 @@ impl Database {
         }
     }
 
+    pub(crate) fn read_transaction<Output>(
+        &self,
+        transaction: impl FnOnce(&ReadTransaction) -> Output,
+    ) -> crate::Result<Output> {
+        let lock = self.database.read();
+        let txn = lock.begin_read()?;
+        let result = transaction(&txn);
+        txn.close()?;
+        Ok(result)
+    }
+
+    pub(crate) fn write_transaction<Output, Error: std::error::Error>(
+        &self,
+        transaction: impl FnOnce(&WriteTransaction) -> Result<Output, Error>,
+    ) -> crate::Result<Result<Output, Error>> {
+        self.transaction().run(&AtomicBool::new(false), transaction)
+    }
+
+    /// [`Database::read_transaction`] on a blocking thread, so long reads don't stall the async
+    /// runtime. Dropping the returned future before the read has started skips it.
+    pub(crate) async fn read_transaction_async<Output: Send + 'static>(
+        &self,
+        transaction: impl FnOnce(&ReadTransaction) -> Output + Send + 'static,
+    ) -> crate::Result<Output> {
+        let database = self.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let _guard = CancelOnDrop(cancelled.clone());
+        tauri::async_runtime::spawn_blocking(move || {
+            if cancelled.load(Ordering::Acquire) {
+                return Err(crate::Error::Cancelled);
+            }
+            database.read_transaction(transaction)
+        })
+        .await?
+    }
+
+    /// [`Database::write_transaction`] on a blocking thread, so large writes don't stall the async
+    /// runtime. Dropping the returned future cancels the write: it is skipped if it hasn't started
+    /// yet, and rolled back rather than committed if it has.
+    pub(crate) async fn write_transaction_async<
+        Output: Send + 'static,
+        Error: std::error::Error + Send + 'static,
+    >(
+        &self,
+        transaction: impl FnOnce(&WriteTransaction) -> Result<Output, Error> + Send + 'static,
+    ) -> crate::Result<Result<Output, Error>> {
+        self.transaction().spawn(transaction).await
+    }
+
+    /// Runs `f` while no other thread can write to the database. Writes `f` makes itself, on the
+    /// same thread, go ahead as usual; anything it waits on that writes from another thread
+    /// deadlocks.
+    pub(crate) fn exclusive<Output>(&self, f: impl FnOnce() -> Output) -> Output {
+        let _writer = self.writer.lock();
+        f()
+    }
+
+    /// Records the current state of the database as a persistent savepoint, returning its ID.
+    pub(crate) fn create_savepoint(&self) -> crate::Result<u64> {
+        let _writer = self.writer.lock();
+        let lock = self.database.write();
+        let txn = lock.begin_write()?;
+        let id = txn.persistent_savepoint()?;
+        txn.commit()?;
+        Ok(id)
+    }
+
+    /// Rolls the whole database back to a savepoint, then deletes it. Every savepoint created
+    /// after it is deleted too.
+    pub(crate) fn restore_savepoint(&self, id: u64) -> crate::Result<()> {
+        let _writer = self.writer.lock();
+        let lock = self.database.write();
+        let mut txn = lock.begin_write()?;
+        let savepoint = txn.get_persistent_savepoint(id)?;
+        txn.restore_savepoint(&savepoint)?;
+        drop(savepoint);
+        let _ = txn.delete_persistent_savepoint(id)?;
+        txn.commit()?;
+        drop(lock);
+        self.publish_all()
+    }
+
+    pub(crate) fn delete_savepoint(&self, id: u64) -> crate::Result<bool> {
+        let _writer = self.writer.lock();
+        let lock = self.database.write();
+        let txn = lock.begin_write()?;
+        let deleted = txn.delete_persistent_savepoint(id)?;
+        txn.commit()?;
+        Ok(deleted)
+    }
+
+    /// Deletes every persistent savepoint. Pages freed after a savepoint can't be reused while it
+    /// exists, so savepoints left over from an earlier session are dropped when a project opens.
+    pub(crate) fn clear_savepoints(&self) -> crate::Result<()> {
+        let _writer = self.writer.lock();
+        let lock = self.database.write();
+        let txn = lock.begin_write()?;
+        for id in txn.list_persistent_savepoints()?.collect::<Vec<_>>() {
+            let _ = txn.delete_persistent_savepoint(id)?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Size of the database file, in bytes.
+    pub fn file_size(&self) -> crate::Result<u64> {
+        Ok(std::fs::metadata(&self.path)?.len())
+    }
+
+    /// Number of rows in every table. Each key/value pair of a multimap table counts as a row.
+    pub fn table_rows(&self) -> crate::Result<Vec<TableRows>> {
+        let lock = self.database.read();
+        let txn = lock.begin_read()?;
+        let mut result = Vec::new();
+        for table in txn.list_tables()? {
+            let name = table.name().to_string();
+            let rows = txn.open_untyped_table(table)?.len()?;
+            result.push(TableRows {
+                table: name,
+                multimap: false,
+                rows: u32::try_from(rows).unwrap_or(u32::MAX),
+            });
+        }
+        for table in txn.list_multimap_tables()? {
+            let name = table.name().to_string();
+            let rows = txn.open_untyped_multimap_table(table)?.len()?;
+            result.push(TableRows {
+                table: name,
+                multimap: true,
+                rows: u32::try_from(rows).unwrap_or(u32::MAX),
+            });
+        }
+        txn.close()?;
+        Ok(result)
+    }
+
+    /// Shrinks the database file by reclaiming free pages. Fails while any persistent savepoint
+    /// exists. Returns `false` if there was nothing left to reclaim.
+    pub(crate) fn compact(&self) -> crate::Result<bool> {
+        let _writer = self.writer.lock();
+        Ok(self.database.write().compact()?)
+    }
+
+    /// Runs redb's integrity check, which repairs what it can in place. Returns `false` if
+    /// problems were found and repaired, and a corruption error if they couldn't be.
+    pub(crate) fn check_integrity(&self) -> crate::Result<bool> {
+        let _writer = self.writer.lock();
+        Ok(self.database.write().check_integrity()?)
+    }
+
+    /// Copies whatever `salvage` can read into a fresh file and swaps it in for this database.
+    /// The original file is kept alongside it as a backup, whose path is returned.
+    pub(crate) fn rebuild<Output>(
+        &self,
+        salvage: impl FnOnce(&ReadTransaction, &WriteTransaction) -> crate::Result<Output>,
+    ) -> crate::Result<(PathBuf, Output)> {
+        let _writer = self.writer.lock();
+        let mut lock = self.database.write();
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| format!("{}.redb", self.name));
+        let rebuilt = self.path.with_file_name(format!("{file_name}.rebuild"));
+        let backup = self.path.with_file_name(format!(
+            "{file_name}.{}.bak",
+            Utc::now().format("%Y%m%d%H%M%S")
+        ));
+        if rebuilt.exists() {
+            std::fs::remove_file(&rebuilt)?;
+        }
+
+        let salvaged = (|| {
+            let fresh = redb::Database::create(&rebuilt)?;
+            let from = lock.begin_read()?;
+            let to = fresh.begin_write()?;
+            let output = salvage(&from, &to)?;
+            to.commit()?;
+            Ok::<_, crate::Error>(output)
+        })();
+        let output = match salvaged {
+            Ok(output) => output,
+            Err(err) => {
+                let _ = std::fs::remove_file(&rebuilt);
+                return Err(err);
+            }
+        };
+
+        // The original has to be closed before it's moved, since not every platform can rename
+        // an open file.
+        let placeholder = redb::Database::builder().create_with_backend(InMemoryBackend::new())?;
+        drop(std::mem::replace(&mut *lock, placeholder));
+        let swapped = std::fs::rename(&self.path, &backup).and_then(|_| {
+            std::fs::rename(&rebuilt, &self.path).inspect_err(|_| {
+                let _ = std::fs::rename(&backup, &self.path);
+            })
+        });
+        *lock = redb::Database::create(&self.path)?;
+        drop(lock);
+        swapped?;
+        self.publish_all()?;
+        Ok((backup, output))
+    }
+
+    /// Starts building a write transaction that can open any number of tables.
+    pub fn transaction(&self) -> TransactionBuilder {
+        TransactionBuilder {
+            database: self.clone(),
+            durability: None,
+            two_phase_commit: false,
+        }
+    }
+
+    /// [`Database::write`] for multimap tables.
     pub fn write_multimap<K: Key + 'static, V: Key + 'static, Output, Error: std::error::Error>(
         &self,
         table: impl Into<String>,
         transaction: impl FnOnce(MultimapTable<K, V>) -> Result<Output, Error>,
     ) -> crate::Result<Result<Output, Error>> {
+        let _writer = self.writer.lock();
         let lock = self.database.write();
         let txn = lock.begin_write()?;
-        let table =
-            txn.open_multimap_table::<K, V>(MultimapTableDefinition::new(table.into().as_str()))?;
+        let collector = ChangeCollector::begin();
+        let table = txn
+            .open_multimap_table_mut::<K, V>(MultimapTableDefinition::new(table.into().as_str()))?;
         match transaction(table) {
             Ok(out) => {
                 txn.commit()?;
+                self.publish(collector.finish());
                 Ok(Ok(out))
             }
             Err(err) => {
@@ -197,6 +591,154 @@ impl Database {
     }
 }
 
+/// Configures a write transaction spanning any number of tables, which are committed or aborted
+/// together. Created by [`Database::transaction`].
+#[derive(Clone, Debug)]
+pub struct TransactionBuilder {
+    database: Database,
+    durability: Option<Durability>,
+    two_phase_commit: bool,
+}
+
+impl TransactionBuilder {
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.durability = Some(durability);
+        self
+    }
+
+    pub fn two_phase_commit(mut self, enabled: bool) -> Self {
+        self.two_phase_commit = enabled;
+        self
+    }
+
+    /// Runs `transaction`, committing every table it opened if it returns `Ok` and aborting them
+    /// all if it returns `Err`.
+    pub fn write<Output, Error: std::error::Error>(
+        self,
+        transaction: impl FnOnce(&Transaction<'_>) -> Result<Output, Error>,
+    ) -> crate::Result<Result<Output, Error>> {
+        self.run(&AtomicBool::new(false), |txn| {
+            transaction(&Transaction { txn })
+        })
+    }
+
+    /// [`TransactionBuilder::write`] on a blocking thread. Dropping the returned future cancels
+    /// the write: it is skipped if it hasn't started yet, and rolled back rather than committed
+    /// if it has.
+    pub async fn write_async<Output: Send + 'static, Error: std::error::Error + Send + 'static>(
+        self,
+        transaction: impl FnOnce(&Transaction<'_>) -> Result<Output, Error> + Send + 'static,
+    ) -> crate::Result<Result<Output, Error>> {
+        self.spawn(move |txn| transaction(&Transaction { txn }))
+            .await
+    }
+
+    async fn spawn<Output: Send + 'static, Error: std::error::Error + Send + 'static>(
+        self,
+        transaction: impl FnOnce(&WriteTransaction) -> Result<Output, Error> + Send + 'static,
+    ) -> crate::Result<Result<Output, Error>> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let _guard = CancelOnDrop(cancelled.clone());
+        tauri::async_runtime::spawn_blocking(move || self.run(&cancelled, transaction)).await?
+    }
+
+    /// Runs a write transaction, rolling it back instead of committing it if `cancelled` was set
+    /// in the meantime.
+    fn run<Output, Error: std::error::Error>(
+        &self,
+        cancelled: &AtomicBool,
+        transaction: impl FnOnce(&WriteTransaction) -> Result<Output, Error>,
+    ) -> crate::Result<Result<Output, Error>> {
+        if cancelled.load(Ordering::Acquire) {
+            return Err(crate::Error::Cancelled);
+        }
+        let _writer = self.database.writer.lock();
+        let lock = self.database.database.write();
+        let mut txn = lock.begin_write()?;
+        if let Some(durability) = self.durability {
+            txn.set_durability(durability)?;
+        }
+        txn.set_two_phase_commit(self.two_phase_commit);
+        let collector = ChangeCollector::begin();
+        match transaction(&txn) {
+            Ok(_) if cancelled.load(Ordering::Acquire) => {
+                txn.abort()?;
+                Err(crate::Error::Cancelled)
+            }
+            Ok(out) => {
+                txn.commit()?;
+                self.database.publish(collector.finish());
+                Ok(Ok(out))
+            }
+            Err(err) => {
+                txn.abort()?;
+                Ok(Err(err))
+            }
+        }
+    }
+}
+
+/// Handed to [`TransactionBuilder::write`], opening tables by name inside a single write
+/// transaction.
+pub struct Transaction<'txn> {
+    txn: &'txn WriteTransaction,
+}
+
+impl<'txn> Transaction<'txn> {
+    /// Opens a raw table. Subscribers are told the whole table changed, since the keys written
+    /// aren't known.
+    pub fn table<K: Key + 'static, V: Value + 'static>(
+        &self,
+        name: &TableName,
+    ) -> crate::Result<Table<'txn, K, V>> {
+        match name {
+            TableName::Unique { name } => {
+                Ok(self.txn.open_table_mut(TableDefinition::new(name))?)
+            }
+            TableName::Multimap { .. } => Err(crate::Error::validation(
+                "table",
+                format!("{name} is not a unique table"),
+            )),
+        }
+    }
+
+    pub fn multimap_table<K: Key + 'static, V: Key + 'static>(
+        &self,
+        name: &TableName,
+    ) -> crate::Result<MultimapTable<'txn, K, V>> {
+        match name {
+            TableName::Multimap { name } => Ok(self
+                .txn
+                .open_multimap_table_mut(MultimapTableDefinition::new(name))?),
+            TableName::Unique { .. } => Err(crate::Error::validation(
+                "table",
+                format!("{name} is not a multimap table"),
+            )),
+        }
+    }
+
+    /// Deletes a table of either kind, returning whether it existed.
+    pub fn delete_table(&self, name: &TableName) -> crate::Result<bool> {
+        let deleted = match name {
+            TableName::Unique { name } => self
+                .txn
+                .delete_table(TableDefinition::<&[u8], &[u8]>::new(name))?,
+            TableName::Multimap { name } => self
+                .txn
+                .delete_multimap_table(MultimapTableDefinition::<&[u8], &[u8]>::new(name))?,
+        };
+        if deleted {
+            note_change(name.clone(), None);
+        }
+        Ok(deleted)
+    }
+
+    /// The underlying redb transaction, for the `*_in` helpers that take one directly.
+    pub(crate) fn inner(&self) -> &'txn WriteTransaction {
+        self.txn
+    }
+}
+
 pub type DbState = Arc<RwLock<HashMap<String, Database>>>;
 
 pub trait DatabasesExt<R: Runtime> {
@@ -205,10 +747,12 @@ pub trait DatabasesExt<R: Runtime> {
         &self,
         name: impl Into<String>,
         path: impl AsRef<Path>,
+        migrations: &[Migration],
     ) -> crate::Result<Database>;
     fn get_database(&self, name: impl Into<String>) -> Option<Database>;
-    fn close_database(&self, name: impl Into<String>) -> ();
-    fn clear_databases(&self) -> ();
+    fn project_database(&self) -> crate::Result<Database>;
+    fn close_database(&self, name: impl Into<String>);
+    fn clear_databases(&self);
     fn list_databases(&self) -> Vec<String>;
 }
 
@@ -225,6 +769,7 @@ impl<R: Runtime, T: Manager<R>> DatabasesExt<R> for T {
         &self,
         name: impl Into<String>,
         path: impl AsRef<Path>,
+        migrations: &[Migration],
     ) -> crate::Result<Database> {
         let name = name.into();
         let path = path.as_ref().to_path_buf();
@@ -234,6 +779,23 @@ impl<R: Runtime, T: Manager<R>> DatabasesExt<R> for T {
             Ok(existing)
         } else {
             let opened = Database::open(name.clone(), path.clone())?;
+            let migrated = opened.migrate(migrations, |migration, step, total| {
+                let _ = self.emit_event(AppEvent::MigrationProgress {
+                    database: name.clone(),
+                    version: migration.version,
+                    description: migration.description.to_string(),
+                    step,
+                    total,
+                });
+            });
+            if let Err(err) = migrated {
+                let _ = self.emit_event(AppEvent::MigrationFailed {
+                    database: name.clone(),
+                    error: MetaError::from(&err),
+                });
+                return Err(err);
+            }
+            self.watch_changes(&opened);
             let _ = registry.insert(name, opened.clone());
             Ok(opened)
         }
@@ -246,14 +808,19 @@ impl<R: Runtime, T: Manager<R>> DatabasesExt<R> for T {
         registry.get(&name).cloned()
     }
 
-    fn close_database(&self, name: impl Into<String>) -> () {
+    fn project_database(&self) -> crate::Result<Database> {
+        self.get_database(PROJECT_DATABASE)
+            .ok_or(crate::Error::NoActiveProject)
+    }
+
+    fn close_database(&self, name: impl Into<String>) {
         let name = name.into();
         let state = self.database_state();
         let mut registry = state.write();
         let _ = registry.remove(&name);
     }
 
-    fn clear_databases(&self) -> () {
+    fn clear_databases(&self) {
         let state = self.database_state();
         let mut registry = state.write();
         registry.clear();
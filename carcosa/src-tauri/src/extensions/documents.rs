@@ -0,0 +1,218 @@
+use std::collections::BTreeSet;
+
+use redb::{
+    ReadTransaction, ReadableTable, TableDefinition, TableError, TableHandle, WriteTransaction,
+};
+use tauri::{Manager, Runtime};
+use uuid::Uuid;
+use yrs::{
+    updates::{decoder::Decode, encoder::Encode},
+    Doc, GetString, ReadTxn, StateVector, Transact, Update,
+};
+
+use crate::extensions::{
+    changes::note_change, links, search, Database, DatabasesExt, HistoryExt, TableName,
+};
+
+/// Name of the root `Y.Text` holding an entity's body.
+pub const DOCUMENT_BODY: &str = "body";
+
+/// Number of logged updates after which a document is folded back into its snapshot.
+pub const COMPACT_AFTER_UPDATES: u64 = 128;
+
+pub(crate) const SNAPSHOTS: TableDefinition<Uuid, &[u8]> =
+    TableDefinition::new("documents.snapshots");
+pub(crate) const UPDATES: TableDefinition<(Uuid, u64), &[u8]> =
+    TableDefinition::new("documents.updates");
+
+fn build(
+    snapshots: Option<&impl ReadableTable<Uuid, &'static [u8]>>,
+    updates: Option<&impl ReadableTable<(Uuid, u64), &'static [u8]>>,
+    id: Uuid,
+) -> crate::Result<Doc> {
+    let doc = Doc::new();
+    let mut doc_txn = doc.transact_mut();
+    if let Some(snapshot) = snapshots.map(|t| t.get(id)).transpose()?.flatten() {
+        doc_txn.apply_update(Update::decode_v1(snapshot.value())?)?;
+    }
+    if let Some(updates) = updates {
+        for entry in updates.range((id, 0)..=(id, u64::MAX))? {
+            let (_, update) = entry?;
+            doc_txn.apply_update(Update::decode_v1(update.value())?)?;
+        }
+    }
+    drop(doc_txn);
+    Ok(doc)
+}
+
+fn load(db: &Database, id: Uuid) -> crate::Result<Doc> {
+    db.read_transaction(|txn| read_in(txn, id))?
+}
+
+/// Loads a document from within a read transaction.
+fn read_in(txn: &ReadTransaction, id: Uuid) -> crate::Result<Doc> {
+    let snapshots = match txn.open_table(SNAPSHOTS) {
+        Ok(table) => Some(table),
+        Err(TableError::TableDoesNotExist(_)) => None,
+        Err(err) => return Err(err.into()),
+    };
+    let updates = match txn.open_table(UPDATES) {
+        Ok(table) => Some(table),
+        Err(TableError::TableDoesNotExist(_)) => None,
+        Err(err) => return Err(err.into()),
+    };
+    build(snapshots.as_ref(), updates.as_ref(), id)
+}
+
+/// The state vector of a document, encoded as v1.
+pub(crate) fn state_vector_in(txn: &ReadTransaction, id: Uuid) -> crate::Result<Vec<u8>> {
+    Ok(read_in(txn, id)?.transact().state_vector().encode_v1())
+}
+
+/// Everything in a document that a peer at `state_vector` is missing, encoded as a v1 update.
+pub(crate) fn diff_in(
+    txn: &ReadTransaction,
+    id: Uuid,
+    state_vector: &[u8],
+) -> crate::Result<Vec<u8>> {
+    let state_vector = StateVector::decode_v1(state_vector)?;
+    Ok(read_in(txn, id)?
+        .transact()
+        .encode_state_as_update_v1(&state_vector))
+}
+
+/// Loads a document from within an open write transaction.
+pub(crate) fn load_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<Doc> {
+    let snapshots = txn.open_table(SNAPSHOTS)?;
+    let updates = txn.open_table(UPDATES)?;
+    build(Some(&snapshots), Some(&updates), id)
+}
+
+pub(crate) fn delete_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<()> {
+    let mut snapshots = txn.open_table(SNAPSHOTS)?;
+    let mut updates = txn.open_table(UPDATES)?;
+    let _ = snapshots.remove(id)?;
+    updates.retain_in((id, 0)..=(id, u64::MAX), |_, _| false)?;
+    note_change(TableName::unique(SNAPSHOTS.name()), Some(id.to_string()));
+    note_change(TableName::unique(UPDATES.name()), Some(id.to_string()));
+    Ok(())
+}
+
+/// Folds a document's logged updates into its snapshot.
+fn compact_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<()> {
+    let mut snapshots = txn.open_table(SNAPSHOTS)?;
+    let mut updates = txn.open_table(UPDATES)?;
+    let doc = build(Some(&snapshots), Some(&updates), id)?;
+    let snapshot = doc
+        .transact()
+        .encode_state_as_update_v1(&StateVector::default());
+    let _ = snapshots.insert(id, snapshot.as_slice())?;
+    updates.retain_in((id, 0)..=(id, u64::MAX), |_, _| false)?;
+    note_change(TableName::unique(SNAPSHOTS.name()), Some(id.to_string()));
+    note_change(TableName::unique(UPDATES.name()), Some(id.to_string()));
+    Ok(())
+}
+
+/// Appends an update to a document's log, compacting it once the log grows long, and re-indexes
+/// the document's search entry and links, all within `txn`.
+pub(crate) fn store_update_in(
+    txn: &WriteTransaction,
+    id: Uuid,
+    update: &[u8],
+) -> crate::Result<()> {
+    // Decoding up front rejects malformed updates before they reach the log.
+    let _ = Update::decode_v1(update)?;
+
+    let logged = {
+        let mut updates = txn.open_table(UPDATES)?;
+        let mut count = 0;
+        let mut last = None;
+        for entry in updates.range((id, 0)..=(id, u64::MAX))? {
+            let (key, _) = entry?;
+            count += 1;
+            last = Some(key.value().1);
+        }
+        let next = last.map(|seq| seq + 1).unwrap_or(0);
+        let _ = updates.insert((id, next), update)?;
+        // Reported by document rather than by (document, sequence) key.
+        note_change(TableName::unique(UPDATES.name()), Some(id.to_string()));
+        count + 1
+    };
+    if logged >= COMPACT_AFTER_UPDATES {
+        compact_in(txn, id)?;
+    }
+    search::reindex_in(txn, id)?;
+    links::index_links_in(txn, id)
+}
+
+/// The plain text of a document's body.
+pub(crate) fn body_text(doc: &Doc) -> String {
+    let body = doc.get_or_insert_text(DOCUMENT_BODY);
+    let text = body.get_string(&doc.transact());
+    text
+}
+
+pub trait DocumentsExt<R: Runtime> {
+    fn load_document(&self, id: Uuid) -> crate::Result<Doc>;
+    fn document_state_vector(&self, id: Uuid) -> crate::Result<Vec<u8>>;
+    fn document_diff(&self, id: Uuid, state_vector: Vec<u8>) -> crate::Result<Vec<u8>>;
+    fn document_text(&self, id: Uuid) -> crate::Result<String>;
+    fn list_documents(&self) -> crate::Result<Vec<Uuid>>;
+
+    /// Appends an update to a document's log without any further checks or broadcasting. Undoing
+    /// an operation recorded before the update keeps it.
+    fn store_document_update(&self, id: Uuid, update: &[u8]) -> crate::Result<()>;
+}
+
+impl<R: Runtime, T: Manager<R>> DocumentsExt<R> for T {
+    fn load_document(&self, id: Uuid) -> crate::Result<Doc> {
+        load(&self.project_database()?, id)
+    }
+
+    fn document_state_vector(&self, id: Uuid) -> crate::Result<Vec<u8>> {
+        self.project_database()?
+            .read_transaction(|txn| state_vector_in(txn, id))?
+    }
+
+    fn document_diff(&self, id: Uuid, state_vector: Vec<u8>) -> crate::Result<Vec<u8>> {
+        self.project_database()?
+            .read_transaction(|txn| diff_in(txn, id, &state_vector))?
+    }
+
+    fn document_text(&self, id: Uuid) -> crate::Result<String> {
+        Ok(body_text(&self.load_document(id)?))
+    }
+
+    fn list_documents(&self) -> crate::Result<Vec<Uuid>> {
+        self.project_database()?
+            .read_transaction(|txn| -> crate::Result<Vec<Uuid>> {
+                let mut documents = BTreeSet::new();
+                match txn.open_table(SNAPSHOTS) {
+                    Ok(snapshots) => {
+                        for entry in snapshots.iter()? {
+                            let _ = documents.insert(entry?.0.value());
+                        }
+                    }
+                    Err(TableError::TableDoesNotExist(_)) => {}
+                    Err(err) => return Err(err.into()),
+                }
+                match txn.open_table(UPDATES) {
+                    Ok(updates) => {
+                        for entry in updates.iter()? {
+                            let _ = documents.insert(entry?.0.value().0);
+                        }
+                    }
+                    Err(TableError::TableDoesNotExist(_)) => {}
+                    Err(err) => return Err(err.into()),
+                }
+                Ok(documents.into_iter().collect())
+            })?
+    }
+
+    fn store_document_update(&self, id: Uuid, update: &[u8]) -> crate::Result<()> {
+        self.project_database()?
+            .write_transaction(|txn| store_update_in(txn, id, update))??;
+        self.app_handle().note_document_edit(id);
+        Ok(())
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+use chrono::Utc;
+use parking_lot::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+use uuid::Uuid;
+use yrs::{updates::encoder::Encode, StateVector};
+
+use crate::{
+    extensions::{
+        insert_record,
+        invites::InviteTable,
+        links,
+        sync::world_snapshot,
+        world::{ensure_hosted, EntityTable},
+        ApplicationExt, AssetsExt, Database, DatabasesExt, DocumentsExt, MapsExt, RelationsExt,
+        SyncExt, TimelineExt, TypedTable, WorldExt, WriteTransactionExt,
+    },
+    procedures::{AppEvent, AppEventExt},
+    types::{
+        Entity, EntityUpdate, History, HistoryItem, HistoryView, MapAnnotation, MapInfo, Relation,
+        Retarget, TimelineEvent,
+    },
+};
+
+/// How to redo an undone operation, by running it again.
+#[derive(Clone, Debug)]
+pub enum HistoryAction {
+    CreateEntity { entity: Entity },
+    UpdateEntity { id: Uuid, update: EntityUpdate },
+    DeleteEntity { id: Uuid },
+    AddRelation { relation: Relation },
+    RemoveRelation { relation: Relation },
+    CreateEvent { event: TimelineEvent },
+    UpdateEvent { event: TimelineEvent },
+    DeleteEvent { id: Uuid },
+    AttachAsset { entity: Uuid, hash: String },
+    DetachAsset { entity: Uuid, hash: String },
+    UpdateMap { map: MapInfo },
+    DeleteMap { id: Uuid },
+    AddAnnotation { annotation: MapAnnotation },
+    UpdateAnnotation { annotation: MapAnnotation },
+    RemoveAnnotation { id: Uuid },
+}
+
+impl HistoryAction {
+    pub fn views(&self) -> Vec<HistoryView> {
+        match self {
+            Self::CreateEntity { .. } => vec![HistoryView::Entities, HistoryView::Search],
+            // Renaming an entity rewrites the links pointing at it.
+            Self::UpdateEntity { .. } => vec![
+                HistoryView::Entities,
+                HistoryView::Search,
+                HistoryView::Documents,
+            ],
+            Self::DeleteEntity { .. } => vec![
+                HistoryView::Entities,
+                HistoryView::Relations,
+                HistoryView::Timeline,
+                HistoryView::Documents,
+                HistoryView::Search,
+                HistoryView::Maps,
+                HistoryView::Assets,
+            ],
+            Self::AddRelation { .. } | Self::RemoveRelation { .. } => vec![HistoryView::Relations],
+            Self::CreateEvent { .. } | Self::UpdateEvent { .. } | Self::DeleteEvent { .. } => {
+                vec![HistoryView::Timeline]
+            }
+            Self::AttachAsset { .. } | Self::DetachAsset { .. } => vec![HistoryView::Assets],
+            // Maps derive "located in" relations from their pins and locations.
+            Self::UpdateMap { .. }
+            | Self::DeleteMap { .. }
+            | Self::AddAnnotation { .. }
+            | Self::UpdateAnnotation { .. }
+            | Self::RemoveAnnotation { .. } => vec![HistoryView::Maps, HistoryView::Relations],
+        }
+    }
+
+    fn apply<R: Runtime>(self, app: &AppHandle<R>) -> crate::Result<()> {
+        match self {
+            Self::CreateEntity { entity } => app.insert_entity(entity).map(drop),
+            Self::UpdateEntity { id, update } => app.update_entity(id, update).map(drop),
+            Self::DeleteEntity { id } => app.delete_entity(id).map(drop),
+            Self::AddRelation { relation } => app.add_relation(relation).map(drop),
+            Self::RemoveRelation { relation } => app.remove_relation(relation).map(drop),
+            Self::CreateEvent { event } => app.insert_event(event).map(drop),
+            Self::UpdateEvent { event } => app.update_event(event).map(drop),
+            Self::DeleteEvent { id } => app.delete_event(id).map(drop),
+            Self::AttachAsset { entity, hash } => app.attach_asset(entity, &hash),
+            Self::DetachAsset { entity, hash } => app.detach_asset(entity, &hash).map(drop),
+            Self::UpdateMap { map } => app.update_map(map).map(drop),
+            Self::DeleteMap { id } => app.delete_map(id).map(drop),
+            Self::AddAnnotation { annotation } => app.insert_annotation(annotation).map(drop),
+            Self::UpdateAnnotation { annotation } => app.update_annotation(annotation).map(drop),
+            Self::RemoveAnnotation { id } => app.remove_annotation(id).map(drop),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct HistoryEntry {
+    item: HistoryItem,
+    action: HistoryAction,
+
+    /// Savepoint holding the project database as it was just before the operation. Undone
+    /// entries have none, since restoring it consumed it.
+    savepoint: Option<u64>,
+
+    /// Documents edited since the operation, which restoring its savepoint mustn't roll back.
+    documents: HashSet<Uuid>,
+}
+
+#[derive(Debug, Default)]
+pub struct HistoryStack {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+
+    /// Set while an entry is being redone, so recording it again keeps the rest of the redo stack.
+    replaying: bool,
+}
+
+pub type HistoryState = Arc<Mutex<HistoryStack>>;
+
+#[async_trait::async_trait]
+pub trait HistoryExt<R: Runtime> {
+    fn history_state(&self) -> HistoryState;
+
+    /// Runs a user-level operation, recording a savepoint first so it can be undone.
+    fn undoable<Output>(
+        &self,
+        label: String,
+        action: HistoryAction,
+        operation: impl FnOnce() -> crate::Result<Output>,
+    ) -> crate::Result<Output>;
+
+    /// Rolls the project database back to just before the most recent operation. Document edits
+    /// made since are kept, since the editor keeps its own history for those, but links in them
+    /// are pointed at the names the entities had before.
+    async fn undo(&self) -> crate::Result<Option<HistoryItem>>;
+
+    /// Runs the most recently undone operation again.
+    fn redo(&self) -> crate::Result<Option<HistoryItem>>;
+    fn history(&self) -> History;
+
+    /// Records that a document was edited, so undoing any operation recorded so far keeps the
+    /// edit.
+    fn note_document_edit(&self, id: Uuid);
+
+    /// Forgets every recorded operation, ie. when another project is opened, or after a change
+    /// that restoring a savepoint can't roll back along with the database, such as importing an
+    /// asset or editing a template.
+    fn reset_history(&self);
+}
+
+#[async_trait::async_trait]
+impl<R: Runtime, T: Manager<R> + Sync> HistoryExt<R> for T {
+    fn history_state(&self) -> HistoryState {
+        if let Some(existing) = self.try_state::<HistoryState>() {
+            existing.inner().clone()
+        } else {
+            self.manage::<HistoryState>(Arc::new(Mutex::new(HistoryStack::default())));
+            self.state::<HistoryState>().inner().clone()
+        }
+    }
+
+    fn undoable<Output>(
+        &self,
+        label: String,
+        action: HistoryAction,
+        operation: impl FnOnce() -> crate::Result<Output>,
+    ) -> crate::Result<Output> {
+        let depth = self.get_app_state().settings().undo_depth() as usize;
+        if depth == 0 {
+            return operation();
+        }
+        let db = self.project_database()?;
+        // Nothing else may write between the savepoint and the operation, or undoing the
+        // operation would roll that back too.
+        let (output, dropped) = db.exclusive(|| -> crate::Result<_> {
+            let savepoint = db.create_savepoint()?;
+            let output = match operation() {
+                Ok(output) => output,
+                Err(err) => {
+                    if let Err(err) = db.delete_savepoint(savepoint) {
+                        log::warn!("Failed to delete savepoint {savepoint}: {err}");
+                    }
+                    return Err(err);
+                }
+            };
+
+            let entry = HistoryEntry {
+                item: HistoryItem {
+                    id: Uuid::now_v7(),
+                    label,
+                    views: action.views(),
+                    at: Utc::now(),
+                },
+                action,
+                savepoint: Some(savepoint),
+                documents: HashSet::new(),
+            };
+            let state = self.history_state();
+            let mut stack = state.lock();
+            if !stack.replaying {
+                stack.redo.clear();
+            }
+            stack.undo.push_back(entry);
+            let excess = stack.undo.len().saturating_sub(depth);
+            Ok((output, stack.undo.drain(..excess).collect::<Vec<_>>()))
+        })?;
+        for savepoint in dropped.into_iter().filter_map(|entry| entry.savepoint) {
+            if let Err(err) = db.delete_savepoint(savepoint) {
+                log::warn!("Failed to delete savepoint {savepoint}: {err}");
+            }
+        }
+        Ok(output)
+    }
+
+    async fn undo(&self) -> crate::Result<Option<HistoryItem>> {
+        ensure_hosted::<R>(self)?;
+        let db = self.project_database()?;
+        let app = self.app_handle().clone();
+        let restored = {
+            let db = db.clone();
+            tauri::async_runtime::spawn_blocking(move || db.exclusive(|| restore_last(&app, &db)))
+                .await??
+        };
+        let Some(entry) = restored else {
+            return Ok(None);
+        };
+
+        let settings = self
+            .get_app_state()
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)?;
+        for message in world_snapshot(&db, &settings).await? {
+            self.app_handle().broadcast_message(message, None);
+        }
+
+        let item = entry.item.clone();
+        self.history_state().lock().redo.push(entry);
+        let _ = self.emit_event(AppEvent::HistoryApplied {
+            item: item.clone(),
+            undone: true,
+        });
+        Ok(Some(item))
+    }
+
+    fn redo(&self) -> crate::Result<Option<HistoryItem>> {
+        ensure_hosted::<R>(self)?;
+        let state = self.history_state();
+        let Some(entry) = state.lock().redo.pop() else {
+            return Ok(None);
+        };
+        state.lock().replaying = true;
+        let result = entry.action.clone().apply(self.app_handle());
+        state.lock().replaying = false;
+        if let Err(err) = result {
+            // Whatever made this fail will also get in the way of everything undone before it.
+            state.lock().redo.clear();
+            return Err(err);
+        }
+        let _ = self.emit_event(AppEvent::HistoryApplied {
+            item: entry.item.clone(),
+            undone: false,
+        });
+        Ok(Some(entry.item))
+    }
+
+    fn history(&self) -> History {
+        let state = self.history_state();
+        let stack = state.lock();
+        History {
+            undo: stack.undo.iter().map(|entry| entry.item.clone()).collect(),
+            redo: stack.redo.iter().map(|entry| entry.item.clone()).collect(),
+            depth: self.get_app_state().settings().undo_depth(),
+        }
+    }
+
+    fn note_document_edit(&self, id: Uuid) {
+        let state = self.history_state();
+        for entry in state.lock().undo.iter_mut() {
+            let _ = entry.documents.insert(id);
+        }
+    }
+
+    fn reset_history(&self) {
+        let forgotten = {
+            let state = self.history_state();
+            let mut stack = state.lock();
+            let mut forgotten = stack.undo.drain(..).collect::<Vec<_>>();
+            forgotten.append(&mut stack.redo);
+            forgotten
+        };
+        // When another project is opened, its savepoints are gone along with its database.
+        let Ok(db) = self.project_database() else {
+            return;
+        };
+        for savepoint in forgotten.into_iter().filter_map(|entry| entry.savepoint) {
+            if let Err(err) = db.delete_savepoint(savepoint) {
+                log::warn!("Failed to delete savepoint {savepoint}: {err}");
+            }
+        }
+    }
+}
+
+/// Restores the savepoint of the most recent operation, keeping the documents edited and the
+/// invites created since, and returns the undone entry.
+/// Has to run inside [`Database::exclusive`], so nothing can write in between.
+fn restore_last<R: Runtime>(
+    app: &AppHandle<R>,
+    db: &Database,
+) -> crate::Result<Option<HistoryEntry>> {
+    let Some(mut entry) = app.history_state().lock().undo.pop_back() else {
+        return Ok(None);
+    };
+    let Some(savepoint) = entry.savepoint.take() else {
+        return Ok(None);
+    };
+
+    // Restoring rolls back every table, so hold on to the documents and invites that
+    // shouldn't be, and to the entities whose names those documents link to.
+    let mut documents = Vec::new();
+    for id in std::mem::take(&mut entry.documents) {
+        documents.push((
+            id,
+            app.document_diff(id, StateVector::default().encode_v1())?,
+        ));
+    }
+    let invites = db.iter::<InviteTable>()?;
+    let entities = db
+        .iter::<EntityTable>()?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    db.restore_savepoint(savepoint)?;
+
+    db.write_transaction(|txn| -> crate::Result<()> {
+        let mut table = txn.open_table_mut(InviteTable::definition())?;
+        table.retain(|_, _| false)?;
+        for (nonce, invite) in &invites {
+            let _ = insert_record::<InviteTable>(&mut table, nonce, invite)?;
+        }
+        Ok(())
+    })??;
+    for (id, update) in documents {
+        if app.get_entity(id).is_ok() {
+            app.store_document_update(id, &update)?;
+        }
+    }
+    // The kept documents may link to names the entities only had after the savepoint.
+    let retargets = db
+        .iter::<EntityTable>()?
+        .into_iter()
+        .filter_map(|(id, restored)| {
+            entities
+                .get(&id)
+                .map(|before| Retarget::between(before, &restored))
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    if !retargets.is_empty() {
+        let rewritten = db.write_transaction(|txn| -> crate::Result<Vec<(Uuid, Vec<u8>)>> {
+            let mut rewritten = Vec::new();
+            for retarget in &retargets {
+                rewritten.extend(links::retarget_in(txn, retarget)?);
+            }
+            Ok(rewritten)
+        })??;
+        for (document, update) in rewritten {
+            app.broadcast_document_update(document, update, None);
+        }
+    }
+    Ok(Some(entry))
+}
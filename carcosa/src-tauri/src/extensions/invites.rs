@@ -0,0 +1,293 @@
+use base64::prelude::*;
+use chrono::{DateTime, Duration, Utc};
+use iroh::{
+    endpoint::Connection,
+    protocol::{AcceptError, ProtocolHandler},
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::{
+    extensions::{
+        app_dirs, migrations::MetadataTable, sync::REMOTE_ADDRESS_KEY, table, ApplicationExt,
+        DatabasesExt, SyncExt,
+    },
+    types::{
+        ActiveProject, InvitePayload, InviteTicket, PeerIdentity, ProjectCollaborator,
+        ProjectSettings,
+    },
+};
+
+/// ALPN identifying the handshake a guest performs to redeem an invite.
+pub const JOIN_ALPN: &[u8] = b"carcosa/join/1";
+
+const MAX_JOIN_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// An outstanding invite, keyed by its nonce. Records are removed once redeemed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InviteRecord {
+    pub expires: DateTime<Utc>,
+    pub can_edit: bool,
+}
+
+table!(pub InviteTable: "sync.invites", String => InviteRecord);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JoinRequest {
+    pub nonce: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum JoinResponse {
+    Accepted { project: String, can_edit: bool },
+    Rejected { reason: String },
+}
+
+/// Directory a remote project hosted by `host` is mirrored into.
+pub fn remote_project_dir(host: &PeerIdentity) -> crate::Result<std::path::PathBuf> {
+    let host: String = host.clone().into();
+    Ok(app_dirs()?.data_dir.join("remote").join(host))
+}
+
+#[derive(Clone)]
+pub(crate) struct JoinProtocol<R: Runtime> {
+    pub(crate) app: AppHandle<R>,
+}
+
+impl<R: Runtime> std::fmt::Debug for JoinProtocol<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JoinProtocol").finish()
+    }
+}
+
+impl<R: Runtime> JoinProtocol<R> {
+    async fn handle(&self, connection: &Connection) -> crate::Result<()> {
+        let peer = PeerIdentity::from(connection.remote_id());
+        let (mut send, mut recv) = connection
+            .accept_bi()
+            .await
+            .map_err(crate::Error::network)?;
+        let data = recv
+            .read_to_end(MAX_JOIN_MESSAGE_SIZE)
+            .await
+            .map_err(crate::Error::network)?;
+        let request = serde_json::from_slice::<JoinRequest>(&data)?;
+        let response = match self.app.redeem_invite(peer.clone(), request) {
+            Ok(response) => response,
+            Err(err) => JoinResponse::Rejected {
+                reason: err.to_string(),
+            },
+        };
+        if let JoinResponse::Rejected { reason } = &response {
+            log::info!("Rejected join from {}: {reason}", peer.short_format());
+        }
+        send.write_all(&serde_json::to_vec(&response)?)
+            .await
+            .map_err(crate::Error::network)?;
+        send.finish().map_err(crate::Error::network)?;
+        let _ = connection.closed().await;
+        Ok(())
+    }
+}
+
+impl<R: Runtime> ProtocolHandler for JoinProtocol<R> {
+    async fn accept(&self, connection: Connection) -> Result<(), AcceptError> {
+        if let Err(err) = self.handle(&connection).await {
+            log::warn!("Join handshake failed: {err}");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+pub trait InvitesExt<R: Runtime> {
+    /// Creates a signed ticket granting one peer access to the open project.
+    fn create_invite(&self, can_edit: bool, valid_hours: u32) -> crate::Result<String>;
+
+    /// Registers `peer` as a collaborator if `request` names a valid, unused invite.
+    fn redeem_invite(
+        &self,
+        peer: PeerIdentity,
+        request: JoinRequest,
+    ) -> crate::Result<JoinResponse>;
+
+    /// Redeems an invite ticket with its host and opens the hosted project locally.
+    async fn join_remote_project(
+        &self,
+        ticket: String,
+        name: String,
+    ) -> crate::Result<ProjectSettings>;
+
+    /// Reopens a previously joined remote project from its local cache. The project stays usable
+    /// while the host is unreachable, and is reconciled once it can be reached again.
+    async fn open_remote_project(&self, host: PeerIdentity) -> crate::Result<ProjectSettings>;
+}
+
+#[async_trait::async_trait]
+impl<R: Runtime, T: Manager<R> + Sync> InvitesExt<R> for T {
+    fn create_invite(&self, can_edit: bool, valid_hours: u32) -> crate::Result<String> {
+        let state = self.get_app_state();
+        let ActiveProject::Local { .. } = state.active_project() else {
+            return Err(crate::Error::validation(
+                "project",
+                "Only locally hosted projects can be shared",
+            ));
+        };
+        let settings = state
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)?;
+        let node = self.sync_node()?;
+
+        let mut nonce = [0u8; 16];
+        rand::rng().fill_bytes(&mut nonce);
+        let nonce = BASE64_URL_SAFE_NO_PAD.encode(nonce);
+        let expires = Utc::now() + Duration::hours(valid_hours.into());
+        let _ = self
+            .project_database()?
+            .insert::<InviteTable>(&nonce, &InviteRecord { expires, can_edit })?;
+
+        InviteTicket::sign(
+            &settings.identity(),
+            InvitePayload {
+                host: node.identity(),
+                address: node.endpoint().addr(),
+                project: settings.name(),
+                nonce,
+                expires,
+                can_edit,
+            },
+        )?
+        .encode()
+    }
+
+    fn redeem_invite(
+        &self,
+        peer: PeerIdentity,
+        request: JoinRequest,
+    ) -> crate::Result<JoinResponse> {
+        let ActiveProject::Local { .. } = self.get_app_state().active_project() else {
+            return Err(crate::Error::NoActiveProject);
+        };
+        if request.name.trim().is_empty() {
+            return Err(crate::Error::validation("name", "Name cannot be empty"));
+        }
+        let invite = self
+            .project_database()?
+            .remove::<InviteTable>(&request.nonce)?
+            .ok_or_else(|| crate::Error::not_found("invite", &request.nonce))?;
+        if invite.expires < Utc::now() {
+            return Err(crate::Error::validation("invite", "Invite has expired"));
+        }
+
+        let settings = self.update_project_settings(|settings| {
+            Ok(settings.with_collaborator(ProjectCollaborator {
+                identity: peer.clone(),
+                name: request.name.clone(),
+                can_edit: invite.can_edit,
+            }))
+        })?;
+        log::info!(
+            "{} ({}) joined the project",
+            request.name,
+            peer.short_format()
+        );
+        Ok(JoinResponse::Accepted {
+            project: settings.name(),
+            can_edit: invite.can_edit,
+        })
+    }
+
+    async fn join_remote_project(
+        &self,
+        ticket: String,
+        name: String,
+    ) -> crate::Result<ProjectSettings> {
+        let ticket = InviteTicket::decode(ticket)?;
+        ticket.verify()?;
+        let InvitePayload {
+            host,
+            address,
+            project,
+            nonce,
+            ..
+        } = ticket.payload;
+
+        let path = remote_project_dir(&host)?;
+        if !path.join("project.json").exists() {
+            std::fs::create_dir_all(&path)?;
+            let _ = ProjectSettings::new(project).save(path.clone())?;
+        }
+        let _ = self.set_active_project(ActiveProject::Remote {
+            host: host.clone(),
+            path,
+        })?;
+
+        let joined = async {
+            let _ = self.start_sync().await?;
+            let connection = self
+                .sync_node()?
+                .endpoint()
+                .connect(address.clone(), JOIN_ALPN)
+                .await
+                .map_err(crate::Error::network)?;
+            let (mut send, mut recv) = connection.open_bi().await.map_err(crate::Error::network)?;
+            send.write_all(&serde_json::to_vec(&JoinRequest { nonce, name })?)
+                .await
+                .map_err(crate::Error::network)?;
+            send.finish().map_err(crate::Error::network)?;
+            let data = recv
+                .read_to_end(MAX_JOIN_MESSAGE_SIZE)
+                .await
+                .map_err(crate::Error::network)?;
+            connection.close(0u32.into(), b"joined");
+            match serde_json::from_slice::<JoinResponse>(&data)? {
+                JoinResponse::Accepted { .. } => {
+                    let _ = self.project_database()?.insert::<MetadataTable>(
+                        &REMOTE_ADDRESS_KEY.to_string(),
+                        &serde_json::to_value(&address)?,
+                    )?;
+                    self.connect_address(address).await
+                }
+                JoinResponse::Rejected { reason } => Err(crate::Error::Network(format!(
+                    "The host rejected the invite: {reason}"
+                ))),
+            }
+        }
+        .await;
+
+        if let Err(err) = joined {
+            let _ = self.set_active_project(ActiveProject::None);
+            return Err(err);
+        }
+        self.get_app_state()
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)
+    }
+
+    async fn open_remote_project(&self, host: PeerIdentity) -> crate::Result<ProjectSettings> {
+        let path = remote_project_dir(&host)?;
+        if !path.join("project.json").exists() {
+            return Err(crate::Error::not_found(
+                "remote project",
+                host.short_format(),
+            ));
+        }
+        let state = self.set_active_project(ActiveProject::Remote {
+            host: host.clone(),
+            path,
+        })?;
+        let _ = self.start_sync().await?;
+        if let Err(err) = self.connect_host().await {
+            log::info!(
+                "Working offline, host {} is not reachable: {err}",
+                host.short_format()
+            );
+        }
+        state
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashSet;
+
+use redb::{
+    MultimapTableDefinition, ReadTransaction, ReadableMultimapTable, TableError, WriteTransaction,
+};
+use uuid::Uuid;
+use yrs::{
+    types::text::YChange, Any, Doc, GetString, Out, ReadTxn, Text, TextRef, Transact,
+    TransactionMut,
+};
+
+use crate::{
+    extensions::{
+        documents::{load_in, store_update_in, DOCUMENT_BODY},
+        world::EntityTable,
+        RecordTableExt, TypedTable, WriteTransactionExt,
+    },
+    types::{Backlink, Retarget, WikiLink},
+};
+
+/// Source entity => every link target in its body, as written.
+const OUTGOING: MultimapTableDefinition<Uuid, &str> =
+    MultimapTableDefinition::new("links.outgoing");
+
+/// Link key (see [`WikiLink::key`]) => every entity whose body links to it.
+const INCOMING: MultimapTableDefinition<&str, Uuid> =
+    MultimapTableDefinition::new("links.incoming");
+
+/// Key of the entity ID in a `Y.Text` embed that references an entity.
+pub const ENTITY_EMBED_KEY: &str = "entity";
+
+/// Every link target in a document's body, from both wiki links and entity embeds.
+fn document_links(doc: &Doc) -> Vec<String> {
+    let body = doc.get_or_insert_text(DOCUMENT_BODY);
+    let txn = doc.transact();
+    let mut targets = WikiLink::parse(&body.get_string(&txn))
+        .into_iter()
+        .map(|link| link.target)
+        .collect::<Vec<_>>();
+    for chunk in body.diff(&txn, YChange::identity) {
+        if let Out::Any(Any::Map(embed)) = chunk.insert {
+            if let Some(Any::String(id)) = embed.get(ENTITY_EMBED_KEY) {
+                targets.push(id.to_string());
+            }
+        }
+    }
+    targets
+}
+
+/// Removes every link from `id`'s body from the index.
+pub(crate) fn unlink_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<()> {
+    let mut outgoing = txn.open_multimap_table_mut(OUTGOING)?;
+    let mut incoming = txn.open_multimap_table_mut(INCOMING)?;
+    let mut targets = Vec::new();
+    for target in outgoing.get(id)? {
+        targets.push(target?.value().to_string());
+    }
+    for target in targets {
+        let _ = incoming.remove(WikiLink::key(&target).as_str(), id)?;
+    }
+    let _ = outgoing.remove_all(id)?;
+    Ok(())
+}
+
+/// Replaces the indexed links of `id`'s body, reading it from the same transaction.
+pub(crate) fn index_links_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<()> {
+    let targets = document_links(&load_in(txn, id)?);
+    unlink_in(txn, id)?;
+    let mut outgoing = txn.open_multimap_table_mut(OUTGOING)?;
+    let mut incoming = txn.open_multimap_table_mut(INCOMING)?;
+    for target in targets {
+        let _ = outgoing.insert(id, target.as_str())?;
+        let _ = incoming.insert(WikiLink::key(&target).as_str(), id)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the links index for every entity.
+pub(crate) fn rebuild_links_in(txn: &WriteTransaction) -> crate::Result<()> {
+    let _ = txn.clear_multimap_table(OUTGOING)?;
+    let _ = txn.clear_multimap_table(INCOMING)?;
+    let entities = {
+        let table = txn.open_table(EntityTable::definition())?;
+        RecordTableExt::<EntityTable>::range_records(&table, ..)?
+    };
+    for (id, _) in entities {
+        index_links_in(txn, id)?;
+    }
+    Ok(())
+}
+
+/// A document body's text, along with the text offset of every embed in it. Each embed takes up
+/// one index of the `Y.Text` but doesn't appear in its string.
+fn body_with_embeds<T: ReadTxn>(body: &TextRef, txn: &T) -> (String, Vec<usize>) {
+    let mut text = String::new();
+    let mut embeds = Vec::new();
+    for chunk in body.diff(txn, YChange::identity) {
+        match chunk.insert {
+            Out::Any(Any::String(chunk)) => text.push_str(&chunk),
+            _ => embeds.push(text.len()),
+        }
+    }
+    (text, embeds)
+}
+
+/// Rewrites every link to `retarget.from` in `body`, returning whether there were any. Links with
+/// an embed inside them are left alone, since rewriting them would delete the embed.
+fn rewrite_links(body: &TextRef, txn: &mut TransactionMut, retarget: &Retarget) -> bool {
+    let key = WikiLink::key(&retarget.from);
+    let (text, embeds) = body_with_embeds(body, txn);
+    let links = WikiLink::parse(&text)
+        .into_iter()
+        .filter(|link| WikiLink::key(&link.target) == key)
+        .filter(|link| {
+            !embeds
+                .iter()
+                .any(|&embed| link.range.start < embed && embed < link.range.end)
+        })
+        .collect::<Vec<_>>();
+    // Documents use byte offsets, so the parsed ranges only need shifting past the embeds before
+    // them. Working backwards keeps the earlier ranges valid.
+    for link in links.iter().rev() {
+        let before = embeds
+            .iter()
+            .filter(|&&embed| embed <= link.range.start)
+            .count();
+        let start = (link.range.start + before) as u32;
+        let replacement = if retarget.keep_text {
+            link.retargeted_as_shown(&retarget.to)
+        } else {
+            link.retargeted(&retarget.to)
+        };
+        body.remove_range(txn, start, link.range.len() as u32);
+        body.insert(txn, start, &replacement);
+    }
+    !links.is_empty()
+}
+
+/// Rewrites the links to `retarget.from` in every body that links to it, returning the update
+/// made to each changed document so it can be broadcast once committed.
+pub(crate) fn retarget_in(
+    txn: &WriteTransaction,
+    retarget: &Retarget,
+) -> crate::Result<Vec<(Uuid, Vec<u8>)>> {
+    let sources = {
+        let incoming = txn.open_multimap_table(INCOMING)?;
+        let mut sources = Vec::new();
+        for source in incoming.get(WikiLink::key(&retarget.from).as_str())? {
+            sources.push(source?.value());
+        }
+        sources
+    };
+
+    let mut updates = Vec::new();
+    for source in sources {
+        let doc = load_in(txn, source)?;
+        let body = doc.get_or_insert_text(DOCUMENT_BODY);
+        let update = {
+            let mut doc_txn = doc.transact_mut();
+            if !rewrite_links(&body, &mut doc_txn, retarget) {
+                continue;
+            }
+            doc_txn.encode_update_v1()
+        };
+        store_update_in(txn, source, &update)?;
+        updates.push((source, update));
+    }
+    Ok(updates)
+}
+
+/// Every link to `id`, by any of its names or by its ID.
+pub(crate) fn backlinks_in(txn: &ReadTransaction, id: Uuid) -> crate::Result<Vec<Backlink>> {
+    let entity = RecordTableExt::<EntityTable>::get_record(
+        &txn.open_table(EntityTable::definition())?,
+        &id,
+    )?
+    .ok_or_else(|| crate::Error::not_found("entity", id))?;
+    let mut keys = entity
+        .names()
+        .iter()
+        .map(|name| WikiLink::key(name))
+        .collect::<HashSet<_>>();
+    let _ = keys.insert(id.to_string());
+
+    let (outgoing, incoming) = match (
+        txn.open_multimap_table(OUTGOING),
+        txn.open_multimap_table(INCOMING),
+    ) {
+        (Ok(outgoing), Ok(incoming)) => (outgoing, incoming),
+        (Err(TableError::TableDoesNotExist(_)), _) | (_, Err(TableError::TableDoesNotExist(_))) => {
+            return Ok(Vec::new())
+        }
+        (Err(err), _) | (_, Err(err)) => return Err(err.into()),
+    };
+    let mut sources = HashSet::new();
+    for key in &keys {
+        for source in incoming.get(key.as_str())? {
+            let _ = sources.insert(source?.value());
+        }
+    }
+    let mut backlinks = Vec::new();
+    for source in sources {
+        for target in outgoing.get(source)? {
+            let target = target?.value().to_string();
+            if keys.contains(&WikiLink::key(&target)) {
+                backlinks.push(Backlink { source, target });
+            }
+        }
+    }
+    Ok(backlinks)
+}
+
+/// Every link whose target doesn't match any entity.
+pub(crate) fn broken_links_in(txn: &ReadTransaction) -> crate::Result<Vec<Backlink>> {
+    let outgoing = match txn.open_multimap_table(OUTGOING) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut valid = HashSet::new();
+    for (_, entity) in RecordTableExt::<EntityTable>::range_records(
+        &txn.open_table(EntityTable::definition())?,
+        ..,
+    )? {
+        valid.extend(entity.names().iter().map(|name| WikiLink::key(name)));
+        let _ = valid.insert(entity.id().to_string());
+    }
+
+    let mut broken = Vec::new();
+    for entry in outgoing.iter()? {
+        let (source, targets) = entry?;
+        for target in targets {
+            let target = target?.value().to_string();
+            if !valid.contains(&WikiLink::key(&target)) {
+                broken.push(Backlink {
+                    source: source.value(),
+                    target,
+                });
+            }
+        }
+    }
+    Ok(broken)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn retarget(from: &str, to: &str, keep_text: bool) -> Retarget {
+        Retarget {
+            from: from.to_string(),
+            to: to.to_string(),
+            keep_text,
+        }
+    }
+
+    fn embed() -> Any {
+        Any::from(HashMap::from([(
+            ENTITY_EMBED_KEY.to_string(),
+            Any::from(Uuid::nil().to_string()),
+        )]))
+    }
+
+    #[test]
+    fn rewrites_links_after_embeds() {
+        let doc = Doc::new();
+        let body = doc.get_or_insert_text(DOCUMENT_BODY);
+        let mut txn = doc.transact_mut();
+        body.insert(&mut txn, 0, "A ");
+        body.insert_embed(&mut txn, 2, embed());
+        body.insert(&mut txn, 3, " then [[Old]] and [[old|label]].");
+        assert!(rewrite_links(
+            &body,
+            &mut txn,
+            &retarget("Old", "New", false)
+        ));
+        assert_eq!(body.get_string(&txn), "A  then [[New]] and [[New|label]].");
+        assert_eq!(body_with_embeds(&body, &txn).1, vec![2]);
+    }
+
+    #[test]
+    fn keeps_shown_text_of_removed_aliases() {
+        let doc = Doc::new();
+        let body = doc.get_or_insert_text(DOCUMENT_BODY);
+        let mut txn = doc.transact_mut();
+        body.insert(&mut txn, 0, "Ask [[Him]].");
+        assert!(rewrite_links(
+            &body,
+            &mut txn,
+            &retarget("Him", "Hastur", true)
+        ));
+        assert_eq!(body.get_string(&txn), "Ask [[Hastur|Him]].");
+    }
+
+    #[test]
+    fn leaves_links_split_by_an_embed_alone() {
+        let doc = Doc::new();
+        let body = doc.get_or_insert_text(DOCUMENT_BODY);
+        let mut txn = doc.transact_mut();
+        body.insert(&mut txn, 0, "[[Ol]]");
+        body.insert_embed(&mut txn, 4, embed());
+        assert!(!rewrite_links(
+            &body,
+            &mut txn,
+            &retarget("Ol", "New", false)
+        ));
+        assert_eq!(body_with_embeds(&body, &txn).1, vec![4]);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{collections::VecDeque, path::PathBuf, sync::Arc};
+
+use parking_lot::RwLock;
+use tauri::{Manager, Runtime};
+
+use crate::{
+    extensions::app_dirs,
+    types::{LogEntry, LogFilter, LogLevel},
+};
+
+/// Number of daily log files kept in the logs directory.
+pub const LOG_FILE_RETENTION: usize = 14;
+
+/// Number of recent entries kept in memory for the `logs` procedures.
+const MAX_BUFFERED_ENTRIES: usize = 2000;
+
+#[derive(Clone, Default)]
+pub struct LogState {
+    project: Arc<RwLock<Option<String>>>,
+    recent: Arc<RwLock<VecDeque<LogEntry>>>,
+}
+
+impl LogState {
+    fn entry(&self, record: &log::Record) -> LogEntry {
+        LogEntry::new(record, self.project.read().clone())
+    }
+
+    fn push(&self, entry: LogEntry) {
+        let mut recent = self.recent.write();
+        if recent.len() >= MAX_BUFFERED_ENTRIES {
+            let _ = recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+}
+
+/// Directory the rotating log files are written to.
+pub fn logs_dir() -> crate::Result<PathBuf> {
+    Ok(app_dirs()?.data_dir.join("logs"))
+}
+
+/// Deletes all but the newest [`LOG_FILE_RETENTION`] log files. File names end in their date, so
+/// sorting by name sorts them by age.
+fn prune_logs(dir: &PathBuf) -> crate::Result<()> {
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect::<Vec<_>>();
+    files.sort();
+    let excess = files.len().saturating_sub(LOG_FILE_RETENTION);
+    for file in files.into_iter().take(excess) {
+        std::fs::remove_file(file)?;
+    }
+    Ok(())
+}
+
+pub trait LoggingExt<R: Runtime> {
+    fn log_state(&self) -> LogState;
+
+    /// Installs the global logger. Records are written as JSON lines to a daily log file, kept in
+    /// memory for [`LoggingExt::recent_logs`], and mirrored to stdout in debug builds.
+    fn init_logging(&self, level: LogLevel) -> crate::Result<()>;
+    fn set_log_level(&self, level: LogLevel);
+
+    /// Sets the project name attached to subsequent records.
+    fn set_log_project(&self, project: Option<String>);
+    fn recent_logs(&self, filter: LogFilter) -> Vec<LogEntry>;
+}
+
+impl<R: Runtime, T: Manager<R>> LoggingExt<R> for T {
+    fn log_state(&self) -> LogState {
+        if let Some(existing) = self.try_state::<LogState>() {
+            existing.inner().clone()
+        } else {
+            self.manage::<LogState>(LogState::default());
+            self.state::<LogState>().inner().clone()
+        }
+    }
+
+    fn init_logging(&self, level: LogLevel) -> crate::Result<()> {
+        let dir = logs_dir()?;
+        std::fs::create_dir_all(&dir)?;
+        if let Err(err) = prune_logs(&dir) {
+            eprintln!("Failed to prune old log files: {err}");
+        }
+
+        let state = self.log_state();
+        let file_state = state.clone();
+        let file = fern::Dispatch::new()
+            .format(move |out, _, record| {
+                let entry = file_state.entry(record);
+                match serde_json::to_string(&entry) {
+                    Ok(line) => out.finish(format_args!("{line}")),
+                    Err(_) => out.finish(format_args!("{}", entry.message)),
+                }
+            })
+            .chain(fern::DateBased::new(dir.join("carcosa-"), "%Y-%m-%d.log").utc_time());
+        let buffer_state = state.clone();
+        let buffer =
+            fern::Output::call(move |record| buffer_state.push(buffer_state.entry(record)));
+
+        let dispatch = fern::Dispatch::new()
+            // Filtering happens through `log::set_max_level`, so the level can change at runtime.
+            .level(log::LevelFilter::Trace)
+            .level_for("iroh", log::LevelFilter::Info)
+            .level_for("quinn", log::LevelFilter::Warn)
+            .chain(file)
+            .chain(buffer);
+
+        #[cfg(debug_assertions)]
+        let dispatch = {
+            use fern::colors::ColoredLevelConfig;
+            let colors = ColoredLevelConfig::new();
+            dispatch.chain(
+                fern::Dispatch::new()
+                    .format(move |out, message, record| {
+                        out.finish(format_args!(
+                            "{} {:<5} [{}] {message}",
+                            chrono::Local::now().format("%H:%M:%S%.3f"),
+                            colors.color(record.level()),
+                            record.target(),
+                        ))
+                    })
+                    .chain(std::io::stdout()),
+            )
+        };
+
+        dispatch.apply()?;
+        self.set_log_level(level);
+        Ok(())
+    }
+
+    fn set_log_level(&self, level: LogLevel) {
+        log::set_max_level(level.into());
+    }
+
+    fn set_log_project(&self, project: Option<String>) {
+        *self.log_state().project.write() = project;
+    }
+
+    fn recent_logs(&self, filter: LogFilter) -> Vec<LogEntry> {
+        let recent = self.log_state().recent.read().clone();
+        let mut entries = recent
+            .into_iter()
+            .rev()
+            .filter(|entry| filter.matches(entry))
+            .take(
+                filter
+                    .limit
+                    .map(|limit| limit as usize)
+                    .unwrap_or(usize::MAX),
+            )
+            .collect::<Vec<_>>();
+        entries.reverse();
+        entries
+    }
+}
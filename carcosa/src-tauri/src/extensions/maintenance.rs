@@ -0,0 +1,216 @@
+use redb::{MultimapTableHandle, ReadTransaction, TableHandle, WriteTransaction};
+use tauri::{Manager, Runtime};
+
+use crate::{
+    extensions::{
+        assets::{self, AssetTable},
+        databases::{copy_multimap_table_in, copy_table_in},
+        documents,
+        invites::InviteTable,
+        links,
+        maps::{self, AnnotationTable, MapTable},
+        migrations::MetadataTable,
+        relations, search,
+        timeline::{self, TimelineEventTable},
+        world::EntityTable,
+        Database, DatabasesExt, HistoryExt, TypedTable,
+    },
+    types::{IntegrityStatus, MaintenanceReport, TableLoss, PROJECT_DATABASE},
+    MetaError,
+};
+
+/// Copies every table of a corrupted project database that can still be read, then rebuilds the
+/// search and backlink indexes from the salvaged entities.
+fn salvage_project(from: &ReadTransaction, to: &WriteTransaction) -> crate::Result<Vec<TableLoss>> {
+    let copied = [
+        (
+            MetadataTable::NAME.to_string(),
+            copy_table_in(from, to, MetadataTable::definition()),
+        ),
+        (
+            EntityTable::NAME.to_string(),
+            copy_table_in(from, to, EntityTable::definition()),
+        ),
+        (
+            documents::SNAPSHOTS.name().to_string(),
+            copy_table_in(from, to, documents::SNAPSHOTS),
+        ),
+        (
+            documents::UPDATES.name().to_string(),
+            copy_table_in(from, to, documents::UPDATES),
+        ),
+        (
+            relations::OUTGOING.name().to_string(),
+            copy_multimap_table_in(from, to, relations::OUTGOING),
+        ),
+        (
+            relations::INCOMING.name().to_string(),
+            copy_multimap_table_in(from, to, relations::INCOMING),
+        ),
+        (
+            TimelineEventTable::NAME.to_string(),
+            copy_table_in(from, to, TimelineEventTable::definition()),
+        ),
+        (
+            timeline::BY_START.name().to_string(),
+            copy_table_in(from, to, timeline::BY_START),
+        ),
+        (
+            MapTable::NAME.to_string(),
+            copy_table_in(from, to, MapTable::definition()),
+        ),
+        (
+            AnnotationTable::NAME.to_string(),
+            copy_table_in(from, to, AnnotationTable::definition()),
+        ),
+        (
+            maps::MAP_ANNOTATIONS.name().to_string(),
+            copy_multimap_table_in(from, to, maps::MAP_ANNOTATIONS),
+        ),
+        (
+            maps::DERIVED_RELATIONS.name().to_string(),
+            copy_multimap_table_in(from, to, maps::DERIVED_RELATIONS),
+        ),
+        (
+            AssetTable::NAME.to_string(),
+            copy_table_in(from, to, AssetTable::definition()),
+        ),
+        (
+            assets::ASSET_ENTITIES.name().to_string(),
+            copy_multimap_table_in(from, to, assets::ASSET_ENTITIES),
+        ),
+        (
+            assets::ENTITY_ASSETS.name().to_string(),
+            copy_multimap_table_in(from, to, assets::ENTITY_ASSETS),
+        ),
+        (
+            InviteTable::NAME.to_string(),
+            copy_table_in(from, to, InviteTable::definition()),
+        ),
+    ];
+
+    let mut losses = Vec::new();
+    for (table, result) in copied {
+        match result {
+            Ok(rows) => log::info!("Salvaged {rows} rows of {table}"),
+            Err(err) => {
+                log::warn!("Failed to salvage {table}: {err}");
+                losses.push(TableLoss {
+                    table,
+                    error: MetaError::from(&err),
+                });
+            }
+        }
+    }
+    let _ = search::rebuild_in(to)?;
+    links::rebuild_links_in(to)?;
+    Ok(losses)
+}
+
+type Salvage = fn(&ReadTransaction, &WriteTransaction) -> crate::Result<Vec<TableLoss>>;
+
+/// The routine that can rebuild the database called `name`, if there is one.
+fn salvage_routine(name: &str) -> Option<Salvage> {
+    match name {
+        PROJECT_DATABASE => Some(salvage_project),
+        _ => None,
+    }
+}
+
+fn compact(database: &Database) -> crate::Result<MaintenanceReport> {
+    let size_before = database.file_size()?;
+    database.clear_savepoints()?;
+    let mut compacted = false;
+    while database.compact()? {
+        compacted = true;
+    }
+    Ok(MaintenanceReport {
+        database: database.name(),
+        size_before: size_before as f64,
+        size_after: database.file_size()? as f64,
+        compacted: Some(compacted),
+        integrity: None,
+        tables: database.table_rows()?,
+    })
+}
+
+fn check(database: &Database) -> crate::Result<MaintenanceReport> {
+    let size_before = database.file_size()?;
+    let integrity = match database.check_integrity() {
+        Ok(true) => IntegrityStatus::Healthy,
+        Ok(false) => IntegrityStatus::Repaired,
+        Err(err @ crate::Error::Database(redb::Error::Corrupted(_))) => {
+            match salvage_routine(&database.name()) {
+                Some(salvage) => {
+                    log::error!(
+                        "Database {} is corrupted beyond repair, rebuilding it: {err}",
+                        database.name()
+                    );
+                    let (backup, losses) = database.rebuild(salvage)?;
+                    IntegrityStatus::Rebuilt { backup, losses }
+                }
+                None => {
+                    log::error!(
+                        "Database {} is corrupted beyond repair and can't be rebuilt: {err}",
+                        database.name()
+                    );
+                    IntegrityStatus::Corrupted {
+                        error: MetaError::from(&err),
+                    }
+                }
+            }
+        }
+        Err(err) => return Err(err),
+    };
+    Ok(MaintenanceReport {
+        database: database.name(),
+        size_before: size_before as f64,
+        size_after: database.file_size()? as f64,
+        compacted: None,
+        integrity: Some(integrity),
+        tables: database.table_rows()?,
+    })
+}
+
+#[async_trait::async_trait]
+pub trait MaintenanceExt<R: Runtime> {
+    /// Compacts every open database. Savepoints keep freed pages from being reclaimed, so this
+    /// also clears the undo history.
+    async fn compact_databases(&self) -> crate::Result<Vec<MaintenanceReport>>;
+
+    /// Checks every open database for corruption. Databases that can't be repaired in place are
+    /// rebuilt into a fresh file, keeping the original as a backup.
+    async fn check_databases(&self) -> crate::Result<Vec<MaintenanceReport>>;
+}
+
+#[async_trait::async_trait]
+impl<R: Runtime, T: Manager<R> + Sync> MaintenanceExt<R> for T {
+    async fn compact_databases(&self) -> crate::Result<Vec<MaintenanceReport>> {
+        self.app_handle().reset_history();
+        let mut reports = Vec::new();
+        for name in self.list_databases() {
+            let Some(database) = self.get_database(name) else {
+                continue;
+            };
+            let report = tauri::async_runtime::spawn_blocking(move || compact(&database)).await??;
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    async fn check_databases(&self) -> crate::Result<Vec<MaintenanceReport>> {
+        let mut reports = Vec::new();
+        for name in self.list_databases() {
+            let Some(database) = self.get_database(name) else {
+                continue;
+            };
+            let report = tauri::async_runtime::spawn_blocking(move || check(&database)).await??;
+            if let Some(IntegrityStatus::Rebuilt { .. }) = report.integrity {
+                // Savepoints aren't carried over into the rebuilt file.
+                self.app_handle().reset_history();
+            }
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+}
@@ -0,0 +1,805 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageReader};
+use redb::{
+    MultimapTableDefinition, ReadTransaction, ReadableMultimapTable, ReadableTable, TableError,
+    WriteTransaction,
+};
+use tauri::{AppHandle, Manager, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{
+        assets::asset_path, history::HistoryAction, insert_record, relations, remove_record,
+        sync::SyncMessage, table, world::ensure_hosted, ApplicationExt, AssetsExt, DatabasesExt,
+        HistoryExt, RecordTableExt, SyncExt, TypedTable, WorldExt, WriteTransactionExt,
+    },
+    types::{AnnotationShape, MapAnnotation, MapInfo, ProjectSettings, Relation, RelationKind},
+};
+
+table!(pub MapTable: "maps.maps", Uuid => MapInfo);
+table!(pub AnnotationTable: "maps.annotations", Uuid => MapAnnotation);
+
+/// Map => every annotation on it.
+pub(crate) const MAP_ANNOTATIONS: MultimapTableDefinition<Uuid, Uuid> =
+    MultimapTableDefinition::new("maps.map_annotations");
+
+/// Map => (source, target) of every "located in" relation derived from its pins, so they can be
+/// removed again when the pins move.
+pub(crate) const DERIVED_RELATIONS: MultimapTableDefinition<Uuid, (Uuid, Uuid)> =
+    MultimapTableDefinition::new("maps.derived_relations");
+
+pub const TILE_SIZE: u32 = 256;
+
+fn tiles_dir(project: impl AsRef<Path>, map: Uuid) -> PathBuf {
+    ProjectSettings::data_dir(project)
+        .join("tiles")
+        .join(map.to_string())
+}
+
+fn tile_path(project: impl AsRef<Path>, map: Uuid, level: u32, x: u32, y: u32) -> PathBuf {
+    tiles_dir(project, map)
+        .join(level.to_string())
+        .join(format!("{x}_{y}.png"))
+}
+
+/// Cuts `image` into tiles in `dir`, halving it after every level until it fits in one tile.
+/// Returns the number of levels written.
+fn build_pyramid(image: DynamicImage, dir: &Path) -> crate::Result<u32> {
+    let mut image = image;
+    let mut level = 0;
+    loop {
+        let level_dir = dir.join(level.to_string());
+        fs::create_dir_all(&level_dir)?;
+        for y in (0..image.height()).step_by(TILE_SIZE as usize) {
+            for x in (0..image.width()).step_by(TILE_SIZE as usize) {
+                let tile = image.crop_imm(
+                    x,
+                    y,
+                    TILE_SIZE.min(image.width() - x),
+                    TILE_SIZE.min(image.height() - y),
+                );
+                tile.save_with_format(
+                    level_dir.join(format!("{}_{}.png", x / TILE_SIZE, y / TILE_SIZE)),
+                    ImageFormat::Png,
+                )?;
+            }
+        }
+        level += 1;
+        if image.width() <= TILE_SIZE && image.height() <= TILE_SIZE {
+            return Ok(level);
+        }
+        image = image.resize_exact(
+            (image.width() / 2).max(1),
+            (image.height() / 2).max(1),
+            FilterType::Triangle,
+        );
+    }
+}
+
+/// Tiles `image` for `map`. The tiles are built next to their final directory and moved into
+/// place once complete, so a half-built pyramid is never served. Every build gets a directory of
+/// its own, since several tile requests may start tiling the same map at once.
+fn build_tiles(image: DynamicImage, project: &Path, map: Uuid) -> crate::Result<u32> {
+    let target = tiles_dir(project, map);
+    let partial = target.with_extension(format!("partial-{}", Uuid::now_v7()));
+    let levels = match build_pyramid(image, &partial) {
+        Ok(levels) => levels,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&partial);
+            return Err(err);
+        }
+    };
+    if let Err(err) = fs::rename(&partial, &target) {
+        let _ = fs::remove_dir_all(&partial);
+        // Someone else finished tiling the same map first.
+        if !target.exists() {
+            return Err(err.into());
+        }
+    }
+    Ok(levels)
+}
+
+/// Tiles a map's image unless that's been done already, ie. on guests, which only receive the
+/// image itself.
+fn ensure_tiles(project: &Path, map: &MapInfo) -> crate::Result<()> {
+    if tiles_dir(project, map.id).exists() {
+        return Ok(());
+    }
+    let image = ImageReader::open(asset_path(project, &map.image)?)?
+        .with_guessed_format()?
+        .decode()?;
+    let _ = build_tiles(image, project, map.id)?;
+    Ok(())
+}
+
+pub(crate) fn remove_tiles(project: impl AsRef<Path>, map: Uuid) {
+    let _ = fs::remove_dir_all(tiles_dir(project, map));
+}
+
+fn annotations_in(txn: &WriteTransaction, map: Uuid) -> crate::Result<Vec<MapAnnotation>> {
+    let index = txn.open_multimap_table(MAP_ANNOTATIONS)?;
+    let table = txn.open_table(AnnotationTable::definition())?;
+    let mut annotations = Vec::new();
+    for id in index.get(map)? {
+        if let Some(annotation) =
+            RecordTableExt::<AnnotationTable>::get_record(&table, &id?.value())?
+        {
+            annotations.push(annotation);
+        }
+    }
+    Ok(annotations)
+}
+
+/// Stores an annotation under its map.
+pub(crate) fn insert_annotation_in(
+    txn: &WriteTransaction,
+    annotation: &MapAnnotation,
+) -> crate::Result<()> {
+    let mut table = txn.open_table(AnnotationTable::definition())?;
+    let _ = insert_record::<AnnotationTable>(&mut table, &annotation.id, annotation)?;
+    let _ = txn
+        .open_multimap_table_mut(MAP_ANNOTATIONS)?
+        .insert(annotation.map, annotation.id)?;
+    Ok(())
+}
+
+pub(crate) fn remove_annotation_in(
+    txn: &WriteTransaction,
+    id: Uuid,
+) -> crate::Result<Option<MapAnnotation>> {
+    let mut table = txn.open_table(AnnotationTable::definition())?;
+    let removed = remove_record::<AnnotationTable>(&mut table, &id)?;
+    if let Some(removed) = removed.as_ref() {
+        let _ = txn
+            .open_multimap_table_mut(MAP_ANNOTATIONS)?
+            .remove(removed.map, id)?;
+    }
+    Ok(removed)
+}
+
+/// Removes a map along with every annotation on it.
+pub(crate) fn remove_map_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<Option<MapInfo>> {
+    let ids = annotations_in(txn, id)?
+        .into_iter()
+        .map(|annotation| annotation.id)
+        .collect::<Vec<_>>();
+    let mut annotations = txn.open_table(AnnotationTable::definition())?;
+    for annotation in ids {
+        let _ = remove_record::<AnnotationTable>(&mut annotations, &annotation)?;
+    }
+    let _ = txn
+        .open_multimap_table_mut(MAP_ANNOTATIONS)?
+        .remove_all(id)?;
+    let mut maps = txn.open_table(MapTable::definition())?;
+    remove_record::<MapTable>(&mut maps, &id)
+}
+
+/// Every annotation on `map`.
+pub(crate) fn map_annotations_in(
+    txn: &ReadTransaction,
+    map: Uuid,
+) -> crate::Result<Vec<MapAnnotation>> {
+    let (index, table) = match (
+        txn.open_multimap_table(MAP_ANNOTATIONS),
+        txn.open_table(AnnotationTable::definition()),
+    ) {
+        (Ok(index), Ok(table)) => (index, table),
+        (Err(TableError::TableDoesNotExist(_)), _) | (_, Err(TableError::TableDoesNotExist(_))) => {
+            return Ok(Vec::new())
+        }
+        (Err(err), _) | (_, Err(err)) => return Err(err.into()),
+    };
+    let mut annotations = Vec::new();
+    for id in index.get(map)? {
+        if let Some(annotation) =
+            RecordTableExt::<AnnotationTable>::get_record(&table, &id?.value())?
+        {
+            annotations.push(annotation);
+        }
+    }
+    Ok(annotations)
+}
+
+/// Every map and every annotation.
+pub(crate) fn all_in(txn: &ReadTransaction) -> crate::Result<(Vec<MapInfo>, Vec<MapAnnotation>)> {
+    let (maps, annotations) = match (
+        txn.open_table(MapTable::definition()),
+        txn.open_table(AnnotationTable::definition()),
+    ) {
+        (Ok(maps), Ok(annotations)) => (maps, annotations),
+        (Err(TableError::TableDoesNotExist(_)), _) | (_, Err(TableError::TableDoesNotExist(_))) => {
+            return Ok((Vec::new(), Vec::new()))
+        }
+        (Err(err), _) | (_, Err(err)) => return Err(err.into()),
+    };
+    Ok((
+        RecordTableExt::<MapTable>::range_records(&maps, ..)?
+            .into_iter()
+            .map(|(_, map)| map)
+            .collect(),
+        RecordTableExt::<AnnotationTable>::range_records(&annotations, ..)?
+            .into_iter()
+            .map(|(_, annotation)| annotation)
+            .collect(),
+    ))
+}
+
+/// Replaces every stored map and annotation with `maps` and `annotations`.
+pub(crate) fn replace_all_in(
+    txn: &WriteTransaction,
+    maps: &[MapInfo],
+    annotations: &[MapAnnotation],
+) -> crate::Result<()> {
+    {
+        let mut table = txn.open_table_mut(MapTable::definition())?;
+        table.retain(|_, _| false)?;
+        for map in maps {
+            let _ = insert_record::<MapTable>(&mut table, &map.id, map)?;
+        }
+    }
+    txn.open_table_mut(AnnotationTable::definition())?
+        .retain(|_, _| false)?;
+    let _ = txn.clear_multimap_table(MAP_ANNOTATIONS)?;
+    for annotation in annotations {
+        insert_annotation_in(txn, annotation)?;
+    }
+    Ok(())
+}
+
+/// The images every map is drawn from, which have to be kept in the asset store.
+pub(crate) fn images_in(txn: &WriteTransaction) -> crate::Result<HashSet<String>> {
+    let table = txn.open_table(MapTable::definition())?;
+    let mut images = HashSet::new();
+    for entry in table.iter()? {
+        let (_, map) = entry?;
+        let _ = images.insert(MapTable::decode(map.value())?.image);
+    }
+    Ok(images)
+}
+
+/// The "located in" relations implied by a map's pins. A pin is located in every linked region
+/// containing it, or in the map's own location if there are none.
+fn implied_relations(map: &MapInfo, annotations: &[MapAnnotation]) -> HashSet<Relation> {
+    let mut implied = HashSet::new();
+    for pin in annotations {
+        let (Some(entity), AnnotationShape::Pin { at }) = (pin.entity, &pin.shape) else {
+            continue;
+        };
+        let mut containers = annotations
+            .iter()
+            .filter(|region| region.shape.contains(*at))
+            .filter_map(|region| region.entity)
+            .collect::<Vec<_>>();
+        if containers.is_empty() {
+            containers.extend(map.location);
+        }
+        implied.extend(
+            containers
+                .into_iter()
+                .filter(|container| *container != entity)
+                .map(|container| Relation {
+                    source: entity,
+                    target: container,
+                    kind: RelationKind::LocatedIn,
+                }),
+        );
+    }
+    implied
+}
+
+/// Brings the relations derived from `map`'s pins up to date, returning the added and removed
+/// relations. Relations that already existed before a pin implied them are left alone.
+fn derive_relations_in(
+    txn: &WriteTransaction,
+    map: Uuid,
+) -> crate::Result<(Vec<Relation>, Vec<Relation>)> {
+    let info = {
+        let table = txn.open_table(MapTable::definition())?;
+        RecordTableExt::<MapTable>::get_record(&table, &map)?
+    };
+    let implied = match info.as_ref() {
+        Some(info) => implied_relations(info, &annotations_in(txn, map)?),
+        None => HashSet::new(),
+    };
+
+    let mut derived = HashSet::new();
+    {
+        let table = txn.open_multimap_table(DERIVED_RELATIONS)?;
+        for value in table.get(map)? {
+            let (source, target) = value?.value();
+            let _ = derived.insert(Relation {
+                source,
+                target,
+                kind: RelationKind::LocatedIn,
+            });
+        }
+    }
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for relation in derived.difference(&implied) {
+        let _ = txn
+            .open_multimap_table_mut(DERIVED_RELATIONS)?
+            .remove(map, (relation.source, relation.target))?;
+        if relations::remove_in(txn, relation)? {
+            removed.push(relation.clone());
+        }
+    }
+    for relation in implied.difference(&derived) {
+        if relations::insert_in(txn, relation)? {
+            let _ = txn
+                .open_multimap_table_mut(DERIVED_RELATIONS)?
+                .insert(map, (relation.source, relation.target))?;
+            added.push(relation.clone());
+        }
+    }
+    Ok((added, removed))
+}
+
+/// What a map operation changed, to be sent on to guests once it's committed.
+#[derive(Debug, Default)]
+pub(crate) struct MapChanges {
+    maps: Vec<MapInfo>,
+    annotations: Vec<MapAnnotation>,
+    added: Vec<Relation>,
+    removed: Vec<Relation>,
+}
+
+impl MapChanges {
+    /// Brings the relations derived from `map`'s pins up to date.
+    fn derive(&mut self, txn: &WriteTransaction, map: Uuid) -> crate::Result<()> {
+        let (added, removed) = derive_relations_in(txn, map)?;
+        self.added.extend(added);
+        self.removed.extend(removed);
+        Ok(())
+    }
+
+    pub(crate) fn broadcast<R: Runtime>(self, app: &AppHandle<R>) {
+        for map in self.maps {
+            app.broadcast_message(SyncMessage::MapChanged { map }, None);
+        }
+        for annotation in self.annotations {
+            app.broadcast_message(SyncMessage::AnnotationChanged { annotation }, None);
+        }
+        for relation in self.removed {
+            app.broadcast_message(SyncMessage::RelationRemoved { relation }, None);
+        }
+        for relation in self.added {
+            app.broadcast_message(SyncMessage::RelationAdded { relation }, None);
+        }
+    }
+}
+
+/// Unlinks a deleted entity from every map and annotation.
+pub(crate) fn detach_entity_in(txn: &WriteTransaction, entity: Uuid) -> crate::Result<MapChanges> {
+    let mut changes = MapChanges::default();
+    {
+        let mut table = txn.open_table(MapTable::definition())?;
+        let located = RecordTableExt::<MapTable>::range_records(&table, ..)?
+            .into_iter()
+            .filter(|(_, map)| map.location == Some(entity))
+            .collect::<Vec<_>>();
+        for (id, map) in located {
+            let map = MapInfo {
+                location: None,
+                ..map
+            };
+            let _ = insert_record::<MapTable>(&mut table, &id, &map)?;
+            changes.maps.push(map);
+        }
+    }
+    {
+        let mut table = txn.open_table(AnnotationTable::definition())?;
+        let linked = RecordTableExt::<AnnotationTable>::range_records(&table, ..)?
+            .into_iter()
+            .filter(|(_, annotation)| annotation.entity == Some(entity))
+            .collect::<Vec<_>>();
+        for (id, annotation) in linked {
+            let annotation = MapAnnotation {
+                entity: None,
+                ..annotation
+            };
+            let _ = insert_record::<AnnotationTable>(&mut table, &id, &annotation)?;
+            changes.annotations.push(annotation);
+        }
+    }
+    let changed = changes
+        .maps
+        .iter()
+        .map(|map| map.id)
+        .chain(changes.annotations.iter().map(|annotation| annotation.map))
+        .collect::<HashSet<_>>();
+    for map in changed {
+        changes.derive(txn, map)?;
+    }
+    Ok(changes)
+}
+
+pub trait MapsExt<R: Runtime> {
+    /// Stores an image as an asset, tiles it, and registers it as a new map.
+    fn import_map(&self, name: String, source: PathBuf) -> crate::Result<MapInfo>;
+    fn get_map(&self, id: Uuid) -> crate::Result<MapInfo>;
+
+    /// Updates a map's name, layers and location. Its image can't be changed.
+    fn update_map(&self, map: MapInfo) -> crate::Result<MapInfo>;
+    fn delete_map(&self, id: Uuid) -> crate::Result<MapInfo>;
+
+    /// Reads a tile, cutting the map's image into tiles first if that hasn't been done on this
+    /// machine yet. The image has to be stored locally for that.
+    fn map_tile(&self, id: Uuid, level: u32, x: u32, y: u32) -> crate::Result<Vec<u8>>;
+
+    fn add_annotation(&self, annotation: MapAnnotation) -> crate::Result<MapAnnotation>;
+
+    /// Stores a new annotation under the ID it already has, ie. when redoing its creation.
+    fn insert_annotation(&self, annotation: MapAnnotation) -> crate::Result<MapAnnotation>;
+    fn update_annotation(&self, annotation: MapAnnotation) -> crate::Result<MapAnnotation>;
+    fn remove_annotation(&self, id: Uuid) -> crate::Result<MapAnnotation>;
+}
+
+impl<R: Runtime, T: Manager<R>> MapsExt<R> for T {
+    fn import_map(&self, name: String, source: PathBuf) -> crate::Result<MapInfo> {
+        ensure_hosted::<R>(self)?;
+        let project = self
+            .get_app_state()
+            .active_project()
+            .path()
+            .ok_or(crate::Error::NoActiveProject)?;
+        let id = Uuid::now_v7();
+
+        let image = ImageReader::open(&source)?
+            .with_guessed_format()?
+            .decode()?;
+        let mut map = MapInfo {
+            id,
+            name,
+            image: String::new(),
+            width: image.width(),
+            height: image.height(),
+            tile_size: TILE_SIZE,
+            levels: 0,
+            layers: Vec::new(),
+            location: None,
+            created: Utc::now(),
+        };
+        map.validate()?;
+
+        // An image that fails to tile is left for the asset garbage collector.
+        map.image = self.import_asset(source)?.hash;
+        map.levels = build_tiles(image, &project, id)?;
+        if let Err(err) = self.project_database()?.insert::<MapTable>(&id, &map) {
+            remove_tiles(&project, id);
+            return Err(err);
+        }
+        // Undoing past the import would drop the map while its tiles and image stay behind.
+        self.app_handle().reset_history();
+        self.app_handle()
+            .broadcast_message(SyncMessage::MapChanged { map: map.clone() }, None);
+        Ok(map)
+    }
+
+    fn get_map(&self, id: Uuid) -> crate::Result<MapInfo> {
+        self.project_database()?
+            .get::<MapTable>(&id)?
+            .ok_or_else(|| crate::Error::not_found("map", id))
+    }
+
+    fn update_map(&self, map: MapInfo) -> crate::Result<MapInfo> {
+        ensure_hosted::<R>(self)?;
+        let existing = self.get_map(map.id)?;
+        if let Some(location) = map.location {
+            let _ = self.get_entity(location)?;
+        }
+        let relocated = existing.location != map.location;
+        let updated = MapInfo {
+            name: map.name,
+            layers: map.layers,
+            location: map.location,
+            ..existing
+        };
+        updated.validate()?;
+
+        let layers = updated
+            .layers
+            .iter()
+            .map(|layer| layer.id)
+            .collect::<HashSet<_>>();
+        let changes = self.app_handle().undoable(
+            format!("Edit {}", existing.name),
+            HistoryAction::UpdateMap {
+                map: updated.clone(),
+            },
+            || {
+                self.project_database()?
+                    .write_transaction(|txn| -> crate::Result<MapChanges> {
+                        let mut changes = MapChanges::default();
+                        // Annotations on removed layers fall back to the base layer.
+                        let orphaned = annotations_in(txn, updated.id)?
+                            .into_iter()
+                            .filter(|annotation| {
+                                annotation
+                                    .layer
+                                    .is_some_and(|layer| !layers.contains(&layer))
+                            })
+                            .collect::<Vec<_>>();
+                        for annotation in orphaned {
+                            let annotation = MapAnnotation {
+                                layer: None,
+                                ..annotation
+                            };
+                            insert_annotation_in(txn, &annotation)?;
+                            changes.annotations.push(annotation);
+                        }
+                        {
+                            let mut maps = txn.open_table(MapTable::definition())?;
+                            let _ = insert_record::<MapTable>(&mut maps, &updated.id, &updated)?;
+                        }
+                        changes.maps.push(updated.clone());
+                        if relocated {
+                            changes.derive(txn, updated.id)?;
+                        }
+                        Ok(changes)
+                    })?
+            },
+        )?;
+        changes.broadcast(self.app_handle());
+        Ok(updated)
+    }
+
+    fn delete_map(&self, id: Uuid) -> crate::Result<MapInfo> {
+        ensure_hosted::<R>(self)?;
+        let existing = self.get_map(id)?;
+        let (map, changes) = self.app_handle().undoable(
+            format!("Delete {}", existing.name),
+            HistoryAction::DeleteMap { id },
+            || {
+                self.project_database()?.write_transaction(
+                    |txn| -> crate::Result<(MapInfo, MapChanges)> {
+                        let map = remove_map_in(txn, id)?
+                            .ok_or_else(|| crate::Error::not_found("map", id))?;
+                        let mut changes = MapChanges::default();
+                        changes.derive(txn, id)?;
+                        Ok((map, changes))
+                    },
+                )?
+            },
+        )?;
+        self.app_handle()
+            .broadcast_message(SyncMessage::MapRemoved { id }, None);
+        changes.broadcast(self.app_handle());
+
+        // The image stays in the asset store until the garbage collector finds it unused, and the
+        // tiles are cut again if the deletion is undone.
+        if let Some(project) = self.get_app_state().active_project().path() {
+            remove_tiles(project, id);
+        }
+        Ok(map)
+    }
+
+    fn map_tile(&self, id: Uuid, level: u32, x: u32, y: u32) -> crate::Result<Vec<u8>> {
+        let map = self.get_map(id)?;
+        let (columns, rows) = map.tiles_at(level);
+        if level >= map.levels || x >= columns || y >= rows {
+            return Err(crate::Error::not_found(
+                "map tile",
+                format!("{id}/{level}/{x}_{y}"),
+            ));
+        }
+        let project = self
+            .get_app_state()
+            .active_project()
+            .path()
+            .ok_or(crate::Error::NoActiveProject)?;
+        ensure_tiles(&project, &map)?;
+        Ok(fs::read(tile_path(project, id, level, x, y))?)
+    }
+
+    fn add_annotation(&self, annotation: MapAnnotation) -> crate::Result<MapAnnotation> {
+        ensure_hosted::<R>(self)?;
+        self.insert_annotation(MapAnnotation {
+            id: Uuid::now_v7(),
+            ..annotation
+        })
+    }
+
+    fn insert_annotation(&self, annotation: MapAnnotation) -> crate::Result<MapAnnotation> {
+        ensure_hosted::<R>(self)?;
+        annotation.validate(&self.get_map(annotation.map)?)?;
+        if let Some(entity) = annotation.entity {
+            let _ = self.get_entity(entity)?;
+        }
+        let db = self.project_database()?;
+        if db.get::<AnnotationTable>(&annotation.id)?.is_some() {
+            return Err(crate::Error::validation(
+                "id",
+                format!("An annotation with ID {} already exists", annotation.id),
+            ));
+        }
+        let changes = self.app_handle().undoable(
+            "Add annotation".to_owned(),
+            HistoryAction::AddAnnotation {
+                annotation: annotation.clone(),
+            },
+            || {
+                db.write_transaction(|txn| -> crate::Result<MapChanges> {
+                    insert_annotation_in(txn, &annotation)?;
+                    let mut changes = MapChanges::default();
+                    changes.annotations.push(annotation.clone());
+                    changes.derive(txn, annotation.map)?;
+                    Ok(changes)
+                })?
+            },
+        )?;
+        changes.broadcast(self.app_handle());
+        Ok(annotation)
+    }
+
+    fn update_annotation(&self, annotation: MapAnnotation) -> crate::Result<MapAnnotation> {
+        ensure_hosted::<R>(self)?;
+        let existing = self
+            .project_database()?
+            .get::<AnnotationTable>(&annotation.id)?
+            .ok_or_else(|| crate::Error::not_found("annotation", annotation.id))?;
+        if existing.map != annotation.map {
+            return Err(crate::Error::validation(
+                "map",
+                "Annotations cannot be moved to another map",
+            ));
+        }
+        annotation.validate(&self.get_map(annotation.map)?)?;
+        if let Some(entity) = annotation.entity {
+            let _ = self.get_entity(entity)?;
+        }
+        let changes = self.app_handle().undoable(
+            "Edit annotation".to_owned(),
+            HistoryAction::UpdateAnnotation {
+                annotation: annotation.clone(),
+            },
+            || {
+                self.project_database()?
+                    .write_transaction(|txn| -> crate::Result<MapChanges> {
+                        insert_annotation_in(txn, &annotation)?;
+                        let mut changes = MapChanges::default();
+                        changes.annotations.push(annotation.clone());
+                        changes.derive(txn, annotation.map)?;
+                        Ok(changes)
+                    })?
+            },
+        )?;
+        changes.broadcast(self.app_handle());
+        Ok(annotation)
+    }
+
+    fn remove_annotation(&self, id: Uuid) -> crate::Result<MapAnnotation> {
+        ensure_hosted::<R>(self)?;
+        let (removed, changes) = self.app_handle().undoable(
+            "Remove annotation".to_owned(),
+            HistoryAction::RemoveAnnotation { id },
+            || {
+                self.project_database()?.write_transaction(
+                    |txn| -> crate::Result<(MapAnnotation, MapChanges)> {
+                        let removed = remove_annotation_in(txn, id)?
+                            .ok_or_else(|| crate::Error::not_found("annotation", id))?;
+                        let mut changes = MapChanges::default();
+                        changes.derive(txn, removed.map)?;
+                        Ok((removed, changes))
+                    },
+                )?
+            },
+        )?;
+        self.app_handle()
+            .broadcast_message(SyncMessage::AnnotationRemoved { id }, None);
+        changes.broadcast(self.app_handle());
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MapPoint;
+
+    fn map(location: Option<Uuid>) -> MapInfo {
+        MapInfo {
+            id: Uuid::now_v7(),
+            name: String::from("Carcosa"),
+            image: "0".repeat(64),
+            width: 100,
+            height: 100,
+            tile_size: TILE_SIZE,
+            levels: 1,
+            layers: Vec::new(),
+            location,
+            created: Utc::now(),
+        }
+    }
+
+    fn annotation(map: &MapInfo, entity: Option<Uuid>, shape: AnnotationShape) -> MapAnnotation {
+        MapAnnotation {
+            id: Uuid::now_v7(),
+            map: map.id,
+            layer: None,
+            label: None,
+            entity,
+            shape,
+        }
+    }
+
+    fn pin(x: f64, y: f64) -> AnnotationShape {
+        AnnotationShape::Pin {
+            at: MapPoint { x, y },
+        }
+    }
+
+    fn square(from: f64, to: f64) -> AnnotationShape {
+        AnnotationShape::Region {
+            points: vec![
+                MapPoint { x: from, y: from },
+                MapPoint { x: to, y: from },
+                MapPoint { x: to, y: to },
+                MapPoint { x: from, y: to },
+            ],
+        }
+    }
+
+    fn located_in(source: Uuid, target: Uuid) -> Relation {
+        Relation {
+            source,
+            target,
+            kind: RelationKind::LocatedIn,
+        }
+    }
+
+    #[test]
+    fn locates_pins_in_every_region_containing_them() {
+        let map = map(Some(Uuid::now_v7()));
+        let (city, district, province) = (Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7());
+        let annotations = [
+            annotation(&map, Some(city), pin(25.0, 25.0)),
+            annotation(&map, Some(district), square(20.0, 30.0)),
+            annotation(&map, Some(province), square(0.0, 50.0)),
+            annotation(&map, None, square(0.0, 100.0)),
+        ];
+        assert_eq!(
+            implied_relations(&map, &annotations),
+            HashSet::from([located_in(city, district), located_in(city, province)])
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_map_location() {
+        let region = Uuid::now_v7();
+        let located = map(Some(region));
+        let city = Uuid::now_v7();
+        let annotations = [annotation(&located, Some(city), pin(75.0, 75.0))];
+        assert_eq!(
+            implied_relations(&located, &annotations),
+            HashSet::from([located_in(city, region)])
+        );
+        assert!(implied_relations(&map(None), &annotations).is_empty());
+    }
+
+    #[test]
+    fn ignores_unlinked_pins_and_self_containment() {
+        let map = map(None);
+        let city = Uuid::now_v7();
+        let annotations = [
+            annotation(&map, None, pin(25.0, 25.0)),
+            annotation(&map, Some(city), pin(10.0, 10.0)),
+            annotation(&map, Some(city), square(0.0, 50.0)),
+            annotation(
+                &map,
+                Some(Uuid::now_v7()),
+                AnnotationShape::Path {
+                    points: vec![MapPoint { x: 0.0, y: 0.0 }, MapPoint { x: 50.0, y: 50.0 }],
+                },
+            ),
+        ];
+        assert!(implied_relations(&map, &annotations).is_empty());
+    }
+}
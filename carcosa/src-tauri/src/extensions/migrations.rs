@@ -0,0 +1,124 @@
+use redb::WriteTransaction;
+
+use crate::extensions::{
+    insert_record, links, search, table, Database, RecordTableExt, TypedTable,
+};
+
+table!(
+    /// Per-database metadata, such as the current schema version.
+    pub MetadataTable: "carcosa.metadata", String => serde_json::Value
+);
+
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// A single schema migration. `apply` runs inside the shared migration transaction, and moves
+/// the database from `version - 1` to `version`.
+#[derive(Clone, Copy, Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub apply: fn(&WriteTransaction) -> crate::Result<()>,
+}
+
+/// Migrations for the per-project database, in ascending version order.
+pub const PROJECT_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Initialize project metadata",
+        apply: initialize_project,
+    },
+    Migration {
+        version: 2,
+        description: "Build the search index",
+        apply: build_search_index,
+    },
+    Migration {
+        version: 3,
+        description: "Build the backlinks index",
+        apply: links::rebuild_links_in,
+    },
+];
+
+fn initialize_project(txn: &WriteTransaction) -> crate::Result<()> {
+    let _ = txn.open_table(MetadataTable::definition())?;
+    Ok(())
+}
+
+fn build_search_index(txn: &WriteTransaction) -> crate::Result<()> {
+    let _ = search::rebuild_in(txn)?;
+    Ok(())
+}
+
+fn read_version(txn: &WriteTransaction) -> crate::Result<u32> {
+    let table = txn.open_table(MetadataTable::definition())?;
+    match RecordTableExt::<MetadataTable>::get_record(&table, &SCHEMA_VERSION_KEY.to_string())? {
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| {
+                crate::Error::validation(SCHEMA_VERSION_KEY, "Stored schema version is invalid")
+            }),
+        None => Ok(0),
+    }
+}
+
+fn write_version(txn: &WriteTransaction, version: u32) -> crate::Result<()> {
+    let mut table = txn.open_table(MetadataTable::definition())?;
+    let _ = insert_record::<MetadataTable>(
+        &mut table,
+        &SCHEMA_VERSION_KEY.to_string(),
+        &serde_json::Value::from(version),
+    )?;
+    Ok(())
+}
+
+impl Database {
+    pub fn schema_version(&self) -> crate::Result<u32> {
+        let version = self.get::<MetadataTable>(&SCHEMA_VERSION_KEY.to_string())?;
+        Ok(version.and_then(|v| v.as_u64()).unwrap_or(0) as u32)
+    }
+
+    /// Applies every migration newer than the stored schema version inside a single write
+    /// transaction. If any step fails nothing is committed. `on_step` is called before each step
+    /// with the migration and its position among the pending steps.
+    ///
+    /// Returns the schema version after migrating.
+    pub fn migrate(
+        &self,
+        migrations: &[Migration],
+        mut on_step: impl FnMut(&Migration, u32, u32),
+    ) -> crate::Result<u32> {
+        if let Some(pair) = migrations.windows(2).find(|w| w[0].version >= w[1].version) {
+            return Err(crate::Error::migration(
+                pair[1].version,
+                "Migrations are not registered in ascending version order",
+            ));
+        }
+        let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+
+        self.write_transaction(|txn| {
+            let current = read_version(txn)?;
+            if current > latest {
+                return Err(crate::Error::migration(
+                    current,
+                    format!(
+                        "Database schema is newer than this version of Carcosa supports ({latest})"
+                    ),
+                ));
+            }
+
+            let pending = migrations
+                .iter()
+                .filter(|m| m.version > current)
+                .collect::<Vec<_>>();
+            for (step, migration) in pending.iter().enumerate() {
+                on_step(migration, step as u32 + 1, pending.len() as u32);
+                (migration.apply)(txn)
+                    .map_err(|err| crate::Error::migration(migration.version, err.to_string()))?;
+                write_version(txn, migration.version)?;
+            }
+
+            Ok(latest)
+        })?
+    }
+}
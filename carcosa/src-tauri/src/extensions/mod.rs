@@ -1,5 +1,65 @@
 pub mod databases;
-pub use databases::{Database, DatabasesExt, TableName};
+pub use databases::{Database, DatabasesExt, TableName, Transaction, TransactionBuilder};
+pub(crate) use databases::WriteTransactionExt;
+
+pub mod tables;
+pub use tables::{insert_record, remove_record, RecordKey, RecordTableExt, TypedTable};
+pub(crate) use tables::table;
+
+pub mod migrations;
+pub use migrations::{Migration, PROJECT_MIGRATIONS};
 
 pub mod app;
-pub use app::{ApplicationExt};
\ No newline at end of file
+pub use app::{app_dirs, ApplicationExt};
+
+pub mod world;
+pub use world::WorldExt;
+
+pub mod templates;
+pub use templates::TemplatesExt;
+
+pub mod documents;
+pub use documents::DocumentsExt;
+
+pub mod sync;
+pub use sync::SyncExt;
+
+pub mod invites;
+pub use invites::InvitesExt;
+
+pub mod recent;
+pub use recent::RecentProjectsExt;
+
+pub mod logging;
+pub use logging::LoggingExt;
+
+pub mod search;
+
+pub mod relations;
+pub use relations::RelationsExt;
+
+pub mod links;
+
+pub mod calendars;
+pub use calendars::CalendarsExt;
+
+pub mod timeline;
+pub use timeline::TimelineExt;
+
+pub mod maps;
+pub use maps::MapsExt;
+
+pub mod assets;
+pub use assets::AssetsExt;
+
+pub mod transfers;
+pub use transfers::TransfersExt;
+
+pub mod history;
+pub use history::HistoryExt;
+
+pub mod maintenance;
+pub use maintenance::MaintenanceExt;
+
+pub mod changes;
+pub use changes::ChangeFeedExt;
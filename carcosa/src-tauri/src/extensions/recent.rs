@@ -0,0 +1,75 @@
+use std::{path::PathBuf, sync::Arc};
+
+use parking_lot::RwLock;
+use tauri::{Manager, Runtime};
+
+use crate::{
+    extensions::app_dirs,
+    types::{ActiveProject, RecentProject, RecentProjects},
+};
+
+pub type RecentProjectsState = Arc<RwLock<RecentProjects>>;
+
+fn recent_projects_path() -> crate::Result<PathBuf> {
+    Ok(app_dirs()?.config_dir.join("recent_projects.json"))
+}
+
+pub trait RecentProjectsExt<R: Runtime> {
+    fn recent_projects_state(&self) -> crate::Result<RecentProjectsState>;
+    fn recent_projects(&self) -> crate::Result<Vec<RecentProject>>;
+    fn recent_project(&self, path: impl Into<PathBuf>) -> crate::Result<RecentProject>;
+    fn update_recent_projects(
+        &self,
+        updater: impl FnOnce(RecentProjects) -> crate::Result<RecentProjects>,
+    ) -> crate::Result<Vec<RecentProject>>;
+    fn remember_project(
+        &self,
+        project: &ActiveProject,
+        name: impl Into<String>,
+    ) -> crate::Result<()>;
+}
+
+impl<R: Runtime, T: Manager<R>> RecentProjectsExt<R> for T {
+    fn recent_projects_state(&self) -> crate::Result<RecentProjectsState> {
+        if let Some(existing) = self.try_state::<RecentProjectsState>() {
+            Ok(existing.inner().clone())
+        } else {
+            let recent = RecentProjects::load(recent_projects_path()?)?;
+            self.manage::<RecentProjectsState>(Arc::new(RwLock::new(recent)));
+            Ok(self.state::<RecentProjectsState>().inner().clone())
+        }
+    }
+
+    fn recent_projects(&self) -> crate::Result<Vec<RecentProject>> {
+        Ok(self.recent_projects_state()?.read().projects())
+    }
+
+    fn recent_project(&self, path: impl Into<PathBuf>) -> crate::Result<RecentProject> {
+        let path = path.into();
+        self.recent_projects_state()?
+            .read()
+            .get(&path)
+            .ok_or_else(|| crate::Error::not_found("recent project", path.display()))
+    }
+
+    fn update_recent_projects(
+        &self,
+        updater: impl FnOnce(RecentProjects) -> crate::Result<RecentProjects>,
+    ) -> crate::Result<Vec<RecentProject>> {
+        let state = self.recent_projects_state()?;
+        let mut current = state.write();
+        let updated = updater(current.clone())?;
+        updated.save(recent_projects_path()?)?;
+        *current = updated;
+        Ok(current.projects())
+    }
+
+    fn remember_project(
+        &self,
+        project: &ActiveProject,
+        name: impl Into<String>,
+    ) -> crate::Result<()> {
+        let _ = self.update_recent_projects(|recent| Ok(recent.with_opened(project, name)))?;
+        Ok(())
+    }
+}
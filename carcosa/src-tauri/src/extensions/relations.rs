@@ -0,0 +1,297 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+use redb::{
+    MultimapTableDefinition, MultimapTableHandle, ReadTransaction, ReadableMultimapTable,
+    TableError, WriteTransaction,
+};
+use tauri::{Manager, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{
+        changes::note_change, history::HistoryAction, sync::SyncMessage, world::ensure_hosted,
+        DatabasesExt, HistoryExt, SyncExt, TableName, WorldExt, WriteTransactionExt,
+    },
+    types::{GraphNode, Neighbourhood, Relation, RelationKind},
+};
+
+/// Source => (target, kind) for every relation.
+pub(crate) const OUTGOING: MultimapTableDefinition<Uuid, (Uuid, &str)> =
+    MultimapTableDefinition::new("relations.outgoing");
+
+/// Target => (source, kind) for every relation, so links can be followed backwards.
+pub(crate) const INCOMING: MultimapTableDefinition<Uuid, (Uuid, &str)> =
+    MultimapTableDefinition::new("relations.incoming");
+
+/// The furthest [`neighbours_in`] and [`shortest_path_in`] will walk.
+pub const MAX_GRAPH_DEPTH: u32 = 8;
+
+/// Every relation starting or ending at `id`.
+pub(crate) fn edges(txn: &ReadTransaction, id: Uuid) -> crate::Result<Vec<Relation>> {
+    let mut relations = Vec::new();
+    for (table, outgoing) in [(OUTGOING, true), (INCOMING, false)] {
+        let table = match txn.open_multimap_table(table) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        for value in table.get(id)? {
+            let value = value?;
+            let (other, kind) = value.value();
+            let kind = RelationKind::from_key(kind);
+            relations.push(if outgoing {
+                Relation {
+                    source: id,
+                    target: other,
+                    kind,
+                }
+            } else {
+                Relation {
+                    source: other,
+                    target: id,
+                    kind,
+                }
+            });
+        }
+    }
+    Ok(relations)
+}
+
+/// Every entity within `depth` relations of `id`, following relations in either direction.
+pub(crate) fn neighbours_in(
+    txn: &ReadTransaction,
+    id: Uuid,
+    depth: u32,
+) -> crate::Result<Neighbourhood> {
+    let depth = depth.min(MAX_GRAPH_DEPTH);
+    let mut depths = HashMap::from([(id, 0)]);
+    let mut relations = HashSet::new();
+    let mut queue = VecDeque::from([id]);
+    while let Some(current) = queue.pop_front() {
+        let current_depth = depths[&current];
+        if current_depth >= depth {
+            continue;
+        }
+        for relation in edges(txn, current)? {
+            let other = relation.other(current);
+            if let Entry::Vacant(entry) = depths.entry(other) {
+                let _ = entry.insert(current_depth + 1);
+                queue.push_back(other);
+            }
+            let _ = relations.insert(relation);
+        }
+    }
+
+    let mut nodes = depths
+        .into_iter()
+        .map(|(id, depth)| GraphNode { id, depth })
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|node| (node.depth, node.id));
+    Ok(Neighbourhood {
+        nodes,
+        relations: relations.into_iter().collect(),
+    })
+}
+
+/// The shortest chain of relations linking `from` to `to`, if there is one.
+pub(crate) fn shortest_path_in(
+    txn: &ReadTransaction,
+    from: Uuid,
+    to: Uuid,
+) -> crate::Result<Option<Vec<Relation>>> {
+    if from == to {
+        return Ok(Some(Vec::new()));
+    }
+    // Maps each reached entity to the relation it was reached through.
+    let mut reached_by = HashMap::<Uuid, Option<Relation>>::from([(from, None)]);
+    let mut frontier = vec![from];
+    for _ in 0..MAX_GRAPH_DEPTH {
+        let mut next = Vec::new();
+        for current in frontier {
+            for relation in edges(txn, current)? {
+                let other = relation.other(current);
+                if reached_by.contains_key(&other) {
+                    continue;
+                }
+                let _ = reached_by.insert(other, Some(relation));
+                if other == to {
+                    let mut path = Vec::new();
+                    let mut at = to;
+                    while let Some(Some(relation)) = reached_by.get(&at) {
+                        at = relation.other(at);
+                        path.push(relation.clone());
+                    }
+                    path.reverse();
+                    return Ok(Some(path));
+                }
+                next.push(other);
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+    Ok(None)
+}
+
+/// Every stored relation.
+pub(crate) fn all_in(txn: &ReadTransaction) -> crate::Result<Vec<Relation>> {
+    let outgoing = match txn.open_multimap_table(OUTGOING) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut relations = Vec::new();
+    for entry in outgoing.iter()? {
+        let (source, targets) = entry?;
+        for value in targets {
+            let value = value?;
+            let (target, kind) = value.value();
+            relations.push(Relation {
+                source: source.value(),
+                target,
+                kind: RelationKind::from_key(kind),
+            });
+        }
+    }
+    Ok(relations)
+}
+
+/// Replaces every stored relation with `relations`.
+pub(crate) fn replace_all_in(txn: &WriteTransaction, relations: &[Relation]) -> crate::Result<()> {
+    let _ = txn.clear_multimap_table(OUTGOING)?;
+    let _ = txn.clear_multimap_table(INCOMING)?;
+    for relation in relations {
+        let _ = insert_in(txn, relation)?;
+    }
+    Ok(())
+}
+
+/// Notes a relation as changed, under its source in the outgoing table and its target in the
+/// incoming one.
+fn note_relation(relation: &Relation) {
+    note_change(
+        TableName::multimap(OUTGOING.name()),
+        Some(relation.source.to_string()),
+    );
+    note_change(
+        TableName::multimap(INCOMING.name()),
+        Some(relation.target.to_string()),
+    );
+}
+
+pub(crate) fn insert_in(txn: &WriteTransaction, relation: &Relation) -> crate::Result<bool> {
+    let kind = relation.kind.to_key();
+    let mut outgoing = txn.open_multimap_table(OUTGOING)?;
+    let mut incoming = txn.open_multimap_table(INCOMING)?;
+    let existed = outgoing.insert(relation.source, (relation.target, kind.as_str()))?;
+    let _ = incoming.insert(relation.target, (relation.source, kind.as_str()))?;
+    if !existed {
+        note_relation(relation);
+    }
+    Ok(!existed)
+}
+
+pub(crate) fn remove_in(txn: &WriteTransaction, relation: &Relation) -> crate::Result<bool> {
+    let kind = relation.kind.to_key();
+    let mut outgoing = txn.open_multimap_table(OUTGOING)?;
+    let mut incoming = txn.open_multimap_table(INCOMING)?;
+    let removed = outgoing.remove(relation.source, (relation.target, kind.as_str()))?;
+    let _ = incoming.remove(relation.target, (relation.source, kind.as_str()))?;
+    if removed {
+        note_relation(relation);
+    }
+    Ok(removed)
+}
+
+/// Removes every relation starting or ending at `id`, returning the removed relations.
+pub(crate) fn remove_all_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<Vec<Relation>> {
+    let mut removed = Vec::new();
+    {
+        let outgoing = txn.open_multimap_table(OUTGOING)?;
+        let incoming = txn.open_multimap_table(INCOMING)?;
+        for value in outgoing.get(id)? {
+            let value = value?;
+            let (target, kind) = value.value();
+            removed.push(Relation {
+                source: id,
+                target,
+                kind: RelationKind::from_key(kind),
+            });
+        }
+        for value in incoming.get(id)? {
+            let value = value?;
+            let (source, kind) = value.value();
+            removed.push(Relation {
+                source,
+                target: id,
+                kind: RelationKind::from_key(kind),
+            });
+        }
+    }
+    for relation in &removed {
+        let _ = remove_in(txn, relation)?;
+    }
+    Ok(removed)
+}
+
+pub trait RelationsExt<R: Runtime> {
+    fn add_relation(&self, relation: Relation) -> crate::Result<Relation>;
+    fn remove_relation(&self, relation: Relation) -> crate::Result<bool>;
+    fn relations_of(&self, id: Uuid) -> crate::Result<Vec<Relation>>;
+}
+
+impl<R: Runtime, T: Manager<R>> RelationsExt<R> for T {
+    fn add_relation(&self, relation: Relation) -> crate::Result<Relation> {
+        ensure_hosted::<R>(self)?;
+        relation.validate()?;
+        let _ = self.get_entity(relation.source)?;
+        let _ = self.get_entity(relation.target)?;
+        let _ = self.app_handle().undoable(
+            "Add relation".to_owned(),
+            HistoryAction::AddRelation {
+                relation: relation.clone(),
+            },
+            || {
+                self.project_database()?
+                    .write_transaction(|txn| insert_in(txn, &relation))?
+            },
+        )?;
+        self.app_handle().broadcast_message(
+            SyncMessage::RelationAdded {
+                relation: relation.clone(),
+            },
+            None,
+        );
+        Ok(relation)
+    }
+
+    fn remove_relation(&self, relation: Relation) -> crate::Result<bool> {
+        ensure_hosted::<R>(self)?;
+        // Checked up front, so removing a missing relation doesn't leave an empty undo entry.
+        if !self.relations_of(relation.source)?.contains(&relation) {
+            return Ok(false);
+        }
+        let removed = self.app_handle().undoable(
+            "Remove relation".to_owned(),
+            HistoryAction::RemoveRelation {
+                relation: relation.clone(),
+            },
+            || {
+                self.project_database()?
+                    .write_transaction(|txn| remove_in(txn, &relation))?
+            },
+        )?;
+        if removed {
+            self.app_handle()
+                .broadcast_message(SyncMessage::RelationRemoved { relation }, None);
+        }
+        Ok(removed)
+    }
+
+    fn relations_of(&self, id: Uuid) -> crate::Result<Vec<Relation>> {
+        self.project_database()?
+            .read_transaction(|txn| edges(txn, id))?
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::{HashMap, HashSet};
+
+use redb::{
+    MultimapTableDefinition, ReadTransaction, ReadableMultimapTable, ReadableTable,
+    TableDefinition, TableError, WriteTransaction,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{
+        documents::{body_text, load_in},
+        insert_record, remove_record, table,
+        world::EntityTable,
+        RecordTableExt, TypedTable, WriteTransactionExt,
+    },
+    types::{tokenize, Entity, EntityKind, ParsedQuery, SearchQuery, SearchResult},
+};
+
+/// (term, entity) => the (field, position) of every occurrence of the term in the entity.
+const POSTINGS: TableDefinition<(&str, Uuid), Vec<(u8, u32)>> =
+    TableDefinition::new("search.postings");
+
+/// Entity => every term indexed for it, so its postings can be removed when it changes.
+const TERMS: MultimapTableDefinition<Uuid, &str> = MultimapTableDefinition::new("search.terms");
+
+/// What the search index knows about an entity, used for filters and snippets.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IndexedEntity {
+    pub title: String,
+    pub kind: EntityKind,
+    pub tags: Vec<String>,
+    pub body: String,
+}
+
+table!(pub IndexedEntityTable: "search.entities", Uuid => IndexedEntity);
+
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+const SNIPPET_WORDS_BEFORE: usize = 8;
+const SNIPPET_WORDS_AFTER: usize = 16;
+
+/// Gap between the positions of separate aliases or tags, so phrases can't span two of them.
+const VALUE_POSITION_GAP: u32 = 1024;
+
+/// The part of an entity a term was found in, stored as its `u8` discriminant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+enum Field {
+    Title = 0,
+    Alias = 1,
+    Tag = 2,
+    Body = 3,
+}
+
+/// How much a match in each [`Field`] counts towards an entity's score.
+const FIELD_WEIGHTS: [f64; 4] = [8.0, 6.0, 4.0, 1.0];
+
+fn weight(field: u8) -> f64 {
+    FIELD_WEIGHTS.get(field as usize).copied().unwrap_or(1.0)
+}
+
+/// Removes an entity from the index.
+pub(crate) fn unindex_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<()> {
+    let mut postings = txn.open_table_mut(POSTINGS)?;
+    let mut terms = txn.open_multimap_table_mut(TERMS)?;
+    let mut entities = txn.open_table(IndexedEntityTable::definition())?;
+
+    let mut indexed = Vec::new();
+    for term in terms.get(id)? {
+        indexed.push(term?.value().to_string());
+    }
+    for term in indexed {
+        let _ = postings.remove((term.as_str(), id))?;
+    }
+    let _ = terms.remove_all(id)?;
+    let _ = remove_record::<IndexedEntityTable>(&mut entities, &id)?;
+    Ok(())
+}
+
+/// Replaces an entity's entries in the index, reading its body from the same transaction.
+pub(crate) fn index_in(txn: &WriteTransaction, entity: &Entity) -> crate::Result<()> {
+    let id = entity.id();
+    let body = body_text(&load_in(txn, id)?);
+    unindex_in(txn, id)?;
+
+    let mut fields = vec![(Field::Title, 0, entity.title())];
+    fields.extend(
+        entity
+            .aliases()
+            .into_iter()
+            .enumerate()
+            .map(|(index, alias)| (Field::Alias, index as u32 * VALUE_POSITION_GAP, alias)),
+    );
+    fields.extend(
+        entity
+            .tags()
+            .into_iter()
+            .enumerate()
+            .map(|(index, tag)| (Field::Tag, index as u32 * VALUE_POSITION_GAP, tag)),
+    );
+    fields.push((Field::Body, 0, body.clone()));
+
+    let mut occurrences: HashMap<String, Vec<(u8, u32)>> = HashMap::new();
+    for (field, offset, text) in fields {
+        for (position, term) in tokenize(&text).into_iter().enumerate() {
+            occurrences
+                .entry(term)
+                .or_default()
+                .push((field as u8, offset + position as u32));
+        }
+    }
+    let mut postings = txn.open_table_mut(POSTINGS)?;
+    let mut terms = txn.open_multimap_table_mut(TERMS)?;
+    for (term, positions) in occurrences {
+        let _ = postings.insert((term.as_str(), id), positions)?;
+        let _ = terms.insert(id, term.as_str())?;
+    }
+
+    let mut entities = txn.open_table(IndexedEntityTable::definition())?;
+    let _ = insert_record::<IndexedEntityTable>(
+        &mut entities,
+        &id,
+        &IndexedEntity {
+            title: entity.title(),
+            kind: entity.kind(),
+            tags: entity.tags(),
+            body,
+        },
+    )?;
+    Ok(())
+}
+
+/// Re-indexes `id` from the entity table, or removes it from the index if it no longer exists.
+pub(crate) fn reindex_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<()> {
+    let entity = {
+        let table = txn.open_table(EntityTable::definition())?;
+        RecordTableExt::<EntityTable>::get_record(&table, &id)?
+    };
+    match entity {
+        Some(entity) => index_in(txn, &entity),
+        None => unindex_in(txn, id),
+    }
+}
+
+/// Rebuilds the whole index from the entity table.
+pub(crate) fn rebuild_in(txn: &WriteTransaction) -> crate::Result<u32> {
+    let _ = txn.clear_table(POSTINGS)?;
+    let _ = txn.clear_multimap_table(TERMS)?;
+    let _ = txn.clear_table(IndexedEntityTable::definition())?;
+    let entities = {
+        let table = txn.open_table(EntityTable::definition())?;
+        RecordTableExt::<EntityTable>::range_records(&table, ..)?
+    };
+    for (_, entity) in &entities {
+        index_in(txn, entity)?;
+    }
+    Ok(entities.len() as u32)
+}
+
+/// Scores every entity with a posting for a term starting with `prefix`. Exact matches score
+/// twice as much as prefix matches.
+fn score_term(
+    postings: &impl ReadableTable<(&'static str, Uuid), Vec<(u8, u32)>>,
+    prefix: &str,
+) -> crate::Result<HashMap<Uuid, f64>> {
+    let mut scores = HashMap::new();
+    for entry in postings.range((prefix, Uuid::nil())..)? {
+        let (key, positions) = entry?;
+        let (term, id) = key.value();
+        if !term.starts_with(prefix) {
+            break;
+        }
+        let factor = if term == prefix { 1.0 } else { 0.5 };
+        for (field, _) in positions.value() {
+            *scores.entry(id).or_insert(0.0) += weight(field) * factor;
+        }
+    }
+    Ok(scores)
+}
+
+/// Scores every entity containing `phrase` as consecutive terms within one field.
+fn score_phrase(
+    postings: &impl ReadableTable<(&'static str, Uuid), Vec<(u8, u32)>>,
+    phrase: &[String],
+) -> crate::Result<HashMap<Uuid, f64>> {
+    let mut occurrences = Vec::new();
+    for word in phrase {
+        let mut found = HashSet::new();
+        for entry in postings.range((word.as_str(), Uuid::nil())..=(word.as_str(), Uuid::max()))? {
+            let (key, positions) = entry?;
+            let (_, id) = key.value();
+            found.extend(
+                positions
+                    .value()
+                    .into_iter()
+                    .map(|(field, position)| (id, field, position)),
+            );
+        }
+        occurrences.push(found);
+    }
+    let mut scores = HashMap::new();
+    for &(id, field, start) in &occurrences[0] {
+        let matched = occurrences
+            .iter()
+            .enumerate()
+            .skip(1)
+            .all(|(offset, found)| found.contains(&(id, field, start + offset as u32)));
+        if matched {
+            *scores.entry(id).or_insert(0.0) += weight(field) * phrase.len() as f64;
+        }
+    }
+    Ok(scores)
+}
+
+/// Keeps only the entities present in both score maps, adding up their scores.
+fn intersect(scores: Option<HashMap<Uuid, f64>>, next: HashMap<Uuid, f64>) -> HashMap<Uuid, f64> {
+    match scores {
+        None => next,
+        Some(scores) => scores
+            .into_iter()
+            .filter_map(|(id, score)| next.get(&id).map(|other| (id, score + other)))
+            .collect(),
+    }
+}
+
+/// An excerpt of `body` around the first word matching the query, or `None` if nothing matches.
+fn snippet(body: &str, query: &ParsedQuery) -> Option<String> {
+    let words = body.split_whitespace().collect::<Vec<_>>();
+    let hit = words.iter().position(|word| {
+        tokenize(word).iter().any(|term| {
+            query
+                .words()
+                .any(|searched| term.starts_with(searched.as_str()))
+        })
+    })?;
+    let start = hit.saturating_sub(SNIPPET_WORDS_BEFORE);
+    let end = (hit + SNIPPET_WORDS_AFTER).min(words.len());
+    let mut snippet = words[start..end].join(" ");
+    if start > 0 {
+        snippet.insert_str(0, "… ");
+    }
+    if end < words.len() {
+        snippet.push_str(" …");
+    }
+    Some(snippet)
+}
+
+/// Runs a search query against the index.
+pub(crate) fn search_in(
+    txn: &ReadTransaction,
+    query: &SearchQuery,
+) -> crate::Result<Vec<SearchResult>> {
+    let parsed = query.parse()?;
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as usize;
+
+    let entities = match txn.open_table(IndexedEntityTable::definition()) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let scores = if parsed.is_empty() {
+        let mut all = HashMap::new();
+        for entry in entities.iter()? {
+            let _ = all.insert(entry?.0.value(), 0.0);
+        }
+        all
+    } else {
+        let postings = match txn.open_table(POSTINGS) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut scores = None;
+        for term in &parsed.terms {
+            scores = Some(intersect(scores, score_term(&postings, term)?));
+        }
+        for phrase in &parsed.phrases {
+            scores = Some(intersect(scores, score_phrase(&postings, phrase)?));
+        }
+        scores.unwrap_or_default()
+    };
+
+    let mut results = Vec::new();
+    for (id, score) in scores {
+        let Some(indexed) = RecordTableExt::<IndexedEntityTable>::get_record(&entities, &id)?
+        else {
+            continue;
+        };
+        if !parsed.kinds.is_empty() && !parsed.kinds.contains(&indexed.kind) {
+            continue;
+        }
+        let tags = indexed
+            .tags
+            .iter()
+            .map(|tag| tag.to_lowercase())
+            .collect::<HashSet<_>>();
+        if !parsed.tags.iter().all(|tag| tags.contains(tag)) {
+            continue;
+        }
+        results.push(SearchResult {
+            id,
+            title: indexed.title,
+            kind: indexed.kind,
+            score,
+            snippet: snippet(&indexed.body, &parsed),
+        });
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    results.truncate(limit);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use redb::backends::InMemoryBackend;
+
+    use super::*;
+    use crate::types::EntityData;
+
+    fn database() -> redb::Database {
+        redb::Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .unwrap()
+    }
+
+    fn entity(title: &str, tags: &[&str]) -> Entity {
+        Entity::new(title, EntityData::Article {})
+            .with_tags(tags.iter().map(|tag| tag.to_string()).collect())
+    }
+
+    #[test]
+    fn scores_prefixes_and_phrases() {
+        let db = database();
+        let king = entity("The King in Yellow", &["play"]);
+        let lake = entity("Lake of Hali", &["yellowed"]);
+        let txn = db.begin_write().unwrap();
+        index_in(&txn, &king).unwrap();
+        index_in(&txn, &lake).unwrap();
+
+        let postings = txn.open_table(POSTINGS).unwrap();
+        let scores = score_term(&postings, "yellow").unwrap();
+        assert_eq!(scores.get(&king.id()), Some(&weight(Field::Title as u8)));
+        assert_eq!(
+            scores.get(&lake.id()),
+            Some(&(weight(Field::Tag as u8) * 0.5))
+        );
+
+        let phrase = [String::from("king"), String::from("in")];
+        let scores = score_phrase(&postings, &phrase).unwrap();
+        assert_eq!(scores.keys().collect::<Vec<_>>(), vec![&king.id()]);
+        let phrase = [String::from("in"), String::from("king")];
+        assert!(score_phrase(&postings, &phrase).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unindexing_removes_every_posting() {
+        let db = database();
+        let king = entity("The King in Yellow", &[]);
+        let lake = entity("Yellow Lake", &[]);
+        let txn = db.begin_write().unwrap();
+        index_in(&txn, &king).unwrap();
+        index_in(&txn, &lake).unwrap();
+        unindex_in(&txn, king.id()).unwrap();
+
+        let postings = txn.open_table(POSTINGS).unwrap();
+        let scores = score_term(&postings, "yellow").unwrap();
+        assert_eq!(scores.keys().collect::<Vec<_>>(), vec![&lake.id()]);
+        assert!(score_term(&postings, "king").unwrap().is_empty());
+    }
+}
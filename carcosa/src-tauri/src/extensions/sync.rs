@@ -0,0 +1,1011 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+    time::Duration,
+};
+
+use iroh::{
+    endpoint::Connection,
+    protocol::{AcceptError, ProtocolHandler, Router},
+    Endpoint, EndpointAddr,
+};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use yrs::{updates::decoder::Decode, Update};
+
+use crate::{
+    extensions::{
+        assets::{self, AssetTable},
+        insert_record,
+        invites::{JoinProtocol, JOIN_ALPN},
+        maps::{self, MapTable},
+        migrations::MetadataTable,
+        relations, remove_record, search, timeline,
+        transfers::{AssetProtocol, ASSET_ALPN},
+        world::{self, EntityTable},
+        ApplicationExt, Database, DatabasesExt, DocumentsExt, HistoryExt, TypedTable,
+        WriteTransactionExt,
+    },
+    procedures::{AppEvent, AppEventExt},
+    types::{
+        ActiveProject, AssetInfo, Calendar, ConnectionStatus, Entity, EntityTemplate,
+        MapAnnotation, MapInfo, PeerIdentity, ProjectSettings, Relation, TimelineEvent,
+    },
+};
+
+/// ALPN identifying the Carcosa document sync protocol.
+pub const SYNC_ALPN: &[u8] = b"carcosa/sync/1";
+
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+const MAX_SEEN_UPDATES: usize = 4096;
+
+/// How often a guest retries connecting to the host of a remote project.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Metadata key under which a remote project's cache records the host's last known address.
+pub const REMOTE_ADDRESS_KEY: &str = "remote.address";
+
+/// Messages exchanged between peers. Each message is sent on its own unidirectional stream, and
+/// the messages to one peer are sent one after another, in the order they were queued.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "message", rename_all = "snake_case")]
+pub enum SyncMessage {
+    /// Sent by both sides when a session starts, listing the state vector of every document.
+    StateVectors {
+        documents: HashMap<Uuid, Vec<u8>>,
+    },
+
+    /// The reply to [`SyncMessage::StateVectors`], holding whatever the other side is missing.
+    Updates {
+        documents: HashMap<Uuid, Vec<u8>>,
+    },
+
+    /// A single live edit.
+    Update {
+        document: Uuid,
+        update: Vec<u8>,
+    },
+
+    /// Sent back to a read-only peer whose updates were discarded.
+    Rejected {
+        documents: Vec<Uuid>,
+    },
+
+    /// Sent by the host when a session starts, replacing the guest's mirror of every entity.
+    Entities {
+        entities: Vec<Entity>,
+    },
+
+    /// Sent by the host when an entity is created or updated.
+    EntityChanged {
+        entity: Entity,
+    },
+
+    /// Sent by the host when an entity is deleted, along with all of its relations.
+    EntityRemoved {
+        id: Uuid,
+    },
+
+    /// Sent by the host when a session starts, replacing the guest's mirror of every relation.
+    Relations {
+        relations: Vec<Relation>,
+    },
+
+    RelationAdded {
+        relation: Relation,
+    },
+    RelationRemoved {
+        relation: Relation,
+    },
+
+    /// Sent by the host when a session starts, replacing the guest's mirror of the timeline.
+    Events {
+        events: Vec<TimelineEvent>,
+    },
+
+    EventChanged {
+        event: TimelineEvent,
+    },
+    EventRemoved {
+        id: Uuid,
+    },
+
+    /// Sent by the host when a session starts, listing every asset and which entities reference
+    /// them. The files themselves are fetched on demand.
+    Assets {
+        assets: Vec<AssetInfo>,
+        references: Vec<(Uuid, String)>,
+    },
+
+    AssetAttached {
+        entity: Uuid,
+        asset: AssetInfo,
+    },
+    AssetDetached {
+        entity: Uuid,
+        hash: String,
+    },
+
+    /// Sent by the host when its garbage collector deleted unused assets.
+    AssetsRemoved {
+        hashes: Vec<String>,
+    },
+
+    /// Sent by the host when a session starts, replacing the guest's mirror of every map and
+    /// annotation. Map images are fetched on demand, like other assets.
+    Maps {
+        maps: Vec<MapInfo>,
+        annotations: Vec<MapAnnotation>,
+    },
+
+    MapChanged {
+        map: MapInfo,
+    },
+    MapRemoved {
+        id: Uuid,
+    },
+    AnnotationChanged {
+        annotation: MapAnnotation,
+    },
+    AnnotationRemoved {
+        id: Uuid,
+    },
+
+    /// Sent by the host when a session starts and whenever a template or calendar changes,
+    /// replacing the guest's copies of both.
+    Settings {
+        templates: Vec<EntityTemplate>,
+        calendars: Vec<Calendar>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct SyncStatus {
+    pub running: bool,
+    pub identity: Option<PeerIdentity>,
+    pub peers: Vec<PeerIdentity>,
+}
+
+/// Messages waiting to be sent to one peer.
+type Outbox = mpsc::UnboundedSender<SyncMessage>;
+
+/// A connected peer, along with the queue its messages go out through.
+#[derive(Clone)]
+struct PeerSession {
+    /// Tells this session apart from a newer one with the same peer, which replaces it if the
+    /// peer reconnects before this one has closed.
+    id: Uuid,
+    connection: Connection,
+    outbox: Outbox,
+}
+
+#[derive(Clone)]
+pub struct SyncNode {
+    endpoint: Endpoint,
+    router: Router,
+    peers: Arc<RwLock<HashMap<PeerIdentity, PeerSession>>>,
+    seen: Arc<RwLock<HashSet<u64>>>,
+}
+
+impl SyncNode {
+    pub fn identity(&self) -> PeerIdentity {
+        PeerIdentity::from(self.endpoint.id())
+    }
+
+    pub fn endpoint(&self) -> Endpoint {
+        self.endpoint.clone()
+    }
+
+    pub fn peers(&self) -> Vec<PeerIdentity> {
+        self.peers.read().keys().cloned().collect()
+    }
+
+    pub fn connection(&self, peer: &PeerIdentity) -> Option<Connection> {
+        self.peers
+            .read()
+            .get(peer)
+            .map(|session| session.connection.clone())
+    }
+
+    /// Queues a message for `peer`, returning `false` if it isn't connected. Messages to the same
+    /// peer are sent in the order they were queued.
+    pub fn send(&self, peer: &PeerIdentity, message: SyncMessage) -> bool {
+        self.peers
+            .read()
+            .get(peer)
+            .is_some_and(|session| session.outbox.send(message).is_ok())
+    }
+
+    /// Whether `other` is a handle to this same running node.
+    pub fn is_same(&self, other: &SyncNode) -> bool {
+        Arc::ptr_eq(&self.peers, &other.peers)
+    }
+
+    pub async fn shutdown(self) {
+        for (_, session) in self.peers.write().drain() {
+            session.connection.close(0u32.into(), b"shutting down");
+        }
+        if let Err(err) = self.router.shutdown().await {
+            log::warn!("Failed to shut down sync router: {err}");
+        }
+    }
+
+    /// Records an update as seen, returning `false` if it had already been seen. This keeps
+    /// relayed updates from bouncing around between peers forever.
+    fn mark_seen(&self, document: Uuid, update: &[u8]) -> bool {
+        let mut hasher = DefaultHasher::new();
+        document.hash(&mut hasher);
+        update.hash(&mut hasher);
+        let mut seen = self.seen.write();
+        if seen.len() >= MAX_SEEN_UPDATES {
+            seen.clear();
+        }
+        seen.insert(hasher.finish())
+    }
+}
+
+pub type SyncState = Arc<RwLock<Option<SyncNode>>>;
+
+pub async fn send_message(connection: &Connection, message: &SyncMessage) -> crate::Result<()> {
+    let data = serde_json::to_vec(message)?;
+    let mut send = connection.open_uni().await.map_err(crate::Error::network)?;
+    send.write_all(&data).await.map_err(crate::Error::network)?;
+    send.finish().map_err(crate::Error::network)?;
+    Ok(())
+}
+
+/// Starts the single task that writes every message queued for `peer` to its connection, so
+/// they're opened as streams, and handled by the peer, in the order they were queued. The task
+/// stops once the connection closes or the outbox is dropped.
+fn spawn_writer(peer: PeerIdentity, connection: Connection) -> Outbox {
+    let (outbox, mut queue) = mpsc::unbounded_channel::<SyncMessage>();
+    tauri::async_runtime::spawn(async move {
+        while let Some(message) = queue.recv().await {
+            if let Err(err) = send_message(&connection, &message).await {
+                log::warn!("Failed to send to {}: {err}", peer.short_format());
+                if connection.close_reason().is_some() {
+                    break;
+                }
+            }
+        }
+    });
+    outbox
+}
+
+fn state_vectors<R: Runtime>(app: &AppHandle<R>) -> crate::Result<HashMap<Uuid, Vec<u8>>> {
+    let mut documents = HashMap::new();
+    for id in app.list_documents()? {
+        let _ = documents.insert(id, app.document_state_vector(id)?);
+    }
+    Ok(documents)
+}
+
+/// Whether `peer` may change documents on this machine. Only peers in the collaborators map are
+/// restricted; any other connected peer is the host of a project this machine joined.
+fn can_edit<R: Runtime>(app: &AppHandle<R>, peer: &PeerIdentity) -> bool {
+    app.get_app_state()
+        .project_settings()
+        .and_then(|settings| settings.collaborator(peer.clone()))
+        .is_none_or(|collaborator| collaborator.can_edit)
+}
+
+/// Whether `peer` takes part in the project open on this machine, either as its host or as one of
+/// its collaborators.
+pub(crate) fn is_member<R: Runtime>(app: &AppHandle<R>, peer: &PeerIdentity) -> bool {
+    let state = app.get_app_state();
+    match state.active_project() {
+        ActiveProject::Remote { host, .. } => host == *peer,
+        _ => state
+            .project_settings()
+            .and_then(|settings| settings.collaborator(peer.clone()))
+            .is_some(),
+    }
+}
+
+/// Whether `peer` hosts the remote project open on this machine.
+fn is_host<R: Runtime>(app: &AppHandle<R>, peer: &PeerIdentity) -> bool {
+    matches!(app.get_app_state().active_project(), ActiveProject::Remote { host, .. } if host == *peer)
+}
+
+/// Applies an entity, relation, timeline, asset, map or settings change sent by `peer` to the
+/// local mirror, ignoring anyone but the host.
+async fn receive_entities<R: Runtime>(
+    app: &AppHandle<R>,
+    peer: &PeerIdentity,
+    message: SyncMessage,
+) -> crate::Result<()> {
+    if !is_host(app, peer) {
+        log::warn!(
+            "Ignored entity changes from non-host peer {}",
+            peer.short_format()
+        );
+        return Ok(());
+    }
+    let db = app.project_database()?;
+    match message {
+        SyncMessage::Entities { entities } => {
+            let _ = db
+                .write_transaction_async(move |txn| -> crate::Result<u32> {
+                    {
+                        let mut table = txn.open_table_mut(EntityTable::definition())?;
+                        table.retain(|_, _| false)?;
+                        for entity in &entities {
+                            let _ = insert_record::<EntityTable>(&mut table, &entity.id(), entity)?;
+                        }
+                    }
+                    search::rebuild_in(txn)
+                })
+                .await??;
+            Ok(())
+        }
+        SyncMessage::EntityChanged { entity } => {
+            db.transaction()
+                .write_async(move |txn| -> crate::Result<()> {
+                    let _ = insert_record::<EntityTable>(
+                        &mut txn.records::<EntityTable>()?,
+                        &entity.id(),
+                        &entity,
+                    )?;
+                    search::index_in(txn.inner(), &entity)
+                })
+                .await?
+        }
+        SyncMessage::EntityRemoved { id } => {
+            let _ = db
+                .transaction()
+                .write_async(move |txn| world::remove_entity_in(txn, id))
+                .await??;
+            Ok(())
+        }
+        SyncMessage::Relations {
+            relations: mirrored,
+        } => {
+            db.write_transaction_async(move |txn| relations::replace_all_in(txn, &mirrored))
+                .await?
+        }
+        SyncMessage::RelationAdded { relation } => {
+            let _ = db
+                .write_transaction_async(move |txn| relations::insert_in(txn, &relation))
+                .await??;
+            Ok(())
+        }
+        SyncMessage::RelationRemoved { relation } => {
+            let _ = db
+                .write_transaction_async(move |txn| relations::remove_in(txn, &relation))
+                .await??;
+            Ok(())
+        }
+        SyncMessage::Events { events } => {
+            db.write_transaction_async(move |txn| timeline::replace_all_in(txn, &events))
+                .await?
+        }
+        SyncMessage::EventChanged { event } => {
+            let _ = db
+                .write_transaction_async(move |txn| timeline::insert_in(txn, &event))
+                .await??;
+            Ok(())
+        }
+        SyncMessage::EventRemoved { id } => {
+            let _ = db
+                .write_transaction_async(move |txn| timeline::remove_in(txn, id))
+                .await??;
+            Ok(())
+        }
+        SyncMessage::Assets {
+            assets: listed,
+            references,
+        } => {
+            for hash in listed
+                .iter()
+                .map(|asset| &asset.hash)
+                .chain(references.iter().map(|(_, hash)| hash))
+            {
+                AssetInfo::validate_hash(hash)?;
+            }
+            // Assets imported on this machine are kept, since they can be served to others.
+            db.write_transaction_async(move |txn| -> crate::Result<()> {
+                {
+                    let mut table = txn.open_table(AssetTable::definition())?;
+                    for asset in &listed {
+                        let _ = insert_record::<AssetTable>(&mut table, &asset.hash, asset)?;
+                    }
+                }
+                assets::replace_references_in(txn, &references)
+            })
+            .await?
+        }
+        SyncMessage::AssetAttached { entity, asset } => {
+            AssetInfo::validate_hash(&asset.hash)?;
+            db.write_transaction_async(move |txn| -> crate::Result<()> {
+                let _ = insert_record::<AssetTable>(
+                    &mut txn.open_table(AssetTable::definition())?,
+                    &asset.hash,
+                    &asset,
+                )?;
+                assets::attach_in(txn, entity, &asset.hash)
+            })
+            .await?
+        }
+        SyncMessage::AssetDetached { entity, hash } => {
+            let _ = db
+                .write_transaction_async(move |txn| assets::detach_in(txn, entity, &hash))
+                .await??;
+            Ok(())
+        }
+        SyncMessage::AssetsRemoved { hashes } => {
+            for hash in &hashes {
+                AssetInfo::validate_hash(hash)?;
+            }
+            let hashes = db
+                .write_async::<AssetTable, _>(move |mut table| {
+                    for hash in &hashes {
+                        let _ = remove_record::<AssetTable>(&mut table, hash)?;
+                    }
+                    Ok(hashes)
+                })
+                .await?;
+            if let Some(project) = app.get_app_state().active_project().path() {
+                for hash in hashes {
+                    let _ = std::fs::remove_file(assets::asset_path(&project, &hash)?);
+                }
+            }
+            Ok(())
+        }
+        SyncMessage::Maps {
+            maps: mirrored,
+            annotations,
+        } => {
+            db.write_transaction_async(move |txn| {
+                maps::replace_all_in(txn, &mirrored, &annotations)
+            })
+            .await?
+        }
+        SyncMessage::MapChanged { map } => {
+            let _ = db
+                .write_async::<MapTable, _>(move |mut table| {
+                    insert_record::<MapTable>(&mut table, &map.id, &map)
+                })
+                .await?;
+            Ok(())
+        }
+        SyncMessage::MapRemoved { id } => {
+            let _ = db
+                .write_transaction_async(move |txn| maps::remove_map_in(txn, id))
+                .await??;
+            if let Some(project) = app.get_app_state().active_project().path() {
+                maps::remove_tiles(project, id);
+            }
+            Ok(())
+        }
+        SyncMessage::AnnotationChanged { annotation } => {
+            db.write_transaction_async(move |txn| maps::insert_annotation_in(txn, &annotation))
+                .await?
+        }
+        SyncMessage::AnnotationRemoved { id } => {
+            let _ = db
+                .write_transaction_async(move |txn| maps::remove_annotation_in(txn, id))
+                .await??;
+            Ok(())
+        }
+        SyncMessage::Settings {
+            templates,
+            calendars,
+        } => {
+            let _ = app.update_project_settings(|settings| {
+                Ok(settings
+                    .with_templates(templates.into_iter().map(|t| (t.id, t)).collect())
+                    .with_calendars(calendars.into_iter().map(|c| (c.id, c)).collect()))
+            })?;
+            let _ = app.emit_event(AppEvent::ProjectSettingsChanged);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Applies an update received from `peer`, returning `false` if it was rejected.
+fn receive_update<R: Runtime>(
+    app: &AppHandle<R>,
+    node: &SyncNode,
+    peer: &PeerIdentity,
+    document: Uuid,
+    update: Vec<u8>,
+) -> crate::Result<bool> {
+    if Update::decode_v1(&update)?.is_empty() {
+        return Ok(true);
+    }
+    if !can_edit(app, peer) {
+        log::warn!(
+            "Rejected update to document {document} from read-only peer {}",
+            peer.short_format()
+        );
+        return Ok(false);
+    }
+    if !node.mark_seen(document, &update) {
+        return Ok(true);
+    }
+    app.store_document_update(document, &update)?;
+    app.broadcast_document_update(document, update, Some(peer.clone()));
+    let _ = app.emit_event(AppEvent::RemoteDocumentUpdate {
+        document,
+        peer: peer.clone(),
+    });
+    Ok(true)
+}
+
+async fn handle_message<R: Runtime>(
+    app: &AppHandle<R>,
+    node: &SyncNode,
+    peer: &PeerIdentity,
+    outbox: &Outbox,
+    message: SyncMessage,
+) -> crate::Result<()> {
+    match message {
+        SyncMessage::StateVectors { documents } => {
+            let mut missing = HashMap::new();
+            for id in app.list_documents()? {
+                let state_vector = documents.get(&id).cloned().unwrap_or_default();
+                let _ = missing.insert(id, app.document_diff(id, state_vector)?);
+            }
+            send_queued(outbox, SyncMessage::Updates { documents: missing })
+        }
+        SyncMessage::Updates { documents } => {
+            let mut rejected = Vec::new();
+            for (document, update) in documents {
+                if !receive_update(app, node, peer, document, update)? {
+                    rejected.push(document);
+                }
+            }
+            reject_updates(app, peer, outbox, rejected)
+        }
+        SyncMessage::Update { document, update } => {
+            if receive_update(app, node, peer, document, update)? {
+                Ok(())
+            } else {
+                reject_updates(app, peer, outbox, vec![document])
+            }
+        }
+        SyncMessage::Rejected { documents } => {
+            log::warn!(
+                "{} rejected updates to {} document(s)",
+                peer.short_format(),
+                documents.len()
+            );
+            let _ = app.emit_event(AppEvent::UpdatesRejectedByPeer {
+                peer: peer.clone(),
+                documents,
+            });
+            Ok(())
+        }
+        message @ (SyncMessage::Entities { .. }
+        | SyncMessage::EntityChanged { .. }
+        | SyncMessage::EntityRemoved { .. }
+        | SyncMessage::Relations { .. }
+        | SyncMessage::RelationAdded { .. }
+        | SyncMessage::RelationRemoved { .. }
+        | SyncMessage::Events { .. }
+        | SyncMessage::EventChanged { .. }
+        | SyncMessage::EventRemoved { .. }
+        | SyncMessage::Assets { .. }
+        | SyncMessage::AssetAttached { .. }
+        | SyncMessage::AssetDetached { .. }
+        | SyncMessage::AssetsRemoved { .. }
+        | SyncMessage::Maps { .. }
+        | SyncMessage::MapChanged { .. }
+        | SyncMessage::MapRemoved { .. }
+        | SyncMessage::AnnotationChanged { .. }
+        | SyncMessage::AnnotationRemoved { .. }
+        | SyncMessage::Settings { .. }) => receive_entities(app, peer, message).await,
+    }
+}
+
+fn reject_updates<R: Runtime>(
+    app: &AppHandle<R>,
+    peer: &PeerIdentity,
+    outbox: &Outbox,
+    documents: Vec<Uuid>,
+) -> crate::Result<()> {
+    if documents.is_empty() {
+        return Ok(());
+    }
+    let _ = app.emit_event(AppEvent::RejectedPeerUpdates {
+        peer: peer.clone(),
+        documents: documents.clone(),
+    });
+    send_queued(outbox, SyncMessage::Rejected { documents })
+}
+
+fn send_queued(outbox: &Outbox, message: SyncMessage) -> crate::Result<()> {
+    outbox
+        .send(message)
+        .map_err(|_| crate::Error::Network(String::from("The connection was closed")))
+}
+
+/// The message a host sends to replace a guest's templates and calendars.
+pub(crate) fn settings_message(settings: &ProjectSettings) -> SyncMessage {
+    SyncMessage::Settings {
+        templates: settings.templates().into_values().collect(),
+        calendars: settings.calendars().into_values().collect(),
+    }
+}
+
+/// Saves a change to the project's templates or calendars and sends the result to connected
+/// guests. Guests can't make such changes, since the host would overwrite them. project.json isn't
+/// rolled back by undo, so the undo history is forgotten too.
+pub(crate) fn update_shared_settings<R: Runtime>(
+    app: &impl Manager<R>,
+    updater: impl FnOnce(ProjectSettings) -> ProjectSettings,
+) -> crate::Result<ProjectSettings> {
+    world::ensure_hosted::<R>(app)?;
+    let settings = app.update_project_settings(|settings| Ok(updater(settings)))?;
+    app.app_handle().reset_history();
+    app.app_handle()
+        .broadcast_message(settings_message(&settings), None);
+    Ok(settings)
+}
+
+/// The messages a host sends to replace a guest's mirror of its entities, relations, timeline,
+/// assets, maps, templates and calendars.
+pub(crate) async fn world_snapshot(
+    db: &Database,
+    settings: &ProjectSettings,
+) -> crate::Result<Vec<SyncMessage>> {
+    let entities = db
+        .iter_async::<EntityTable>()
+        .await?
+        .into_iter()
+        .map(|(_, entity)| entity)
+        .collect();
+    let relations = db.read_transaction_async(relations::all_in).await??;
+    let events = db.read_transaction_async(timeline::all_in).await??;
+    let references = db
+        .read_transaction_async(assets::references_all_in)
+        .await??;
+    let assets = db
+        .iter_async::<AssetTable>()
+        .await?
+        .into_iter()
+        .map(|(_, asset)| asset)
+        .collect();
+    let (maps, annotations) = db.read_transaction_async(maps::all_in).await??;
+    Ok(vec![
+        settings_message(settings),
+        SyncMessage::Entities { entities },
+        SyncMessage::Relations { relations },
+        SyncMessage::Events { events },
+        SyncMessage::Assets { assets, references },
+        SyncMessage::Maps { maps, annotations },
+    ])
+}
+
+/// Runs a sync session with a connected peer until the connection closes.
+pub async fn run_session<R: Runtime>(
+    app: AppHandle<R>,
+    node: SyncNode,
+    peer: PeerIdentity,
+    connection: Connection,
+) -> crate::Result<()> {
+    let outbox = spawn_writer(peer.clone(), connection.clone());
+    let session = Uuid::now_v7();
+    let _ = node.peers.write().insert(
+        peer.clone(),
+        PeerSession {
+            id: session,
+            connection: connection.clone(),
+            outbox: outbox.clone(),
+        },
+    );
+    let _ = app.emit_event(AppEvent::PeerConnected { peer: peer.clone() });
+
+    let result = async {
+        if let ActiveProject::Local { .. } = app.get_app_state().active_project() {
+            let settings = app
+                .get_app_state()
+                .project_settings()
+                .ok_or(crate::Error::NoActiveProject)?;
+            for message in world_snapshot(&app.project_database()?, &settings).await? {
+                send_queued(&outbox, message)?;
+            }
+        }
+        send_queued(
+            &outbox,
+            SyncMessage::StateVectors {
+                documents: state_vectors(&app)?,
+            },
+        )?;
+        while let Ok(mut recv) = connection.accept_uni().await {
+            let data = recv
+                .read_to_end(MAX_MESSAGE_SIZE)
+                .await
+                .map_err(crate::Error::network)?;
+            let message = serde_json::from_slice::<SyncMessage>(&data)?;
+            if let Err(err) = handle_message(&app, &node, &peer, &outbox, message).await {
+                log::warn!(
+                    "Failed to handle sync message from {}: {err}",
+                    peer.short_format()
+                );
+            }
+        }
+        Ok::<_, crate::Error>(())
+    }
+    .await;
+
+    let removed = {
+        let mut peers = node.peers.write();
+        let current = peers
+            .get(&peer)
+            .is_some_and(|existing| existing.id == session);
+        current && peers.remove(&peer).is_some()
+    };
+    if removed {
+        let _ = app.emit_event(AppEvent::PeerDisconnected { peer });
+    }
+    result
+}
+
+#[derive(Clone)]
+struct SyncProtocol<R: Runtime> {
+    app: AppHandle<R>,
+}
+
+impl<R: Runtime> std::fmt::Debug for SyncProtocol<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncProtocol").finish()
+    }
+}
+
+impl<R: Runtime> ProtocolHandler for SyncProtocol<R> {
+    async fn accept(&self, connection: Connection) -> Result<(), AcceptError> {
+        let peer = PeerIdentity::from(connection.remote_id());
+        let allowed = is_member(&self.app, &peer);
+        let node = self.app.sync_state().read().clone();
+        match (allowed, node) {
+            (true, Some(node)) => {
+                if let Err(err) =
+                    run_session(self.app.clone(), node, peer.clone(), connection).await
+                {
+                    log::warn!("Sync session with {} failed: {err}", peer.short_format());
+                }
+            }
+            _ => {
+                log::info!("Rejected sync connection from {}", peer.short_format());
+                connection.close(1u32.into(), b"not a collaborator");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+pub trait SyncExt<R: Runtime> {
+    fn sync_state(&self) -> SyncState;
+    fn sync_node(&self) -> crate::Result<SyncNode>;
+    fn sync_status(&self) -> SyncStatus;
+    async fn start_sync(&self) -> crate::Result<SyncStatus>;
+    async fn stop_sync(&self);
+    async fn connect_peer(&self, peer: PeerIdentity) -> crate::Result<()>;
+    async fn connect_address(&self, address: EndpointAddr) -> crate::Result<()>;
+
+    /// Connects to the host of the open remote project, using its last known address.
+    async fn connect_host(&self) -> crate::Result<()>;
+    fn connection_status(&self) -> ConnectionStatus;
+    fn broadcast_message(&self, message: SyncMessage, except: Option<PeerIdentity>);
+    fn broadcast_document_update(
+        &self,
+        document: Uuid,
+        update: Vec<u8>,
+        except: Option<PeerIdentity>,
+    );
+}
+
+#[async_trait::async_trait]
+impl<R: Runtime, T: Manager<R> + Sync> SyncExt<R> for T {
+    fn sync_state(&self) -> SyncState {
+        if let Some(existing) = self.try_state::<SyncState>() {
+            existing.inner().clone()
+        } else {
+            self.manage::<SyncState>(Arc::new(RwLock::new(None)));
+            self.state::<SyncState>().inner().clone()
+        }
+    }
+
+    fn sync_node(&self) -> crate::Result<SyncNode> {
+        self.sync_state()
+            .read()
+            .clone()
+            .ok_or(crate::Error::SyncNotRunning)
+    }
+
+    fn sync_status(&self) -> SyncStatus {
+        match self.sync_state().read().clone() {
+            Some(node) => SyncStatus {
+                running: true,
+                identity: Some(node.identity()),
+                peers: node.peers(),
+            },
+            None => SyncStatus {
+                running: false,
+                identity: None,
+                peers: Vec::new(),
+            },
+        }
+    }
+
+    async fn start_sync(&self) -> crate::Result<SyncStatus> {
+        if self.sync_state().read().is_some() {
+            return Ok(self.sync_status());
+        }
+        let settings = self
+            .get_app_state()
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)?;
+        let endpoint = Endpoint::builder()
+            .secret_key(settings.identity().private_key())
+            .alpns(vec![
+                SYNC_ALPN.to_vec(),
+                JOIN_ALPN.to_vec(),
+                ASSET_ALPN.to_vec(),
+            ])
+            .bind()
+            .await
+            .map_err(crate::Error::network)?;
+        let router = Router::builder(endpoint.clone())
+            .accept(
+                SYNC_ALPN,
+                SyncProtocol {
+                    app: self.app_handle().clone(),
+                },
+            )
+            .accept(
+                JOIN_ALPN,
+                JoinProtocol {
+                    app: self.app_handle().clone(),
+                },
+            )
+            .accept(
+                ASSET_ALPN,
+                AssetProtocol {
+                    app: self.app_handle().clone(),
+                },
+            )
+            .spawn();
+        let node = SyncNode {
+            endpoint,
+            router,
+            peers: Arc::new(RwLock::new(HashMap::new())),
+            seen: Arc::new(RwLock::new(HashSet::new())),
+        };
+        if let Some(previous) = self.sync_state().write().replace(node.clone()) {
+            tauri::async_runtime::spawn(previous.shutdown());
+        }
+
+        if let ActiveProject::Remote { host, .. } = self.get_app_state().active_project() {
+            // Keep trying to reach the host for as long as this node is running.
+            let app = self.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(RECONNECT_INTERVAL).await;
+                    if !app
+                        .sync_state()
+                        .read()
+                        .as_ref()
+                        .is_some_and(|n| n.is_same(&node))
+                    {
+                        break;
+                    }
+                    if node.connection(&host).is_none() {
+                        if let Err(err) = app.connect_host().await {
+                            log::debug!("Host {} is not reachable: {err}", host.short_format());
+                        }
+                    }
+                }
+            });
+        }
+
+        for collaborator in settings.collaborators().into_keys() {
+            let app = self.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = app.connect_peer(collaborator.clone()).await {
+                    log::info!(
+                        "Collaborator {} is not reachable: {err}",
+                        collaborator.short_format()
+                    );
+                }
+            });
+        }
+        Ok(self.sync_status())
+    }
+
+    async fn stop_sync(&self) {
+        let node = self.sync_state().write().take();
+        if let Some(node) = node {
+            node.shutdown().await;
+        }
+    }
+
+    async fn connect_peer(&self, peer: PeerIdentity) -> crate::Result<()> {
+        self.connect_address(EndpointAddr::from(peer.into_inner()))
+            .await
+    }
+
+    async fn connect_address(&self, address: EndpointAddr) -> crate::Result<()> {
+        let node = self.sync_node()?;
+        let peer = PeerIdentity::from(address.id);
+        if node.connection(&peer).is_some() {
+            return Ok(());
+        }
+        let connection = node
+            .endpoint
+            .connect(address, SYNC_ALPN)
+            .await
+            .map_err(crate::Error::network)?;
+        let app = self.app_handle().clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = run_session(app, node, peer.clone(), connection).await {
+                log::warn!("Sync session with {} failed: {err}", peer.short_format());
+            }
+        });
+        Ok(())
+    }
+
+    async fn connect_host(&self) -> crate::Result<()> {
+        let ActiveProject::Remote { host, .. } = self.get_app_state().active_project() else {
+            return Err(crate::Error::NoActiveProject);
+        };
+        let address = self
+            .project_database()?
+            .get::<MetadataTable>(&REMOTE_ADDRESS_KEY.to_string())?
+            .and_then(|address| serde_json::from_value::<EndpointAddr>(address).ok())
+            .filter(|address| PeerIdentity::from(address.id) == host)
+            .unwrap_or_else(|| EndpointAddr::from(host.into_inner()));
+        self.connect_address(address).await
+    }
+
+    fn connection_status(&self) -> ConnectionStatus {
+        let state = self.get_app_state();
+        let node = self.sync_state().read().clone();
+        match (state.active_project(), node) {
+            (ActiveProject::None, _) | (ActiveProject::Local { .. }, None) => {
+                ConnectionStatus::Offline
+            }
+            (ActiveProject::Local { .. }, Some(node)) => ConnectionStatus::Hosting {
+                peers: node.peers().len() as u32,
+            },
+            (ActiveProject::Remote { host, .. }, Some(node))
+                if node.connection(&host).is_some() =>
+            {
+                ConnectionStatus::Connected
+            }
+            (ActiveProject::Remote { .. }, _) => ConnectionStatus::Offline,
+        }
+    }
+
+    fn broadcast_message(&self, message: SyncMessage, except: Option<PeerIdentity>) {
+        let Some(node) = self.sync_state().read().clone() else {
+            return;
+        };
+        for peer in node.peers() {
+            if except.as_ref() != Some(&peer) {
+                let _ = node.send(&peer, message.clone());
+            }
+        }
+    }
+
+    fn broadcast_document_update(
+        &self,
+        document: Uuid,
+        update: Vec<u8>,
+        except: Option<PeerIdentity>,
+    ) {
+        let Some(node) = self.sync_state().read().clone() else {
+            return;
+        };
+        let _ = node.mark_seen(document, &update);
+        self.broadcast_message(SyncMessage::Update { document, update }, except);
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    fmt::Display,
+    ops::{Bound, RangeBounds},
+};
+
+use redb::{Key, ReadOnlyTable, ReadableTable, Table, TableDefinition, TableError};
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+
+use crate::extensions::{changes::note_change, Database, TableName, Transaction};
+
+/// A redb key type that can be stored and read back as an owned value. Keys are displayed when
+/// reporting which records changed.
+pub trait RecordKey: Key + Clone + Display + 'static {
+    fn to_stored(&self) -> Self::SelfType<'_>;
+    fn from_stored(stored: Self::SelfType<'_>) -> Self;
+}
+
+macro_rules! owned_keys {
+    ($($key:ty),+) => {
+        $(
+            impl RecordKey for $key {
+                fn to_stored(&self) -> $key {
+                    self.clone()
+                }
+
+                fn from_stored(stored: $key) -> $key {
+                    stored
+                }
+            }
+        )+
+    };
+}
+
+owned_keys!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, bool, String, Uuid);
+
+/// A table with a fixed name, key type and serde record type. Records are stored as JSON blobs.
+///
+/// Declare implementors with the [`table!`] macro rather than by hand.
+pub trait TypedTable: 'static {
+    const NAME: &'static str;
+    type Key: RecordKey;
+    type Record: Serialize + DeserializeOwned;
+
+    fn table_name() -> TableName {
+        TableName::unique(Self::NAME)
+    }
+
+    fn definition() -> TableDefinition<'static, Self::Key, &'static [u8]> {
+        TableDefinition::new(Self::NAME)
+    }
+
+    fn encode(record: &Self::Record) -> crate::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(record)?)
+    }
+
+    fn decode(data: &[u8]) -> crate::Result<Self::Record> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+/// Declares a [`TypedTable`]:
+///
+/// ```ignore
+/// table!(pub EntityTable: "world.entities", Uuid => Entity);
+/// ```
+macro_rules! table {
+    ($(#[$meta:meta])* $vis:vis $ident:ident: $name:literal, $key:ty => $record:ty) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug)]
+        $vis struct $ident;
+
+        impl $crate::extensions::TypedTable for $ident {
+            const NAME: &'static str = $name;
+            type Key = $key;
+            type Record = $record;
+        }
+    };
+}
+
+pub(crate) use table;
+
+pub type RecordTable<'txn, T> = Table<'txn, <T as TypedTable>::Key, &'static [u8]>;
+pub type ReadOnlyRecordTable<T> = ReadOnlyTable<<T as TypedTable>::Key, &'static [u8]>;
+
+fn stored_bound<K: RecordKey>(bound: Bound<&K>) -> Bound<K::SelfType<'_>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.to_stored()),
+        Bound::Excluded(key) => Bound::Excluded(key.to_stored()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Typed record access for tables that are already open, ie. inside a [`Database::write`] closure.
+pub trait RecordTableExt<T: TypedTable> {
+    fn get_record(&self, key: &T::Key) -> crate::Result<Option<T::Record>>;
+    fn range_records(
+        &self,
+        range: impl RangeBounds<T::Key>,
+    ) -> crate::Result<Vec<(T::Key, T::Record)>>;
+}
+
+impl<T: TypedTable, Tbl: ReadableTable<T::Key, &'static [u8]>> RecordTableExt<T> for Tbl {
+    fn get_record(&self, key: &T::Key) -> crate::Result<Option<T::Record>> {
+        match self.get(key.to_stored())? {
+            Some(data) => Ok(Some(T::decode(data.value())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn range_records(
+        &self,
+        range: impl RangeBounds<T::Key>,
+    ) -> crate::Result<Vec<(T::Key, T::Record)>> {
+        let bounds = (
+            stored_bound(range.start_bound()),
+            stored_bound(range.end_bound()),
+        );
+        let mut result = Vec::new();
+        for entry in self.range(bounds)? {
+            let (key, value) = entry?;
+            result.push((T::Key::from_stored(key.value()), T::decode(value.value())?));
+        }
+        Ok(result)
+    }
+}
+
+/// Inserts a record into an open table, returning the previous record if one existed.
+pub fn insert_record<T: TypedTable>(
+    table: &mut RecordTable<'_, T>,
+    key: &T::Key,
+    record: &T::Record,
+) -> crate::Result<Option<T::Record>> {
+    let encoded = T::encode(record)?;
+    let previous = match table.insert(key.to_stored(), encoded.as_slice())? {
+        Some(previous) => Some(T::decode(previous.value())?),
+        None => None,
+    };
+    note_change(T::table_name(), Some(key.to_string()));
+    Ok(previous)
+}
+
+/// Removes a record from an open table, returning it if it existed.
+pub fn remove_record<T: TypedTable>(
+    table: &mut RecordTable<'_, T>,
+    key: &T::Key,
+) -> crate::Result<Option<T::Record>> {
+    let removed = match table.remove(key.to_stored())? {
+        Some(previous) => T::decode(previous.value())?,
+        None => return Ok(None),
+    };
+    note_change(T::table_name(), Some(key.to_string()));
+    Ok(Some(removed))
+}
+
+impl Database {
+    /// Reads from a typed table, treating a table that hasn't been created yet as empty.
+    pub fn read_table<T: TypedTable, Output>(
+        &self,
+        empty: impl FnOnce() -> Output,
+        transaction: impl FnOnce(ReadOnlyRecordTable<T>) -> crate::Result<Output>,
+    ) -> crate::Result<Output> {
+        match self.read::<T::Key, &'static [u8], _>(T::NAME, transaction) {
+            Ok(result) => result,
+            Err(crate::Error::Database(redb::Error::TableDoesNotExist(_))) => Ok(empty()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Writes to a typed table. Only the records written through [`insert_record`] and
+    /// [`remove_record`] are reported to subscribers.
+    pub fn write_table<T: TypedTable, Output>(
+        &self,
+        transaction: impl FnOnce(RecordTable<'_, T>) -> crate::Result<Output>,
+    ) -> crate::Result<Output> {
+        self.write_transaction(|txn| transaction(txn.open_table(T::definition())?))?
+    }
+
+    pub fn get<T: TypedTable>(&self, key: &T::Key) -> crate::Result<Option<T::Record>> {
+        self.read_table::<T, _>(
+            || None,
+            |table| RecordTableExt::<T>::get_record(&table, key),
+        )
+    }
+
+    pub fn insert<T: TypedTable>(
+        &self,
+        key: &T::Key,
+        record: &T::Record,
+    ) -> crate::Result<Option<T::Record>> {
+        self.write_table::<T, _>(|mut table| insert_record::<T>(&mut table, key, record))
+    }
+
+    pub fn remove<T: TypedTable>(&self, key: &T::Key) -> crate::Result<Option<T::Record>> {
+        self.write_table::<T, _>(|mut table| remove_record::<T>(&mut table, key))
+    }
+
+    pub fn range<T: TypedTable>(
+        &self,
+        range: impl RangeBounds<T::Key>,
+    ) -> crate::Result<Vec<(T::Key, T::Record)>> {
+        self.read_table::<T, _>(Vec::new, |table| {
+            RecordTableExt::<T>::range_records(&table, range)
+        })
+    }
+
+    pub fn iter<T: TypedTable>(&self) -> crate::Result<Vec<(T::Key, T::Record)>> {
+        self.range::<T>(..)
+    }
+
+    /// [`Database::read_table`] on a blocking thread, so long reads don't stall the async runtime.
+    /// Dropping the returned future before the read has started skips it.
+    pub async fn read_async<T: TypedTable, Output: Send + 'static>(
+        &self,
+        empty: impl FnOnce() -> Output + Send + 'static,
+        transaction: impl FnOnce(ReadOnlyRecordTable<T>) -> crate::Result<Output> + Send + 'static,
+    ) -> crate::Result<Output> {
+        self.read_transaction_async(move |txn| match txn.open_table(T::definition()) {
+            Ok(table) => transaction(table),
+            Err(TableError::TableDoesNotExist(_)) => Ok(empty()),
+            Err(err) => Err(err.into()),
+        })
+        .await?
+    }
+
+    /// [`Database::write_table`] on a blocking thread, so large writes don't stall the async
+    /// runtime. Dropping the returned future cancels the write: it is skipped if it hasn't started
+    /// yet, and rolled back rather than committed if it has.
+    pub async fn write_async<T: TypedTable, Output: Send + 'static>(
+        &self,
+        transaction: impl FnOnce(RecordTable<'_, T>) -> crate::Result<Output> + Send + 'static,
+    ) -> crate::Result<Output> {
+        self.write_transaction_async(move |txn| transaction(txn.open_table(T::definition())?))
+            .await?
+    }
+
+    /// [`Database::range`] on a blocking thread, for large reads from async code.
+    pub(crate) async fn range_async<T: TypedTable>(
+        &self,
+        range: impl RangeBounds<T::Key> + Send + 'static,
+    ) -> crate::Result<Vec<(T::Key, T::Record)>>
+    where
+        T::Key: Send,
+        T::Record: Send + 'static,
+    {
+        self.read_async::<T, _>(Vec::new, move |table| {
+            RecordTableExt::<T>::range_records(&table, range)
+        })
+        .await
+    }
+
+    pub(crate) async fn iter_async<T: TypedTable>(&self) -> crate::Result<Vec<(T::Key, T::Record)>>
+    where
+        T::Key: Send,
+        T::Record: Send + 'static,
+    {
+        self.range_async::<T>(..).await
+    }
+}
+
+impl<'txn> Transaction<'txn> {
+    /// Opens a typed table inside the transaction. Only the records written through
+    /// [`insert_record`] and [`remove_record`] are reported to subscribers.
+    pub fn records<T: TypedTable>(&self) -> crate::Result<RecordTable<'txn, T>> {
+        Ok(self.inner().open_table(T::definition())?)
+    }
+}
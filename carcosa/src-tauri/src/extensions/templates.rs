@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use tauri::{Manager, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{
+        insert_record, search,
+        sync::{settings_message, update_shared_settings, SyncMessage},
+        world::{ensure_hosted, EntityTable},
+        ApplicationExt, DatabasesExt, HistoryExt, RecordTableExt, SyncExt,
+    },
+    types::{Entity, EntityData, EntityTemplate, FieldChange},
+};
+
+pub trait TemplatesExt<R: Runtime> {
+    fn list_templates(&self) -> crate::Result<Vec<EntityTemplate>>;
+    fn get_template(&self, id: Uuid) -> crate::Result<EntityTemplate>;
+    fn create_template(&self, template: EntityTemplate) -> crate::Result<EntityTemplate>;
+    fn delete_template(&self, id: Uuid) -> crate::Result<EntityTemplate>;
+    fn evolve_template(
+        &self,
+        template: EntityTemplate,
+        changes: Vec<FieldChange>,
+    ) -> crate::Result<EntityTemplate>;
+}
+
+impl<R: Runtime, T: Manager<R>> TemplatesExt<R> for T {
+    fn list_templates(&self) -> crate::Result<Vec<EntityTemplate>> {
+        let settings = self
+            .get_app_state()
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)?;
+        Ok(settings.templates().into_values().collect())
+    }
+
+    fn get_template(&self, id: Uuid) -> crate::Result<EntityTemplate> {
+        self.get_app_state()
+            .project_settings()
+            .ok_or(crate::Error::NoActiveProject)?
+            .template(id)
+            .ok_or_else(|| crate::Error::not_found("template", id))
+    }
+
+    fn create_template(&self, template: EntityTemplate) -> crate::Result<EntityTemplate> {
+        let template = EntityTemplate {
+            id: Uuid::now_v7(),
+            ..template
+        };
+        template.validate_definition()?;
+        let _ = update_shared_settings(self, |settings| settings.with_template(template.clone()))?;
+        Ok(template)
+    }
+
+    fn delete_template(&self, id: Uuid) -> crate::Result<EntityTemplate> {
+        let template = self.get_template(id)?;
+        let in_use = self
+            .project_database()?
+            .iter::<EntityTable>()?
+            .into_iter()
+            .any(|(_, entity)| entity.data().template() == Some(id));
+        if in_use {
+            return Err(crate::Error::validation(
+                "id",
+                "Template is still used by existing entities",
+            ));
+        }
+        let _ = update_shared_settings(self, |settings| settings.remove_template(id))?;
+        Ok(template)
+    }
+
+    fn evolve_template(
+        &self,
+        template: EntityTemplate,
+        changes: Vec<FieldChange>,
+    ) -> crate::Result<EntityTemplate> {
+        ensure_hosted::<R>(self)?;
+        let previous = self.get_template(template.id)?;
+        template.validate_definition()?;
+
+        // The template is saved first, since project.json can't be rolled back along with the
+        // entities. If migrating them fails, the previous version is put back instead.
+        let settings =
+            self.update_project_settings(|settings| Ok(settings.with_template(template.clone())))?;
+        let db = self.project_database()?;
+        let written = db.transaction().write(|txn| -> crate::Result<Vec<Entity>> {
+            let mut table = txn.records::<EntityTable>()?;
+            let entities = RecordTableExt::<EntityTable>::range_records(&table, ..)?;
+            let kinds = entities
+                .iter()
+                .map(|(id, entity)| (*id, entity.kind()))
+                .collect::<HashMap<_, _>>();
+
+            let mut migrated = Vec::new();
+            for (id, entity) in entities {
+                if let EntityData::Custom {
+                    template: entity_template,
+                    fields,
+                } = entity.data()
+                {
+                    if entity_template != template.id {
+                        continue;
+                    }
+                    let fields = EntityTemplate::migrate_fields(&template, &changes, fields);
+                    template
+                        .validate(&fields, |target| kinds.get(&target).copied())
+                        .map_err(|err| match err {
+                            crate::Error::Validation { input, reason } => {
+                                crate::Error::validation(format!("entities.{id}.{input}"), reason)
+                            }
+                            other => other,
+                        })?;
+                    let entity = entity
+                        .with_data(EntityData::Custom {
+                            template: template.id,
+                            fields,
+                        })
+                        .touch();
+                    let _ = insert_record::<EntityTable>(&mut table, &id, &entity)?;
+                    migrated.push(entity);
+                }
+            }
+            drop(table);
+            for entity in &migrated {
+                search::index_in(txn.inner(), entity)?;
+            }
+            Ok(migrated)
+        });
+        let migrated = match written.and_then(|written| written) {
+            Ok(migrated) => migrated,
+            Err(err) => {
+                if let Err(restore) =
+                    self.update_project_settings(|settings| Ok(settings.with_template(previous)))
+                {
+                    log::error!(
+                        "Failed to restore template {} after a failed migration: {restore}",
+                        template.id
+                    );
+                }
+                return Err(err);
+            }
+        };
+        self.app_handle().reset_history();
+        self.app_handle()
+            .broadcast_message(settings_message(&settings), None);
+        for entity in migrated {
+            self.app_handle()
+                .broadcast_message(SyncMessage::EntityChanged { entity }, None);
+        }
+        Ok(template)
+    }
+}
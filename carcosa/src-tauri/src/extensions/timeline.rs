@@ -0,0 +1,235 @@
+use redb::{ReadTransaction, TableDefinition, TableError, WriteTransaction};
+use tauri::{Manager, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{
+        history::HistoryAction, insert_record, remove_record, sync::SyncMessage, table,
+        world::ensure_hosted, DatabasesExt, HistoryExt, RecordTableExt, SyncExt, TypedTable,
+        WorldExt, WriteTransactionExt,
+    },
+    types::{Calendar, TimelineEntry, TimelineEvent, TimelineQuery},
+};
+
+table!(pub TimelineEventTable: "timeline.events", Uuid => TimelineEvent);
+
+/// (start day, event) for every event, so the timeline can be read in date order.
+pub(crate) const BY_START: TableDefinition<(i32, Uuid), ()> =
+    TableDefinition::new("timeline.by_start");
+
+/// Stores an event, returning the version it replaced.
+pub(crate) fn insert_in(
+    txn: &WriteTransaction,
+    event: &TimelineEvent,
+) -> crate::Result<Option<TimelineEvent>> {
+    let mut events = txn.open_table(TimelineEventTable::definition())?;
+    let mut by_start = txn.open_table_mut(BY_START)?;
+    let previous = insert_record::<TimelineEventTable>(&mut events, &event.id, event)?;
+    if let Some(previous) = previous.as_ref() {
+        let _ = by_start.remove((previous.start, previous.id))?;
+    }
+    let _ = by_start.insert((event.start, event.id), ())?;
+    Ok(previous)
+}
+
+pub(crate) fn remove_in(txn: &WriteTransaction, id: Uuid) -> crate::Result<Option<TimelineEvent>> {
+    let mut events = txn.open_table(TimelineEventTable::definition())?;
+    let mut by_start = txn.open_table_mut(BY_START)?;
+    let removed = remove_record::<TimelineEventTable>(&mut events, &id)?;
+    if let Some(removed) = removed.as_ref() {
+        let _ = by_start.remove((removed.start, removed.id))?;
+    }
+    Ok(removed)
+}
+
+/// Every event starting on or before `until`, in order of their start day.
+pub(crate) fn starting_until(
+    txn: &ReadTransaction,
+    until: Option<i32>,
+) -> crate::Result<Vec<TimelineEvent>> {
+    let (events, by_start) = match (
+        txn.open_table(TimelineEventTable::definition()),
+        txn.open_table(BY_START),
+    ) {
+        (Ok(events), Ok(by_start)) => (events, by_start),
+        (Err(TableError::TableDoesNotExist(_)), _) | (_, Err(TableError::TableDoesNotExist(_))) => {
+            return Ok(Vec::new())
+        }
+        (Err(err), _) | (_, Err(err)) => return Err(err.into()),
+    };
+    let mut result = Vec::new();
+    let range = match until {
+        Some(until) => by_start.range(..=(until, Uuid::max()))?,
+        None => by_start.range::<(i32, Uuid)>(..)?,
+    };
+    for entry in range {
+        let (_, id) = entry?.0.value();
+        if let Some(event) = RecordTableExt::<TimelineEventTable>::get_record(&events, &id)? {
+            result.push(event);
+        }
+    }
+    Ok(result)
+}
+
+/// The events matching `query` among `events`, with their dates described in `calendar` if
+/// one is given.
+pub(crate) fn entries(
+    events: Vec<TimelineEvent>,
+    query: &TimelineQuery,
+    calendar: Option<&Calendar>,
+) -> crate::Result<Vec<TimelineEntry>> {
+    let mut entries = Vec::new();
+    for event in events {
+        if !query.matches(&event) {
+            continue;
+        }
+        let (start, end) = match calendar {
+            Some(calendar) => (
+                Some(calendar.describe(event.start)?),
+                event.end.map(|end| calendar.describe(end)).transpose()?,
+            ),
+            None => (None, None),
+        };
+        entries.push(TimelineEntry { event, start, end });
+    }
+    Ok(entries)
+}
+
+/// Every stored event, in order of their start day.
+pub(crate) fn all_in(txn: &ReadTransaction) -> crate::Result<Vec<TimelineEvent>> {
+    starting_until(txn, None)
+}
+
+/// Replaces every stored event with `events`.
+pub(crate) fn replace_all_in(
+    txn: &WriteTransaction,
+    events: &[TimelineEvent],
+) -> crate::Result<()> {
+    let _ = txn.clear_table(TimelineEventTable::definition())?;
+    let _ = txn.clear_table(BY_START)?;
+    for event in events {
+        let _ = insert_in(txn, event)?;
+    }
+    Ok(())
+}
+
+/// Detaches a deleted entity from every event it was attached to.
+pub(crate) fn detach_in(txn: &WriteTransaction, entity: Uuid) -> crate::Result<()> {
+    let attached = {
+        let events = txn.open_table(TimelineEventTable::definition())?;
+        RecordTableExt::<TimelineEventTable>::range_records(&events, ..)?
+            .into_iter()
+            .filter(|(_, event)| event.entities.contains(&entity))
+            .collect::<Vec<_>>()
+    };
+    for (_, mut event) in attached {
+        event.entities.retain(|id| *id != entity);
+        let _ = insert_in(txn, &event)?;
+    }
+    Ok(())
+}
+
+pub trait TimelineExt<R: Runtime> {
+    fn get_event(&self, id: Uuid) -> crate::Result<TimelineEvent>;
+    fn create_event(&self, event: TimelineEvent) -> crate::Result<TimelineEvent>;
+
+    /// Stores a new event under the ID it already has, ie. when redoing its creation.
+    fn insert_event(&self, event: TimelineEvent) -> crate::Result<TimelineEvent>;
+    fn update_event(&self, event: TimelineEvent) -> crate::Result<TimelineEvent>;
+    fn delete_event(&self, id: Uuid) -> crate::Result<TimelineEvent>;
+    fn validate_event(&self, event: &TimelineEvent) -> crate::Result<()>;
+}
+
+impl<R: Runtime, T: Manager<R>> TimelineExt<R> for T {
+    fn get_event(&self, id: Uuid) -> crate::Result<TimelineEvent> {
+        self.project_database()?
+            .get::<TimelineEventTable>(&id)?
+            .ok_or_else(|| crate::Error::not_found("event", id))
+    }
+
+    fn create_event(&self, event: TimelineEvent) -> crate::Result<TimelineEvent> {
+        self.insert_event(TimelineEvent {
+            id: Uuid::now_v7(),
+            ..event
+        })
+    }
+
+    fn insert_event(&self, event: TimelineEvent) -> crate::Result<TimelineEvent> {
+        ensure_hosted::<R>(self)?;
+        self.validate_event(&event)?;
+        let db = self.project_database()?;
+        if db.get::<TimelineEventTable>(&event.id)?.is_some() {
+            return Err(crate::Error::validation(
+                "id",
+                format!("An event with ID {} already exists", event.id),
+            ));
+        }
+        let _ = self.app_handle().undoable(
+            format!("Create event {}", event.title),
+            HistoryAction::CreateEvent {
+                event: event.clone(),
+            },
+            || db.write_transaction(|txn| insert_in(txn, &event))?,
+        )?;
+        self.app_handle().broadcast_message(
+            SyncMessage::EventChanged {
+                event: event.clone(),
+            },
+            None,
+        );
+        Ok(event)
+    }
+
+    fn update_event(&self, event: TimelineEvent) -> crate::Result<TimelineEvent> {
+        ensure_hosted::<R>(self)?;
+        let existing = self.get_event(event.id)?;
+        self.validate_event(&event)?;
+        let _ = self.app_handle().undoable(
+            format!("Edit event {}", existing.title),
+            HistoryAction::UpdateEvent {
+                event: event.clone(),
+            },
+            || {
+                self.project_database()?
+                    .write_transaction(|txn| insert_in(txn, &event))?
+            },
+        )?;
+        self.app_handle().broadcast_message(
+            SyncMessage::EventChanged {
+                event: event.clone(),
+            },
+            None,
+        );
+        Ok(event)
+    }
+
+    fn delete_event(&self, id: Uuid) -> crate::Result<TimelineEvent> {
+        ensure_hosted::<R>(self)?;
+        let existing = self.get_event(id)?;
+        let removed = self.app_handle().undoable(
+            format!("Delete event {}", existing.title),
+            HistoryAction::DeleteEvent { id },
+            || {
+                self.project_database()?
+                    .write_transaction(|txn| remove_in(txn, id))??
+                    .ok_or_else(|| crate::Error::not_found("event", id))
+            },
+        )?;
+        self.app_handle()
+            .broadcast_message(SyncMessage::EventRemoved { id }, None);
+        Ok(removed)
+    }
+
+    fn validate_event(&self, event: &TimelineEvent) -> crate::Result<()> {
+        event.validate()?;
+        for (index, entity) in event.entities.iter().enumerate() {
+            if self.get_entity(*entity).is_err() {
+                return Err(crate::Error::validation(
+                    format!("entities.{index}"),
+                    format!("No entity exists with ID {entity}"),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
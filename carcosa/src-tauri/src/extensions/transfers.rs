@@ -0,0 +1,419 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use iroh::{
+    endpoint::{Connection, RecvStream, SendStream},
+    protocol::{AcceptError, ProtocolHandler},
+    EndpointAddr,
+};
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+
+use crate::{
+    extensions::{
+        assets::{asset_path, AssetTable, MAX_ASSET_SIZE},
+        sync::{is_member, SyncNode},
+        ApplicationExt, AssetsExt, DatabasesExt, SyncExt,
+    },
+    procedures::{AppEvent, AppEventExt},
+    types::{ActiveProject, AssetInfo, PeerIdentity},
+    MetaError,
+};
+
+/// ALPN identifying the protocol peers use to fetch asset files from each other.
+///
+/// This is a small protocol of its own rather than iroh-blobs. Assets already live as plain files
+/// named after their BLAKE3 hash in the project directory, where the asset table, garbage
+/// collection and backups expect them, whereas iroh-blobs manages a content store of its own that
+/// would have to be kept in step with all of those. Only collaborators may fetch assets, which is
+/// checked once per connection here. Resuming from an offset and verifying the hash once the file
+/// is complete is all the app needs.
+pub const ASSET_ALPN: &[u8] = b"carcosa/assets/1";
+
+const MAX_REQUEST_SIZE: usize = 1024;
+const MAX_HEADER_SIZE: u32 = 64 * 1024;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How many bytes are received between progress events.
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+/// Asks for the contents of an asset, starting `offset` bytes in.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AssetRequest {
+    pub hash: String,
+    pub offset: u64,
+}
+
+/// Sent ahead of the file contents. The contents follow on the same stream, starting at `offset`,
+/// which is the requested offset unless that was past the end of the file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum AssetResponse {
+    Found { asset: AssetInfo, offset: u64 },
+    Missing,
+}
+
+/// One lock per asset hash, so concurrent fetches of the same asset never share a partial file.
+pub type TransferState = Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>;
+
+/// A fetch's hold on the lock for its hash, which drops the lock from [`TransferState`] once no
+/// other fetch of the same asset is waiting on it.
+struct TransferLock {
+    state: TransferState,
+    hash: String,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl TransferLock {
+    fn new(state: TransferState, hash: &str) -> Self {
+        let lock = state.lock().entry(hash.to_string()).or_default().clone();
+        Self {
+            state,
+            hash: hash.to_string(),
+            lock,
+        }
+    }
+}
+
+impl Drop for TransferLock {
+    fn drop(&mut self) {
+        let mut locks = self.state.lock();
+        // Other fetches only clone the lock while holding the state, so if just the map and this
+        // fetch refer to it, nobody else can be waiting.
+        if Arc::strong_count(&self.lock) <= 2 {
+            let _ = locks.remove(&self.hash);
+        }
+    }
+}
+
+/// Headers are sent as JSON, prefixed by their length as a big-endian `u32`.
+async fn write_header<T: Serialize>(send: &mut SendStream, header: &T) -> crate::Result<()> {
+    let data = serde_json::to_vec(header)?;
+    send.write_all(&(data.len() as u32).to_be_bytes())
+        .await
+        .map_err(crate::Error::network)?;
+    send.write_all(&data).await.map_err(crate::Error::network)?;
+    Ok(())
+}
+
+async fn read_header<T: DeserializeOwned>(recv: &mut RecvStream) -> crate::Result<T> {
+    let mut length = [0u8; 4];
+    recv.read_exact(&mut length)
+        .await
+        .map_err(crate::Error::network)?;
+    let length = u32::from_be_bytes(length);
+    if length > MAX_HEADER_SIZE {
+        return Err(crate::Error::Network(format!(
+            "Header of {length} bytes is too large"
+        )));
+    }
+    let mut data = vec![0u8; length as usize];
+    recv.read_exact(&mut data)
+        .await
+        .map_err(crate::Error::network)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+fn project_dir<R: Runtime>(app: &AppHandle<R>) -> crate::Result<PathBuf> {
+    app.get_app_state()
+        .active_project()
+        .path()
+        .ok_or(crate::Error::NoActiveProject)
+}
+
+fn blake3_hash(path: &Path) -> crate::Result<String> {
+    Ok(blake3::Hasher::new()
+        .update_reader(File::open(path)?)?
+        .finalize()
+        .to_hex()
+        .to_string())
+}
+
+#[derive(Clone)]
+pub(crate) struct AssetProtocol<R: Runtime> {
+    pub(crate) app: AppHandle<R>,
+}
+
+impl<R: Runtime> std::fmt::Debug for AssetProtocol<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssetProtocol").finish()
+    }
+}
+
+impl<R: Runtime> AssetProtocol<R> {
+    /// Answers a single request for an asset.
+    async fn serve(&self, mut send: SendStream, mut recv: RecvStream) -> crate::Result<()> {
+        let data = recv
+            .read_to_end(MAX_REQUEST_SIZE)
+            .await
+            .map_err(crate::Error::network)?;
+        let request = serde_json::from_slice::<AssetRequest>(&data)?;
+        let stored = self.app.get_asset(&request.hash).ok().and_then(|asset| {
+            let path = asset_path(project_dir(&self.app).ok()?, &asset.hash).ok()?;
+            path.exists().then_some((asset, path))
+        });
+        let Some((asset, path)) = stored else {
+            write_header(&mut send, &AssetResponse::Missing).await?;
+            send.finish().map_err(crate::Error::network)?;
+            return Ok(());
+        };
+
+        let offset = if request.offset > asset.size as u64 {
+            0
+        } else {
+            request.offset
+        };
+        let mut file = tokio::fs::File::open(&path).await?;
+        let _ = file.seek(SeekFrom::Start(offset)).await?;
+        write_header(&mut send, &AssetResponse::Found { asset, offset }).await?;
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            send.write_all(&buffer[..read])
+                .await
+                .map_err(crate::Error::network)?;
+        }
+        send.finish().map_err(crate::Error::network)?;
+        Ok(())
+    }
+}
+
+impl<R: Runtime> ProtocolHandler for AssetProtocol<R> {
+    async fn accept(&self, connection: Connection) -> Result<(), AcceptError> {
+        let peer = PeerIdentity::from(connection.remote_id());
+        if !is_member(&self.app, &peer) {
+            log::info!("Rejected asset request from {}", peer.short_format());
+            connection.close(1u32.into(), b"not a collaborator");
+            return Ok(());
+        }
+        while let Ok((send, recv)) = connection.accept_bi().await {
+            if let Err(err) = self.serve(send, recv).await {
+                log::warn!("Failed to send an asset to {}: {err}", peer.short_format());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Downloads an asset from `peer` into `partial`, continuing from however much of it is already
+/// there. Returns `None` if the peer doesn't have the asset.
+async fn download<R: Runtime>(
+    app: &AppHandle<R>,
+    node: &SyncNode,
+    peer: &PeerIdentity,
+    hash: &str,
+    partial: &Path,
+) -> crate::Result<Option<AssetInfo>> {
+    let connection = node
+        .endpoint()
+        .connect(EndpointAddr::from(peer.clone().into_inner()), ASSET_ALPN)
+        .await
+        .map_err(crate::Error::network)?;
+    let downloaded = async {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(partial)
+            .await?;
+        let request = AssetRequest {
+            hash: hash.to_string(),
+            offset: file.metadata().await?.len(),
+        };
+        let (mut send, mut recv) = connection.open_bi().await.map_err(crate::Error::network)?;
+        send.write_all(&serde_json::to_vec(&request)?)
+            .await
+            .map_err(crate::Error::network)?;
+        send.finish().map_err(crate::Error::network)?;
+
+        let (asset, offset) = match read_header::<AssetResponse>(&mut recv).await? {
+            AssetResponse::Found { asset, offset } => (asset, offset),
+            AssetResponse::Missing => return Ok(None),
+        };
+        let total = asset.size as u64;
+        if asset.hash != hash || total > MAX_ASSET_SIZE || offset > total {
+            return Err(crate::Error::Network(format!(
+                "{} sent an invalid asset header",
+                peer.short_format()
+            )));
+        }
+        if offset != request.offset {
+            // The partial file was longer than the asset, so start over. Appends follow the
+            // truncated length.
+            file.set_len(offset).await?;
+        }
+
+        let mut received = offset;
+        let mut reported = offset;
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        while let Some(read) = recv
+            .read(&mut buffer)
+            .await
+            .map_err(crate::Error::network)?
+        {
+            received += read as u64;
+            if received > total {
+                return Err(crate::Error::Network(format!(
+                    "{} sent more data than the asset holds",
+                    peer.short_format()
+                )));
+            }
+            file.write_all(&buffer[..read]).await?;
+            if received - reported >= PROGRESS_STEP || received == total {
+                reported = received;
+                let _ = app.emit_event(AppEvent::AssetTransferProgress {
+                    hash: hash.to_string(),
+                    peer: peer.clone(),
+                    received: received as u32,
+                    total: total as u32,
+                });
+            }
+        }
+        file.flush().await?;
+        if received != total {
+            return Err(crate::Error::Network(format!(
+                "Transfer from {} ended after {received} of {total} bytes",
+                peer.short_format()
+            )));
+        }
+        Ok::<_, crate::Error>(Some(asset))
+    }
+    .await;
+    connection.close(0u32.into(), b"done");
+    downloaded
+}
+
+#[async_trait::async_trait]
+pub trait TransfersExt<R: Runtime> {
+    fn transfer_state(&self) -> TransferState;
+
+    /// Makes sure the asset with `hash` is stored locally, fetching it from whichever connected
+    /// peer has it if it isn't. Interrupted transfers pick up where they left off next time.
+    async fn fetch_asset(&self, hash: &str) -> crate::Result<AssetInfo>;
+
+    /// Reads an asset's contents, fetching it first if it isn't stored locally.
+    async fn load_asset(&self, hash: &str) -> crate::Result<Vec<u8>>;
+}
+
+#[async_trait::async_trait]
+impl<R: Runtime, T: Manager<R> + Sync> TransfersExt<R> for T {
+    fn transfer_state(&self) -> TransferState {
+        if let Some(existing) = self.try_state::<TransferState>() {
+            existing.inner().clone()
+        } else {
+            self.manage::<TransferState>(Arc::new(Mutex::new(HashMap::new())));
+            self.state::<TransferState>().inner().clone()
+        }
+    }
+
+    async fn fetch_asset(&self, hash: &str) -> crate::Result<AssetInfo> {
+        let app = self.app_handle().clone();
+        let target = asset_path(project_dir(&app)?, hash)?;
+        let transfer = TransferLock::new(self.transfer_state(), hash);
+        let _guard = transfer.lock.lock().await;
+        if target.exists() {
+            if let Ok(asset) = self.get_asset(hash) {
+                return Ok(asset);
+            }
+        }
+
+        let node = self.sync_node()?;
+        let mut peers = node.peers();
+        // The host holds every asset its project references, so ask it first.
+        if let ActiveProject::Remote { host, .. } = self.get_app_state().active_project() {
+            peers.sort_by_key(|peer| *peer != host);
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let partial = target.with_extension("partial");
+
+        let mut failure = crate::Error::not_found("asset", hash);
+        for peer in peers {
+            let asset = match download(&app, &node, &peer, hash, &partial).await {
+                Ok(Some(asset)) => asset,
+                Ok(None) => continue,
+                Err(err) => {
+                    log::warn!(
+                        "Failed to fetch asset {hash} from {}: {err}",
+                        peer.short_format()
+                    );
+                    failure = err;
+                    continue;
+                }
+            };
+
+            let verify = partial.clone();
+            let actual =
+                tauri::async_runtime::spawn_blocking(move || blake3_hash(&verify)).await??;
+            if actual != hash {
+                log::warn!(
+                    "Asset {hash} from {} failed verification",
+                    peer.short_format()
+                );
+                fs::remove_file(&partial).await?;
+                failure = crate::Error::Network(format!(
+                    "{} sent corrupted contents for asset {hash}",
+                    peer.short_format()
+                ));
+                continue;
+            }
+            fs::rename(&partial, &target).await?;
+            let asset = match self.get_asset(hash) {
+                Ok(existing) => existing,
+                Err(_) => {
+                    let _ = self
+                        .project_database()?
+                        .insert::<AssetTable>(&asset.hash, &asset)?;
+                    asset
+                }
+            };
+            let _ = self.emit_event(AppEvent::AssetTransferred {
+                hash: hash.to_string(),
+                peer,
+            });
+            return Ok(asset);
+        }
+
+        let _ = self.emit_event(AppEvent::AssetTransferFailed {
+            hash: hash.to_string(),
+            error: MetaError::from(&failure),
+        });
+        Err(failure)
+    }
+
+    async fn load_asset(&self, hash: &str) -> crate::Result<Vec<u8>> {
+        let asset = self.fetch_asset(hash).await?;
+        Ok(fs::read(asset_path(project_dir(self.app_handle())?, &asset.hash)?).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_locks_once_the_last_fetch_is_done() {
+        let state = TransferState::default();
+        let first = TransferLock::new(state.clone(), "a");
+        let second = TransferLock::new(state.clone(), "a");
+        assert!(Arc::ptr_eq(&first.lock, &second.lock));
+
+        drop(first);
+        assert!(state.lock().contains_key("a"));
+        drop(second);
+        assert!(state.lock().is_empty());
+    }
+}
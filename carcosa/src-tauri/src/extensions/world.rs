@@ -0,0 +1,205 @@
+use tauri::{Manager, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{
+        assets, documents,
+        history::HistoryAction,
+        insert_record, links,
+        maps::{self, MapChanges},
+        relations, remove_record, search,
+        sync::SyncMessage,
+        table, timeline, ApplicationExt, DatabasesExt, HistoryExt, SyncExt, Transaction,
+    },
+    types::{ActiveProject, Entity, EntityData, EntityUpdate, Retarget},
+};
+
+table!(pub EntityTable: "world.entities", Uuid => Entity);
+
+/// Entities of a remote project are mirrored from its host, so only the host may change them.
+pub(crate) fn ensure_hosted<R: Runtime>(app: &impl Manager<R>) -> crate::Result<()> {
+    match app.get_app_state().active_project() {
+        ActiveProject::Remote { .. } => Err(crate::Error::validation(
+            "project",
+            "Entities of a remote project can only be changed by its host",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Removes an entity along with its document, search entry, links, relations, timeline and asset
+/// references, all in one transaction.
+pub(crate) fn remove_entity_in(txn: &Transaction<'_>, id: Uuid) -> crate::Result<Option<Entity>> {
+    let removed = remove_record::<EntityTable>(&mut txn.records::<EntityTable>()?, &id)?;
+    let txn = txn.inner();
+    documents::delete_in(txn, id)?;
+    search::unindex_in(txn, id)?;
+    links::unlink_in(txn, id)?;
+    let _ = relations::remove_all_in(txn, id)?;
+    timeline::detach_in(txn, id)?;
+    assets::detach_all_in(txn, id)?;
+    Ok(removed)
+}
+
+pub trait WorldExt<R: Runtime> {
+    fn create_entity(&self, title: impl Into<String>, data: EntityData) -> crate::Result<Entity>;
+
+    /// Stores a new entity under the ID it already has, ie. when redoing its creation.
+    fn insert_entity(&self, entity: Entity) -> crate::Result<Entity>;
+    fn get_entity(&self, id: Uuid) -> crate::Result<Entity>;
+    fn update_entity(&self, id: Uuid, update: EntityUpdate) -> crate::Result<Entity>;
+    fn delete_entity(&self, id: Uuid) -> crate::Result<Entity>;
+    fn validate_entity(&self, entity: &Entity) -> crate::Result<()>;
+}
+
+impl<R: Runtime, T: Manager<R>> WorldExt<R> for T {
+    fn create_entity(&self, title: impl Into<String>, data: EntityData) -> crate::Result<Entity> {
+        self.insert_entity(Entity::new(title, data))
+    }
+
+    fn insert_entity(&self, entity: Entity) -> crate::Result<Entity> {
+        ensure_hosted::<R>(self)?;
+        self.validate_entity(&entity)?;
+        let db = self.project_database()?;
+        if db.get::<EntityTable>(&entity.id())?.is_some() {
+            return Err(crate::Error::validation(
+                "id",
+                format!("An entity with ID {} already exists", entity.id()),
+            ));
+        }
+        self.app_handle().undoable(
+            format!("Create {}", entity.title()),
+            HistoryAction::CreateEntity {
+                entity: entity.clone(),
+            },
+            || {
+                db.transaction().write(|txn| -> crate::Result<()> {
+                    let _ = insert_record::<EntityTable>(
+                        &mut txn.records::<EntityTable>()?,
+                        &entity.id(),
+                        &entity,
+                    )?;
+                    search::index_in(txn.inner(), &entity)
+                })?
+            },
+        )?;
+        self.app_handle().broadcast_message(
+            SyncMessage::EntityChanged {
+                entity: entity.clone(),
+            },
+            None,
+        );
+        Ok(entity)
+    }
+
+    fn get_entity(&self, id: Uuid) -> crate::Result<Entity> {
+        self.project_database()?
+            .get::<EntityTable>(&id)?
+            .ok_or_else(|| crate::Error::not_found("entity", id))
+    }
+
+    fn update_entity(&self, id: Uuid, update: EntityUpdate) -> crate::Result<Entity> {
+        ensure_hosted::<R>(self)?;
+        let existing = self.get_entity(id)?;
+        if let Some(data) = update.data.as_ref() {
+            if data.kind() != existing.kind() {
+                return Err(crate::Error::validation(
+                    "data",
+                    format!("Cannot change a {} into a {}", existing.kind(), data.kind()),
+                ));
+            }
+        }
+        if update
+            .data
+            .as_ref()
+            .is_some_and(|data| data.template() != existing.data().template())
+        {
+            return Err(crate::Error::validation(
+                "data.template",
+                "Cannot change the template of an existing entity",
+            ));
+        }
+        let action = HistoryAction::UpdateEntity {
+            id,
+            update: update.clone(),
+        };
+        let updated = update.apply(existing.clone());
+        self.validate_entity(&updated)?;
+        let retargets = Retarget::between(&existing, &updated);
+        let label = format!("Edit {}", existing.title());
+        let rewritten = self.app_handle().undoable(label, action, || {
+            self.project_database()?.transaction().write(
+                |txn| -> crate::Result<Vec<(Uuid, Vec<u8>)>> {
+                    let _ = insert_record::<EntityTable>(
+                        &mut txn.records::<EntityTable>()?,
+                        &id,
+                        &updated,
+                    )?;
+                    search::index_in(txn.inner(), &updated)?;
+                    // Renaming the entity rewrites the links that used its old names.
+                    let mut rewritten = Vec::new();
+                    for retarget in &retargets {
+                        rewritten.extend(links::retarget_in(txn.inner(), retarget)?);
+                    }
+                    Ok(rewritten)
+                },
+            )?
+        })?;
+        self.app_handle().broadcast_message(
+            SyncMessage::EntityChanged {
+                entity: updated.clone(),
+            },
+            None,
+        );
+        for (document, update) in rewritten {
+            self.app_handle()
+                .broadcast_document_update(document, update, None);
+        }
+        Ok(updated)
+    }
+
+    fn delete_entity(&self, id: Uuid) -> crate::Result<Entity> {
+        ensure_hosted::<R>(self)?;
+        let existing = self.get_entity(id)?;
+        let removed = self.app_handle().undoable(
+            format!("Delete {}", existing.title()),
+            HistoryAction::DeleteEntity { id },
+            || {
+                self.project_database()?.transaction().write(
+                    |txn| -> crate::Result<(Entity, MapChanges)> {
+                        let removed = remove_entity_in(txn, id)?
+                            .ok_or_else(|| crate::Error::not_found("entity", id))?;
+                        // Maps derive relations of their own, so they're detached once the entity's
+                        // relations are gone.
+                        let changes = maps::detach_entity_in(txn.inner(), id)?;
+                        Ok((removed, changes))
+                    },
+                )?
+            },
+        )?;
+        let (removed, changes) = removed;
+        self.app_handle()
+            .broadcast_message(SyncMessage::EntityRemoved { id }, None);
+        changes.broadcast(self.app_handle());
+        Ok(removed)
+    }
+
+    fn validate_entity(&self, entity: &Entity) -> crate::Result<()> {
+        entity.validate()?;
+        if let EntityData::Custom { template, fields } = entity.data() {
+            let template = self
+                .get_app_state()
+                .project_settings()
+                .and_then(|settings| settings.template(template))
+                .ok_or_else(|| crate::Error::not_found("template", template))?;
+            let db = self.project_database()?;
+            template.validate(&fields, |id| {
+                db.get::<EntityTable>(&id)
+                    .ok()
+                    .flatten()
+                    .map(|entity| entity.kind())
+            })?;
+        }
+        Ok(())
+    }
+}
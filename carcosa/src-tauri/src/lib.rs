@@ -6,6 +6,8 @@ mod error;
 pub use error::*;
 use tauri::Manager;
 
+use crate::extensions::{ApplicationExt, LoggingExt};
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -14,8 +16,19 @@ pub fn run() {
         .plugin(tauri_plugin_persisted_scope::init())
         .invoke_handler(procedures::handler())
         .setup(|app| {
-                app.manage(types::ApplicationState::default());
-                Ok(())
+            app.manage(types::ApplicationStateWrapper::default());
+            let settings = app.handle().load_app_settings();
+            let level = settings.as_ref().map(|s| s.log_level).unwrap_or_default();
+            if let Err(err) = app.handle().init_logging(level) {
+                eprintln!("Failed to initialize logging: {err}");
+            }
+            if let Err(err) = settings {
+                log::warn!("Failed to load app settings: {err}");
+            }
+            if let Err(err) = app.handle().restore_last_project() {
+                log::warn!("Failed to reopen the last project: {err}");
+            }
+            Ok(())
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{assets::AssetTable, AssetsExt, DatabasesExt, RecordTableExt, TransfersExt},
+    types::{AssetInfo, AssetUsage},
+};
+
+#[taurpc::procedures(path = "assets")]
+pub trait AssetsApi {
+    async fn import_asset<R: Runtime>(
+        app_handle: AppHandle<R>,
+        source: String,
+    ) -> crate::MetaResult<AssetInfo>;
+    async fn get_asset<R: Runtime>(
+        app_handle: AppHandle<R>,
+        hash: String,
+    ) -> crate::MetaResult<AssetInfo>;
+    async fn list_assets<R: Runtime>(app_handle: AppHandle<R>)
+        -> crate::MetaResult<Vec<AssetInfo>>;
+    async fn read_asset<R: Runtime>(
+        app_handle: AppHandle<R>,
+        hash: String,
+    ) -> crate::MetaResult<Vec<u8>>;
+    async fn fetch<R: Runtime>(
+        app_handle: AppHandle<R>,
+        hash: String,
+    ) -> crate::MetaResult<AssetInfo>;
+    async fn usage<R: Runtime>(
+        app_handle: AppHandle<R>,
+        hash: String,
+    ) -> crate::MetaResult<AssetUsage>;
+    async fn entity_assets<R: Runtime>(
+        app_handle: AppHandle<R>,
+        entity: Uuid,
+    ) -> crate::MetaResult<Vec<AssetInfo>>;
+    async fn attach<R: Runtime>(
+        app_handle: AppHandle<R>,
+        entity: Uuid,
+        hash: String,
+    ) -> crate::MetaResult<()>;
+    async fn detach<R: Runtime>(
+        app_handle: AppHandle<R>,
+        entity: Uuid,
+        hash: String,
+    ) -> crate::MetaResult<bool>;
+    async fn collect_garbage<R: Runtime>(
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<AssetInfo>>;
+    async fn directory<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<String>;
+}
+
+#[derive(Clone)]
+pub struct AssetsApiImpl;
+
+#[taurpc::resolvers]
+impl AssetsApi for AssetsApiImpl {
+    async fn import_asset<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        source: String,
+    ) -> crate::MetaResult<AssetInfo> {
+        // Hashing and copying large files takes a while, so keep it off the async runtime.
+        Ok(tauri::async_runtime::spawn_blocking(move || {
+            app_handle.import_asset(PathBuf::from(source))
+        })
+        .await??)
+    }
+
+    async fn get_asset<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        hash: String,
+    ) -> crate::MetaResult<AssetInfo> {
+        Ok(app_handle.get_asset(&hash)?)
+    }
+
+    async fn list_assets<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<AssetInfo>> {
+        Ok(app_handle
+            .project_database()?
+            .read_async::<AssetTable, _>(Vec::new, |table| {
+                Ok(RecordTableExt::<AssetTable>::range_records(&table, ..)?
+                    .into_iter()
+                    .map(|(_, asset)| asset)
+                    .collect())
+            })
+            .await?)
+    }
+
+    async fn read_asset<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        hash: String,
+    ) -> crate::MetaResult<Vec<u8>> {
+        Ok(app_handle.load_asset(&hash).await?)
+    }
+
+    async fn fetch<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        hash: String,
+    ) -> crate::MetaResult<AssetInfo> {
+        Ok(app_handle.fetch_asset(&hash).await?)
+    }
+
+    async fn usage<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        hash: String,
+    ) -> crate::MetaResult<AssetUsage> {
+        Ok(app_handle.asset_usage(&hash)?)
+    }
+
+    async fn entity_assets<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        entity: Uuid,
+    ) -> crate::MetaResult<Vec<AssetInfo>> {
+        Ok(app_handle.entity_assets(entity)?)
+    }
+
+    async fn attach<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        entity: Uuid,
+        hash: String,
+    ) -> crate::MetaResult<()> {
+        Ok(app_handle.attach_asset(entity, &hash)?)
+    }
+
+    async fn detach<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        entity: Uuid,
+        hash: String,
+    ) -> crate::MetaResult<bool> {
+        Ok(app_handle.detach_asset(entity, &hash)?)
+    }
+
+    async fn collect_garbage<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<AssetInfo>> {
+        Ok(app_handle.collect_garbage()?)
+    }
+
+    async fn directory<R: Runtime>(self, app_handle: AppHandle<R>) -> crate::MetaResult<String> {
+        Ok(app_handle.assets_dir()?.to_string_lossy().to_string())
+    }
+}
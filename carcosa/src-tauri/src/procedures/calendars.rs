@@ -0,0 +1,103 @@
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::CalendarsExt,
+    types::{Calendar, CalendarDate, DescribedDate},
+};
+
+#[taurpc::procedures(path = "calendars")]
+pub trait CalendarsApi {
+    async fn list_calendars<R: Runtime>(
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<Calendar>>;
+    async fn get_calendar<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Calendar>;
+    async fn create_calendar<R: Runtime>(
+        app_handle: AppHandle<R>,
+        calendar: Calendar,
+    ) -> crate::MetaResult<Calendar>;
+    async fn update_calendar<R: Runtime>(
+        app_handle: AppHandle<R>,
+        calendar: Calendar,
+    ) -> crate::MetaResult<Calendar>;
+    async fn delete_calendar<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Calendar>;
+    async fn to_days<R: Runtime>(
+        app_handle: AppHandle<R>,
+        calendar: Uuid,
+        date: CalendarDate,
+    ) -> crate::MetaResult<i32>;
+    async fn from_days<R: Runtime>(
+        app_handle: AppHandle<R>,
+        calendar: Uuid,
+        days: i32,
+    ) -> crate::MetaResult<DescribedDate>;
+}
+
+#[derive(Clone)]
+pub struct CalendarsApiImpl;
+
+#[taurpc::resolvers]
+impl CalendarsApi for CalendarsApiImpl {
+    async fn list_calendars<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<Calendar>> {
+        Ok(app_handle.list_calendars()?)
+    }
+
+    async fn get_calendar<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Calendar> {
+        Ok(app_handle.get_calendar(id)?)
+    }
+
+    async fn create_calendar<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        calendar: Calendar,
+    ) -> crate::MetaResult<Calendar> {
+        Ok(app_handle.create_calendar(calendar)?)
+    }
+
+    async fn update_calendar<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        calendar: Calendar,
+    ) -> crate::MetaResult<Calendar> {
+        Ok(app_handle.update_calendar(calendar)?)
+    }
+
+    async fn delete_calendar<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Calendar> {
+        Ok(app_handle.delete_calendar(id)?)
+    }
+
+    async fn to_days<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        calendar: Uuid,
+        date: CalendarDate,
+    ) -> crate::MetaResult<i32> {
+        Ok(app_handle.date_to_days(calendar, date)?)
+    }
+
+    async fn from_days<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        calendar: Uuid,
+        days: i32,
+    ) -> crate::MetaResult<DescribedDate> {
+        Ok(app_handle.describe_day(calendar, days)?)
+    }
+}
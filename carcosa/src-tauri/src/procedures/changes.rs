@@ -0,0 +1,38 @@
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{extensions::ChangeFeedExt, types::ChangeSubscription};
+
+#[taurpc::procedures(path = "changes")]
+pub trait ChangesApi {
+    async fn subscribe<R: Runtime>(
+        app_handle: AppHandle<R>,
+        database: Option<String>,
+        tables: Vec<String>,
+    ) -> crate::MetaResult<ChangeSubscription>;
+    async fn unsubscribe<R: Runtime>(app_handle: AppHandle<R>, id: Uuid)
+        -> crate::MetaResult<bool>;
+}
+
+#[derive(Clone)]
+pub struct ChangesApiImpl;
+
+#[taurpc::resolvers]
+impl ChangesApi for ChangesApiImpl {
+    async fn subscribe<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        database: Option<String>,
+        tables: Vec<String>,
+    ) -> crate::MetaResult<ChangeSubscription> {
+        Ok(app_handle.subscribe_changes(database, tables)?)
+    }
+
+    async fn unsubscribe<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<bool> {
+        Ok(app_handle.unsubscribe_changes(id))
+    }
+}
@@ -0,0 +1,76 @@
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::extensions::{
+    documents, world::EntityTable, DatabasesExt, HistoryExt, RecordTableExt, SyncExt, TypedTable,
+};
+
+#[taurpc::procedures(path = "documents")]
+pub trait DocumentsApi {
+    async fn state_vector<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Vec<u8>>;
+    async fn diff<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        state_vector: Vec<u8>,
+    ) -> crate::MetaResult<Vec<u8>>;
+    async fn apply_update<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        update: Vec<u8>,
+    ) -> crate::MetaResult<()>;
+}
+
+#[derive(Clone)]
+pub struct DocumentsApiImpl;
+
+#[taurpc::resolvers]
+impl DocumentsApi for DocumentsApiImpl {
+    async fn state_vector<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Vec<u8>> {
+        Ok(app_handle
+            .project_database()?
+            .read_transaction_async(move |txn| documents::state_vector_in(txn, id))
+            .await??)
+    }
+
+    async fn diff<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        state_vector: Vec<u8>,
+    ) -> crate::MetaResult<Vec<u8>> {
+        Ok(app_handle
+            .project_database()?
+            .read_transaction_async(move |txn| documents::diff_in(txn, id, &state_vector))
+            .await??)
+    }
+
+    async fn apply_update<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        update: Vec<u8>,
+    ) -> crate::MetaResult<()> {
+        let stored = update.clone();
+        app_handle
+            .project_database()?
+            .write_transaction_async(move |txn| -> crate::Result<()> {
+                let entities = txn.open_table(EntityTable::definition())?;
+                if RecordTableExt::<EntityTable>::get_record(&entities, &id)?.is_none() {
+                    return Err(crate::Error::not_found("entity", id));
+                }
+                drop(entities);
+                documents::store_update_in(txn, id, &stored)
+            })
+            .await??;
+        app_handle.note_document_edit(id);
+        app_handle.broadcast_document_update(id, update, None);
+        Ok(())
+    }
+}
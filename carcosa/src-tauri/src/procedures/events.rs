@@ -3,12 +3,74 @@ use specta::Type;
 use tauri::{Manager, Runtime};
 use uuid::Uuid;
 
-use crate::types::ActiveProject;
+use crate::{
+    types::{ActiveProject, HistoryItem, PeerIdentity, TableChange},
+    MetaError,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Type)]
 #[serde(tag = "event", rename_all = "snake_case")]
 pub enum AppEvent {
-    ActivatedProject { project: ActiveProject },
+    ActivatedProject {
+        project: ActiveProject,
+    },
+    MigrationProgress {
+        database: String,
+        version: u32,
+        description: String,
+        step: u32,
+        total: u32,
+    },
+    MigrationFailed {
+        database: String,
+        error: MetaError,
+    },
+    PeerConnected {
+        peer: PeerIdentity,
+    },
+    PeerDisconnected {
+        peer: PeerIdentity,
+    },
+    RemoteDocumentUpdate {
+        document: Uuid,
+        peer: PeerIdentity,
+    },
+    RejectedPeerUpdates {
+        peer: PeerIdentity,
+        documents: Vec<Uuid>,
+    },
+    UpdatesRejectedByPeer {
+        peer: PeerIdentity,
+        documents: Vec<Uuid>,
+    },
+    AssetTransferProgress {
+        hash: String,
+        peer: PeerIdentity,
+        received: u32,
+        total: u32,
+    },
+    AssetTransferred {
+        hash: String,
+        peer: PeerIdentity,
+    },
+    AssetTransferFailed {
+        hash: String,
+        error: MetaError,
+    },
+    HistoryApplied {
+        item: HistoryItem,
+        undone: bool,
+    },
+    TablesChanged {
+        subscription: Uuid,
+        database: String,
+        changes: Vec<TableChange>,
+    },
+    ChangesMissed {
+        subscription: Uuid,
+        database: String,
+    },
+    ProjectSettingsChanged,
 }
 
 #[taurpc::procedures(event_trigger = AppEventTrigger)]
@@ -0,0 +1,37 @@
+use tauri::{AppHandle, Runtime};
+
+use crate::{
+    extensions::HistoryExt,
+    types::{History, HistoryItem},
+};
+
+#[taurpc::procedures(path = "history")]
+pub trait HistoryApi {
+    async fn undo<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<Option<HistoryItem>>;
+    async fn redo<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<Option<HistoryItem>>;
+    async fn history<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<History>;
+}
+
+#[derive(Clone)]
+pub struct HistoryApiImpl;
+
+#[taurpc::resolvers]
+impl HistoryApi for HistoryApiImpl {
+    async fn undo<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Option<HistoryItem>> {
+        Ok(app_handle.undo().await?)
+    }
+
+    async fn redo<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Option<HistoryItem>> {
+        Ok(app_handle.redo()?)
+    }
+
+    async fn history<R: Runtime>(self, app_handle: AppHandle<R>) -> crate::MetaResult<History> {
+        Ok(app_handle.history())
+    }
+}
@@ -0,0 +1,44 @@
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{links, DatabasesExt},
+    types::Backlink,
+};
+
+#[taurpc::procedures(path = "links")]
+pub trait LinksApi {
+    async fn what_links_here<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Vec<Backlink>>;
+    async fn broken_links<R: Runtime>(app_handle: AppHandle<R>)
+        -> crate::MetaResult<Vec<Backlink>>;
+}
+
+#[derive(Clone)]
+pub struct LinksApiImpl;
+
+#[taurpc::resolvers]
+impl LinksApi for LinksApiImpl {
+    async fn what_links_here<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Vec<Backlink>> {
+        Ok(app_handle
+            .project_database()?
+            .read_transaction_async(move |txn| links::backlinks_in(txn, id))
+            .await??)
+    }
+
+    async fn broken_links<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<Backlink>> {
+        Ok(app_handle
+            .project_database()?
+            .read_transaction_async(links::broken_links_in)
+            .await??)
+    }
+}
@@ -0,0 +1,33 @@
+use tauri::{AppHandle, Runtime};
+
+use crate::{
+    extensions::{logging::logs_dir, LoggingExt},
+    types::{LogEntry, LogFilter},
+};
+
+#[taurpc::procedures(path = "logs")]
+pub trait LogsApi {
+    async fn recent<R: Runtime>(
+        app_handle: AppHandle<R>,
+        filter: LogFilter,
+    ) -> crate::MetaResult<Vec<LogEntry>>;
+    async fn directory<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<String>;
+}
+
+#[derive(Clone)]
+pub struct LogsApiImpl;
+
+#[taurpc::resolvers]
+impl LogsApi for LogsApiImpl {
+    async fn recent<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        filter: LogFilter,
+    ) -> crate::MetaResult<Vec<LogEntry>> {
+        Ok(app_handle.recent_logs(filter))
+    }
+
+    async fn directory<R: Runtime>(self, _app_handle: AppHandle<R>) -> crate::MetaResult<String> {
+        Ok(logs_dir()?.to_string_lossy().to_string())
+    }
+}
@@ -0,0 +1,33 @@
+use tauri::{AppHandle, Runtime};
+
+use crate::{extensions::MaintenanceExt, types::MaintenanceReport};
+
+#[taurpc::procedures(path = "maintenance")]
+pub trait MaintenanceApi {
+    async fn compact<R: Runtime>(
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<MaintenanceReport>>;
+    async fn check_integrity<R: Runtime>(
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<MaintenanceReport>>;
+}
+
+#[derive(Clone)]
+pub struct MaintenanceApiImpl;
+
+#[taurpc::resolvers]
+impl MaintenanceApi for MaintenanceApiImpl {
+    async fn compact<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<MaintenanceReport>> {
+        Ok(app_handle.compact_databases().await?)
+    }
+
+    async fn check_integrity<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<MaintenanceReport>> {
+        Ok(app_handle.check_databases().await?)
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{
+        maps::{self, MapTable},
+        DatabasesExt, MapsExt, RecordTableExt, TransfersExt,
+    },
+    types::{MapAnnotation, MapInfo},
+};
+
+#[taurpc::procedures(path = "maps")]
+pub trait MapsApi {
+    async fn import_map<R: Runtime>(
+        app_handle: AppHandle<R>,
+        name: String,
+        source: String,
+    ) -> crate::MetaResult<MapInfo>;
+    async fn get_map<R: Runtime>(app_handle: AppHandle<R>, id: Uuid) -> crate::MetaResult<MapInfo>;
+    async fn list_maps<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<Vec<MapInfo>>;
+    async fn update_map<R: Runtime>(
+        app_handle: AppHandle<R>,
+        map: MapInfo,
+    ) -> crate::MetaResult<MapInfo>;
+    async fn delete_map<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<MapInfo>;
+    async fn tile<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        level: u32,
+        x: u32,
+        y: u32,
+    ) -> crate::MetaResult<Vec<u8>>;
+    async fn annotations<R: Runtime>(
+        app_handle: AppHandle<R>,
+        map: Uuid,
+    ) -> crate::MetaResult<Vec<MapAnnotation>>;
+    async fn add_annotation<R: Runtime>(
+        app_handle: AppHandle<R>,
+        annotation: MapAnnotation,
+    ) -> crate::MetaResult<MapAnnotation>;
+    async fn update_annotation<R: Runtime>(
+        app_handle: AppHandle<R>,
+        annotation: MapAnnotation,
+    ) -> crate::MetaResult<MapAnnotation>;
+    async fn remove_annotation<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<MapAnnotation>;
+}
+
+#[derive(Clone)]
+pub struct MapsApiImpl;
+
+#[taurpc::resolvers]
+impl MapsApi for MapsApiImpl {
+    async fn import_map<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        name: String,
+        source: String,
+    ) -> crate::MetaResult<MapInfo> {
+        // Tiling a large image takes a while, so keep it off the async runtime.
+        Ok(tauri::async_runtime::spawn_blocking(move || {
+            app_handle.import_map(name, PathBuf::from(source))
+        })
+        .await??)
+    }
+
+    async fn get_map<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<MapInfo> {
+        Ok(app_handle.get_map(id)?)
+    }
+
+    async fn list_maps<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<MapInfo>> {
+        Ok(app_handle
+            .project_database()?
+            .read_async::<MapTable, _>(Vec::new, |table| {
+                Ok(RecordTableExt::<MapTable>::range_records(&table, ..)?
+                    .into_iter()
+                    .map(|(_, map)| map)
+                    .collect())
+            })
+            .await?)
+    }
+
+    async fn update_map<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        map: MapInfo,
+    ) -> crate::MetaResult<MapInfo> {
+        Ok(app_handle.update_map(map)?)
+    }
+
+    async fn delete_map<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<MapInfo> {
+        Ok(app_handle.delete_map(id)?)
+    }
+
+    async fn tile<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        level: u32,
+        x: u32,
+        y: u32,
+    ) -> crate::MetaResult<Vec<u8>> {
+        // Guests only receive the map's image when they first look at it.
+        let map = app_handle.get_map(id)?;
+        let _ = app_handle.fetch_asset(&map.image).await?;
+        Ok(
+            tauri::async_runtime::spawn_blocking(move || app_handle.map_tile(id, level, x, y))
+                .await??,
+        )
+    }
+
+    async fn annotations<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        map: Uuid,
+    ) -> crate::MetaResult<Vec<MapAnnotation>> {
+        let _ = app_handle.get_map(map)?;
+        Ok(app_handle
+            .project_database()?
+            .read_transaction_async(move |txn| maps::map_annotations_in(txn, map))
+            .await??)
+    }
+
+    async fn add_annotation<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        annotation: MapAnnotation,
+    ) -> crate::MetaResult<MapAnnotation> {
+        Ok(app_handle.add_annotation(annotation)?)
+    }
+
+    async fn update_annotation<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        annotation: MapAnnotation,
+    ) -> crate::MetaResult<MapAnnotation> {
+        Ok(app_handle.update_annotation(annotation)?)
+    }
+
+    async fn remove_annotation<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<MapAnnotation> {
+        Ok(app_handle.remove_annotation(id)?)
+    }
+}
@@ -1,15 +1,56 @@
 use tauri::{Runtime, ipc::Invoke};
 use taurpc::Router;
 
-use crate::procedures::{events::AppEventApi, project_management::ProjectManagementApi};
+use crate::procedures::{
+    assets::AssetsApi, calendars::CalendarsApi, changes::ChangesApi, documents::DocumentsApi,
+    events::AppEventApi, history::HistoryApi, links::LinksApi, logs::LogsApi,
+    maintenance::MaintenanceApi, maps::MapsApi, project_management::ProjectManagementApi,
+    recent::RecentProjectsApi, relations::RelationsApi, search::SearchApi,
+    settings::SettingsApi, sync::SyncApi, templates::TemplatesApi, timeline::TimelineApi,
+    world::WorldApi,
+};
 
 pub mod project_management;
 pub mod events;
+pub mod world;
+pub mod templates;
+pub mod documents;
+pub mod sync;
+pub mod recent;
+pub mod settings;
+pub mod logs;
+pub mod search;
+pub mod relations;
+pub mod links;
+pub mod calendars;
+pub mod timeline;
+pub mod maps;
+pub mod assets;
+pub mod history;
+pub mod maintenance;
+pub mod changes;
 pub use events::{AppEvent, AppEventExt};
 
 pub fn handler<R: Runtime>() -> impl Fn(Invoke<R>) -> bool {
     let router = Router::<R>::new()
         .merge(project_management::ProjectManagementApiImpl.into_handler())
+        .merge(world::WorldApiImpl.into_handler())
+        .merge(templates::TemplatesApiImpl.into_handler())
+        .merge(documents::DocumentsApiImpl.into_handler())
+        .merge(sync::SyncApiImpl.into_handler())
+        .merge(recent::RecentProjectsApiImpl.into_handler())
+        .merge(settings::SettingsApiImpl.into_handler())
+        .merge(logs::LogsApiImpl.into_handler())
+        .merge(search::SearchApiImpl.into_handler())
+        .merge(relations::RelationsApiImpl.into_handler())
+        .merge(links::LinksApiImpl.into_handler())
+        .merge(calendars::CalendarsApiImpl.into_handler())
+        .merge(timeline::TimelineApiImpl.into_handler())
+        .merge(maps::MapsApiImpl.into_handler())
+        .merge(assets::AssetsApiImpl.into_handler())
+        .merge(history::HistoryApiImpl.into_handler())
+        .merge(maintenance::MaintenanceApiImpl.into_handler())
+        .merge(changes::ChangesApiImpl.into_handler())
         .merge(events::AppEventApiImpl.into_handler());
     router.into_handler()
 }
\ No newline at end of file
@@ -3,8 +3,8 @@ use std::path::PathBuf;
 use tauri::{AppHandle, Runtime};
 
 use crate::{
-    extensions::{ApplicationExt, DatabasesExt},
-    types::{ActiveProject, ProjectSettings},
+    extensions::{ApplicationExt, InvitesExt, SyncExt},
+    types::{ActiveProject, CurrentProject, PeerIdentity, ProjectSettings},
     MetaError,
 };
 
@@ -19,9 +19,18 @@ pub trait ProjectManagementApi {
         app_handle: AppHandle<R>,
         path: String,
     ) -> crate::MetaResult<ProjectSettings>;
+    async fn open_remote_project<R: Runtime>(
+        app_handle: AppHandle<R>,
+        host: PeerIdentity,
+    ) -> crate::MetaResult<ProjectSettings>;
     async fn current_project<R: Runtime>(
         app_handle: AppHandle<R>,
-    ) -> crate::MetaResult<Option<(ActiveProject, ProjectSettings)>>;
+    ) -> crate::MetaResult<Option<CurrentProject>>;
+    async fn join_remote_project<R: Runtime>(
+        app_handle: AppHandle<R>,
+        ticket: String,
+        name: String,
+    ) -> crate::MetaResult<ProjectSettings>;
 }
 
 #[derive(Clone)]
@@ -48,12 +57,7 @@ impl ProjectManagementApi for ProjectManagementApiImpl {
         let project_path = std::path::PathBuf::from(path.clone());
         let target_path = if project_path.exists() {
             if project_path.is_dir() {
-                if project_path
-                    .file_name()
-                    .unwrap()
-                    .to_string_lossy()
-                    .to_string()
-                    == name.clone()
+                if project_path.file_name().unwrap().to_string_lossy() == name.as_str()
                     && project_path.read_dir()?.count() == 0
                 {
                     Ok(project_path.clone())
@@ -73,7 +77,6 @@ impl ProjectManagementApi for ProjectManagementApiImpl {
         }?;
 
         ProjectSettings::new(name.clone()).save(target_path.clone())?;
-        app_handle.clear_databases();
         app_handle
             .set_active_project(ActiveProject::Local {
                 path: target_path.clone(),
@@ -92,17 +95,36 @@ impl ProjectManagementApi for ProjectManagementApiImpl {
         })?;
         Ok(state.project_settings().unwrap())
     }
+
+    async fn open_remote_project<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        host: PeerIdentity,
+    ) -> crate::MetaResult<ProjectSettings> {
+        Ok(app_handle.open_remote_project(host).await?)
+    }
+
     async fn current_project<R: Runtime>(
         self,
         app_handle: AppHandle<R>,
-    ) -> crate::MetaResult<Option<(ActiveProject, ProjectSettings)>> {
+    ) -> crate::MetaResult<Option<CurrentProject>> {
         let state = app_handle.get_app_state();
         match state.active_project() {
             ActiveProject::None => Ok(None),
-            ActiveProject::Local { path } => Ok(Some((
-                ActiveProject::Local { path },
-                state.project_settings().unwrap(),
-            ))),
+            project => Ok(Some(CurrentProject {
+                project,
+                settings: state.project_settings().unwrap(),
+                connection: app_handle.connection_status(),
+            })),
         }
     }
+
+    async fn join_remote_project<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        ticket: String,
+        name: String,
+    ) -> crate::MetaResult<ProjectSettings> {
+        Ok(app_handle.join_remote_project(ticket, name).await?)
+    }
 }
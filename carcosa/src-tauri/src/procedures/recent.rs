@@ -0,0 +1,72 @@
+use tauri::{AppHandle, Runtime};
+
+use crate::{
+    extensions::{ApplicationExt, InvitesExt, RecentProjectsExt},
+    types::{ActiveProject, ProjectSettings, RecentProject},
+};
+
+#[taurpc::procedures(path = "recent")]
+pub trait RecentProjectsApi {
+    async fn list<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<Vec<RecentProject>>;
+    async fn pin<R: Runtime>(
+        app_handle: AppHandle<R>,
+        path: String,
+        pinned: bool,
+    ) -> crate::MetaResult<Vec<RecentProject>>;
+    async fn forget<R: Runtime>(
+        app_handle: AppHandle<R>,
+        path: String,
+    ) -> crate::MetaResult<Vec<RecentProject>>;
+    async fn reopen<R: Runtime>(
+        app_handle: AppHandle<R>,
+        path: String,
+    ) -> crate::MetaResult<ProjectSettings>;
+}
+
+#[derive(Clone)]
+pub struct RecentProjectsApiImpl;
+
+#[taurpc::resolvers]
+impl RecentProjectsApi for RecentProjectsApiImpl {
+    async fn list<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<RecentProject>> {
+        Ok(app_handle.recent_projects()?)
+    }
+
+    async fn pin<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        path: String,
+        pinned: bool,
+    ) -> crate::MetaResult<Vec<RecentProject>> {
+        Ok(app_handle.update_recent_projects(|recent| recent.with_pinned(path, pinned))?)
+    }
+
+    async fn forget<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        path: String,
+    ) -> crate::MetaResult<Vec<RecentProject>> {
+        Ok(app_handle.update_recent_projects(|recent| Ok(recent.without(path)))?)
+    }
+
+    async fn reopen<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        path: String,
+    ) -> crate::MetaResult<ProjectSettings> {
+        let recent = app_handle.recent_project(path)?;
+        if recent.missing {
+            return Err(crate::Error::not_found("project", recent.path.display()).into());
+        }
+        match recent.project() {
+            ActiveProject::Remote { host, .. } => Ok(app_handle.open_remote_project(host).await?),
+            project => {
+                let state = app_handle.set_active_project(project)?;
+                Ok(state.project_settings().unwrap())
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{relations, DatabasesExt, RelationsExt, WorldExt},
+    types::{Neighbourhood, Relation},
+};
+
+#[taurpc::procedures(path = "relations")]
+pub trait RelationsApi {
+    async fn add_relation<R: Runtime>(
+        app_handle: AppHandle<R>,
+        relation: Relation,
+    ) -> crate::MetaResult<Relation>;
+    async fn remove_relation<R: Runtime>(
+        app_handle: AppHandle<R>,
+        relation: Relation,
+    ) -> crate::MetaResult<bool>;
+    async fn relations_of<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Vec<Relation>>;
+    async fn neighbours<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        depth: u32,
+    ) -> crate::MetaResult<Neighbourhood>;
+    async fn shortest_path<R: Runtime>(
+        app_handle: AppHandle<R>,
+        from: Uuid,
+        to: Uuid,
+    ) -> crate::MetaResult<Option<Vec<Relation>>>;
+}
+
+#[derive(Clone)]
+pub struct RelationsApiImpl;
+
+#[taurpc::resolvers]
+impl RelationsApi for RelationsApiImpl {
+    async fn add_relation<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        relation: Relation,
+    ) -> crate::MetaResult<Relation> {
+        Ok(app_handle.add_relation(relation)?)
+    }
+
+    async fn remove_relation<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        relation: Relation,
+    ) -> crate::MetaResult<bool> {
+        Ok(app_handle.remove_relation(relation)?)
+    }
+
+    async fn relations_of<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Vec<Relation>> {
+        Ok(app_handle.relations_of(id)?)
+    }
+
+    async fn neighbours<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        depth: u32,
+    ) -> crate::MetaResult<Neighbourhood> {
+        let _ = app_handle.get_entity(id)?;
+        Ok(app_handle
+            .project_database()?
+            .read_transaction_async(move |txn| relations::neighbours_in(txn, id, depth))
+            .await??)
+    }
+
+    async fn shortest_path<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        from: Uuid,
+        to: Uuid,
+    ) -> crate::MetaResult<Option<Vec<Relation>>> {
+        let _ = app_handle.get_entity(from)?;
+        let _ = app_handle.get_entity(to)?;
+        Ok(app_handle
+            .project_database()?
+            .read_transaction_async(move |txn| relations::shortest_path_in(txn, from, to))
+            .await??)
+    }
+}
@@ -0,0 +1,40 @@
+use tauri::{AppHandle, Runtime};
+
+use crate::{
+    extensions::{search, DatabasesExt},
+    types::{SearchQuery, SearchResult},
+};
+
+#[taurpc::procedures(path = "search")]
+pub trait SearchApi {
+    async fn search<R: Runtime>(
+        app_handle: AppHandle<R>,
+        query: SearchQuery,
+    ) -> crate::MetaResult<Vec<SearchResult>>;
+    async fn rebuild_index<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<u32>;
+}
+
+#[derive(Clone)]
+pub struct SearchApiImpl;
+
+#[taurpc::resolvers]
+impl SearchApi for SearchApiImpl {
+    async fn search<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        query: SearchQuery,
+    ) -> crate::MetaResult<Vec<SearchResult>> {
+        Ok(app_handle
+            .project_database()?
+            .read_transaction_async(move |txn| search::search_in(txn, &query))
+            .await??)
+    }
+
+    async fn rebuild_index<R: Runtime>(self, app_handle: AppHandle<R>) -> crate::MetaResult<u32> {
+        // Rebuilding touches every entity, so keep the write off the async runtime.
+        Ok(app_handle
+            .project_database()?
+            .write_transaction_async(search::rebuild_in)
+            .await??)
+    }
+}
@@ -0,0 +1,39 @@
+use tauri::{AppHandle, Runtime};
+
+use crate::{extensions::ApplicationExt, types::AppSettings};
+
+#[taurpc::procedures(path = "settings")]
+pub trait SettingsApi {
+    async fn get_settings<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<AppSettings>;
+    async fn update_settings<R: Runtime>(
+        app_handle: AppHandle<R>,
+        settings: AppSettings,
+    ) -> crate::MetaResult<AppSettings>;
+}
+
+#[derive(Clone)]
+pub struct SettingsApiImpl;
+
+#[taurpc::resolvers]
+impl SettingsApi for SettingsApiImpl {
+    async fn get_settings<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<AppSettings> {
+        Ok(app_handle.get_app_state().settings())
+    }
+
+    async fn update_settings<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        settings: AppSettings,
+    ) -> crate::MetaResult<AppSettings> {
+        // The last project is tracked by the backend as projects are opened and closed.
+        Ok(app_handle.update_app_settings(|current| {
+            Ok(AppSettings {
+                last_project: current.last_project,
+                ..settings
+            })
+        })?)
+    }
+}
@@ -0,0 +1,59 @@
+use tauri::{AppHandle, Runtime};
+
+use crate::{
+    extensions::{sync::SyncStatus, InvitesExt, SyncExt},
+    types::PeerIdentity,
+};
+
+#[taurpc::procedures(path = "sync")]
+pub trait SyncApi {
+    async fn start<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<SyncStatus>;
+    async fn stop<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<SyncStatus>;
+    async fn status<R: Runtime>(app_handle: AppHandle<R>) -> crate::MetaResult<SyncStatus>;
+    async fn connect<R: Runtime>(
+        app_handle: AppHandle<R>,
+        peer: PeerIdentity,
+    ) -> crate::MetaResult<SyncStatus>;
+    async fn create_invite<R: Runtime>(
+        app_handle: AppHandle<R>,
+        can_edit: bool,
+        valid_hours: u32,
+    ) -> crate::MetaResult<String>;
+}
+
+#[derive(Clone)]
+pub struct SyncApiImpl;
+
+#[taurpc::resolvers]
+impl SyncApi for SyncApiImpl {
+    async fn start<R: Runtime>(self, app_handle: AppHandle<R>) -> crate::MetaResult<SyncStatus> {
+        Ok(app_handle.start_sync().await?)
+    }
+
+    async fn stop<R: Runtime>(self, app_handle: AppHandle<R>) -> crate::MetaResult<SyncStatus> {
+        app_handle.stop_sync().await;
+        Ok(app_handle.sync_status())
+    }
+
+    async fn status<R: Runtime>(self, app_handle: AppHandle<R>) -> crate::MetaResult<SyncStatus> {
+        Ok(app_handle.sync_status())
+    }
+
+    async fn connect<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        peer: PeerIdentity,
+    ) -> crate::MetaResult<SyncStatus> {
+        app_handle.connect_peer(peer).await?;
+        Ok(app_handle.sync_status())
+    }
+
+    async fn create_invite<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        can_edit: bool,
+        valid_hours: u32,
+    ) -> crate::MetaResult<String> {
+        Ok(app_handle.create_invite(can_edit, valid_hours)?)
+    }
+}
@@ -0,0 +1,77 @@
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::TemplatesExt,
+    types::{EntityTemplate, FieldChange},
+};
+
+#[taurpc::procedures(path = "templates")]
+pub trait TemplatesApi {
+    async fn list_templates<R: Runtime>(
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<EntityTemplate>>;
+    async fn get_template<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<EntityTemplate>;
+    async fn create_template<R: Runtime>(
+        app_handle: AppHandle<R>,
+        template: EntityTemplate,
+    ) -> crate::MetaResult<EntityTemplate>;
+    async fn delete_template<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<EntityTemplate>;
+    async fn evolve_template<R: Runtime>(
+        app_handle: AppHandle<R>,
+        template: EntityTemplate,
+        changes: Vec<FieldChange>,
+    ) -> crate::MetaResult<EntityTemplate>;
+}
+
+#[derive(Clone)]
+pub struct TemplatesApiImpl;
+
+#[taurpc::resolvers]
+impl TemplatesApi for TemplatesApiImpl {
+    async fn list_templates<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+    ) -> crate::MetaResult<Vec<EntityTemplate>> {
+        Ok(app_handle.list_templates()?)
+    }
+
+    async fn get_template<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<EntityTemplate> {
+        Ok(app_handle.get_template(id)?)
+    }
+
+    async fn create_template<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        template: EntityTemplate,
+    ) -> crate::MetaResult<EntityTemplate> {
+        Ok(app_handle.create_template(template)?)
+    }
+
+    async fn delete_template<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<EntityTemplate> {
+        Ok(app_handle.delete_template(id)?)
+    }
+
+    async fn evolve_template<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        template: EntityTemplate,
+        changes: Vec<FieldChange>,
+    ) -> crate::MetaResult<EntityTemplate> {
+        Ok(app_handle.evolve_template(template, changes)?)
+    }
+}
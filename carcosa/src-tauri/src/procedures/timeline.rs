@@ -0,0 +1,86 @@
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{timeline, CalendarsExt, DatabasesExt, TimelineExt},
+    types::{TimelineEntry, TimelineEvent, TimelineQuery},
+};
+
+#[taurpc::procedures(path = "timeline")]
+pub trait TimelineApi {
+    async fn get_event<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<TimelineEvent>;
+    async fn create_event<R: Runtime>(
+        app_handle: AppHandle<R>,
+        event: TimelineEvent,
+    ) -> crate::MetaResult<TimelineEvent>;
+    async fn update_event<R: Runtime>(
+        app_handle: AppHandle<R>,
+        event: TimelineEvent,
+    ) -> crate::MetaResult<TimelineEvent>;
+    async fn delete_event<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<TimelineEvent>;
+    async fn query<R: Runtime>(
+        app_handle: AppHandle<R>,
+        query: TimelineQuery,
+    ) -> crate::MetaResult<Vec<TimelineEntry>>;
+}
+
+#[derive(Clone)]
+pub struct TimelineApiImpl;
+
+#[taurpc::resolvers]
+impl TimelineApi for TimelineApiImpl {
+    async fn get_event<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<TimelineEvent> {
+        Ok(app_handle.get_event(id)?)
+    }
+
+    async fn create_event<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        event: TimelineEvent,
+    ) -> crate::MetaResult<TimelineEvent> {
+        Ok(app_handle.create_event(event)?)
+    }
+
+    async fn update_event<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        event: TimelineEvent,
+    ) -> crate::MetaResult<TimelineEvent> {
+        Ok(app_handle.update_event(event)?)
+    }
+
+    async fn delete_event<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<TimelineEvent> {
+        Ok(app_handle.delete_event(id)?)
+    }
+
+    async fn query<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        query: TimelineQuery,
+    ) -> crate::MetaResult<Vec<TimelineEntry>> {
+        let calendar = query
+            .calendar
+            .map(|id| app_handle.get_calendar(id))
+            .transpose()?;
+        let until = query.end;
+        let events = app_handle
+            .project_database()?
+            .read_transaction_async(move |txn| timeline::starting_until(txn, until))
+            .await??;
+        Ok(timeline::entries(events, &query, calendar.as_ref())?)
+    }
+}
@@ -0,0 +1,98 @@
+use tauri::{AppHandle, Runtime};
+use uuid::Uuid;
+
+use crate::{
+    extensions::{world::EntityTable, DatabasesExt, RecordTableExt, WorldExt},
+    types::{Entity, EntityData, EntityKind, EntityUpdate},
+};
+
+#[taurpc::procedures(path = "world")]
+pub trait WorldApi {
+    async fn create_entity<R: Runtime>(
+        app_handle: AppHandle<R>,
+        title: String,
+        data: EntityData,
+    ) -> crate::MetaResult<Entity>;
+    async fn get_entity<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Entity>;
+    async fn update_entity<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        update: EntityUpdate,
+    ) -> crate::MetaResult<Entity>;
+    async fn delete_entity<R: Runtime>(
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Entity>;
+    async fn list_entities<R: Runtime>(
+        app_handle: AppHandle<R>,
+        kind: Option<EntityKind>,
+    ) -> crate::MetaResult<Vec<Entity>>;
+}
+
+#[derive(Clone)]
+pub struct WorldApiImpl;
+
+#[taurpc::resolvers]
+impl WorldApi for WorldApiImpl {
+    async fn create_entity<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        title: String,
+        data: EntityData,
+    ) -> crate::MetaResult<Entity> {
+        // Entity writes hold the database's write lock across their undo savepoint, which has to
+        // stay on one thread, so the whole operation runs off the async runtime.
+        Ok(
+            tauri::async_runtime::spawn_blocking(move || app_handle.create_entity(title, data))
+                .await??,
+        )
+    }
+
+    async fn get_entity<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Entity> {
+        Ok(app_handle.get_entity(id)?)
+    }
+
+    async fn update_entity<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+        update: EntityUpdate,
+    ) -> crate::MetaResult<Entity> {
+        Ok(
+            tauri::async_runtime::spawn_blocking(move || app_handle.update_entity(id, update))
+                .await??,
+        )
+    }
+
+    async fn delete_entity<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        id: Uuid,
+    ) -> crate::MetaResult<Entity> {
+        Ok(tauri::async_runtime::spawn_blocking(move || app_handle.delete_entity(id)).await??)
+    }
+
+    async fn list_entities<R: Runtime>(
+        self,
+        app_handle: AppHandle<R>,
+        kind: Option<EntityKind>,
+    ) -> crate::MetaResult<Vec<Entity>> {
+        Ok(app_handle
+            .project_database()?
+            .read_async::<EntityTable, _>(Vec::new, move |table| {
+                Ok(RecordTableExt::<EntityTable>::range_records(&table, ..)?
+                    .into_iter()
+                    .map(|(_, entity)| entity)
+                    .filter(|entity| kind.is_none_or(|kind| entity.kind() == kind))
+                    .collect())
+            })
+            .await?)
+    }
+}
@@ -5,7 +5,7 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
-use crate::types::ProjectSettings;
+use crate::types::{AppSettings, PeerIdentity, ProjectSettings};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Type, Default)]
 #[serde(tag = "kind", rename_all = "snake_case")]
@@ -15,22 +15,49 @@ pub enum ActiveProject {
     Local {
         path: PathBuf,
     },
+    /// A project hosted by another peer, mirrored into a local cache directory.
+    Remote {
+        host: PeerIdentity,
+        path: PathBuf,
+    },
+}
+
+impl ActiveProject {
+    /// The directory holding this project's files, if a project is open.
+    pub fn path(&self) -> Option<PathBuf> {
+        match self {
+            Self::None => None,
+            Self::Local { path } | Self::Remote { path, .. } => Some(path.clone()),
+        }
+    }
+}
+
+/// Whether the open project is currently reachable by, or connected to, other peers.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Type)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ConnectionStatus {
+    /// Sync is not running, or the host of a remote project can't be reached. Edits are kept
+    /// locally and reconciled once a connection is made.
+    Offline,
+    /// A local project is being shared with `peers` connected collaborators.
+    Hosting { peers: u32 },
+    /// Connected to the host of a remote project.
+    Connected,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+pub struct CurrentProject {
+    pub project: ActiveProject,
+    pub settings: ProjectSettings,
+    pub connection: ConnectionStatus,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Type, CloneGetters, WithSetters)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type, CloneGetters, WithSetters)]
 #[getset(get_clone = "pub", set = "pub", set_with = "pub")]
 pub struct ApplicationState {
     active_project: ActiveProject,
     project_settings: Option<ProjectSettings>,
-}
-
-impl Default for ApplicationState {
-    fn default() -> Self {
-        Self {
-            active_project: ActiveProject::default(),
-            project_settings: None,
-        }
-    }
+    settings: AppSettings,
 }
 
 pub type ApplicationStateWrapper = Arc<RwLock<ApplicationState>>;
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+use crate::types::PeerIdentity;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+pub struct AssetDimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A file imported into a project, identified by the BLAKE3 hash of its contents.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct AssetInfo {
+    /// Hex-encoded BLAKE3 hash of the file's contents.
+    pub hash: String,
+
+    /// File name the asset was first imported under.
+    pub name: String,
+    pub mime: String,
+    pub size: u32,
+
+    /// Set for images in a format the backend can read.
+    #[serde(default)]
+    pub dimensions: Option<AssetDimensions>,
+    pub uploaded_by: PeerIdentity,
+    pub uploaded: DateTime<Utc>,
+}
+
+impl AssetInfo {
+    /// Checks that `hash` looks like one of ours, so it can safely be used in a path.
+    pub fn validate_hash(hash: &str) -> crate::Result<()> {
+        if hash.len() == 64 && hash.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+            Ok(())
+        } else {
+            Err(crate::Error::validation("hash", "Not a valid asset hash"))
+        }
+    }
+}
+
+/// An asset along with every entity referencing it.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct AssetUsage {
+    pub asset: AssetInfo,
+    pub entities: Vec<Uuid>,
+}
@@ -0,0 +1,477 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+/// The longest leap cycle a calendar may have, in years. Date conversion walks a full cycle, so
+/// this bounds its cost.
+pub const MAX_LEAP_CYCLE: i64 = 10_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct CalendarMonth {
+    pub name: String,
+    pub days: u32,
+
+    /// Days added to this month in leap years.
+    #[serde(default)]
+    pub leap_days: u32,
+}
+
+/// Years divisible by `every` are leap years if `leap` is set, or common years otherwise. Later
+/// rules override earlier ones, so the Gregorian rules are `4: leap, 100: common, 400: leap`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct LeapRule {
+    pub every: u32,
+    pub leap: bool,
+}
+
+/// A named span of years, lasting until the next era starts.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct Era {
+    pub name: String,
+
+    #[serde(default)]
+    pub abbreviation: Option<String>,
+
+    /// The calendar year this era's first year falls on.
+    pub start_year: i32,
+}
+
+/// A project-defined calendar. Every calendar maps its dates onto the same absolute day count,
+/// which is what events are stored as.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct Calendar {
+    pub id: Uuid,
+    pub name: String,
+    pub months: Vec<CalendarMonth>,
+
+    #[serde(default)]
+    pub weekdays: Vec<String>,
+
+    #[serde(default)]
+    pub leap_rules: Vec<LeapRule>,
+
+    /// Eras in order of their start year. Years before the first era have no era.
+    #[serde(default)]
+    pub eras: Vec<Era>,
+
+    /// The absolute day on which year 0 of this calendar starts.
+    #[serde(default)]
+    pub epoch: i32,
+
+    /// The weekday of the epoch, as an index into `weekdays`.
+    #[serde(default)]
+    pub epoch_weekday: u32,
+}
+
+/// A day in a specific calendar. `month` and `day` count from 1.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Type)]
+pub struct CalendarDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+/// An absolute day described in a calendar, ready for display.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct DescribedDate {
+    pub days: i32,
+    pub date: CalendarDate,
+    pub month: String,
+    pub weekday: Option<String>,
+    pub era: Option<String>,
+
+    /// The year counted from the start of `era`, starting at 1.
+    pub year_of_era: i32,
+    pub text: String,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn out_of_range(input: &str) -> crate::Error {
+    crate::Error::validation(input, "Date is out of range")
+}
+
+impl Calendar {
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(crate::Error::validation(
+                "name",
+                "Calendar name cannot be empty",
+            ));
+        }
+        if self.months.is_empty() {
+            return Err(crate::Error::validation(
+                "months",
+                "A calendar needs at least one month",
+            ));
+        }
+        for (index, month) in self.months.iter().enumerate() {
+            if month.name.trim().is_empty() {
+                return Err(crate::Error::validation(
+                    format!("months.{index}.name"),
+                    "Month name cannot be empty",
+                ));
+            }
+            if month.days == 0 {
+                return Err(crate::Error::validation(
+                    format!("months.{index}.days"),
+                    "Months need at least one day",
+                ));
+            }
+        }
+        if let Some(index) = self.weekdays.iter().position(|day| day.trim().is_empty()) {
+            return Err(crate::Error::validation(
+                format!("weekdays.{index}"),
+                "Weekday name cannot be empty",
+            ));
+        }
+        if !self.weekdays.is_empty() && self.epoch_weekday as usize >= self.weekdays.len() {
+            return Err(crate::Error::validation(
+                "epoch_weekday",
+                "Epoch weekday must be one of the calendar's weekdays",
+            ));
+        }
+        if let Some(index) = self.leap_rules.iter().position(|rule| rule.every == 0) {
+            return Err(crate::Error::validation(
+                format!("leap_rules.{index}.every"),
+                "Leap rules must repeat at least every year",
+            ));
+        }
+        if self.leap_cycle().is_none() {
+            return Err(crate::Error::validation(
+                "leap_rules",
+                format!("Leap rules cannot take more than {MAX_LEAP_CYCLE} years to repeat"),
+            ));
+        }
+        for (index, era) in self.eras.iter().enumerate() {
+            if era.name.trim().is_empty() {
+                return Err(crate::Error::validation(
+                    format!("eras.{index}.name"),
+                    "Era name cannot be empty",
+                ));
+            }
+            if index > 0 && era.start_year <= self.eras[index - 1].start_year {
+                return Err(crate::Error::validation(
+                    format!("eras.{index}.start_year"),
+                    "Eras must be listed in order of their start year",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of years after which the leap rules repeat, or `None` if that's longer than
+    /// [`MAX_LEAP_CYCLE`].
+    fn leap_cycle(&self) -> Option<i64> {
+        self.leap_rules.iter().try_fold(1, |cycle, rule| {
+            let every = i64::from(rule.every.max(1));
+            let cycle = cycle / gcd(cycle, every) * every;
+            (cycle <= MAX_LEAP_CYCLE).then_some(cycle)
+        })
+    }
+
+    pub fn is_leap_year(&self, year: i64) -> bool {
+        self.leap_rules.iter().fold(false, |leap, rule| {
+            if year.rem_euclid(i64::from(rule.every.max(1))) == 0 {
+                rule.leap
+            } else {
+                leap
+            }
+        })
+    }
+
+    fn month_length(&self, year: i64, month: &CalendarMonth) -> i64 {
+        let leap_days = if self.is_leap_year(year) {
+            month.leap_days
+        } else {
+            0
+        };
+        i64::from(month.days) + i64::from(leap_days)
+    }
+
+    pub fn year_length(&self, year: i64) -> i64 {
+        self.months
+            .iter()
+            .map(|month| self.month_length(year, month))
+            .sum()
+    }
+
+    /// Years per leap cycle, and days per leap cycle.
+    fn cycle(&self) -> (i64, i64) {
+        let years = self.leap_cycle().unwrap_or(1);
+        (years, (0..years).map(|year| self.year_length(year)).sum())
+    }
+
+    /// Days from the start of year 0 to the start of `year`, negative for earlier years.
+    fn days_before_year(&self, year: i64) -> i64 {
+        let (cycle_years, cycle_days) = self.cycle();
+        let rest = year.rem_euclid(cycle_years);
+        year.div_euclid(cycle_years) * cycle_days
+            + (0..rest).map(|year| self.year_length(year)).sum::<i64>()
+    }
+
+    /// Converts a date in this calendar to an absolute day.
+    pub fn to_days(&self, date: CalendarDate) -> crate::Result<i32> {
+        let year = i64::from(date.year);
+        let month_index = (date.month as usize)
+            .checked_sub(1)
+            .filter(|index| *index < self.months.len())
+            .ok_or_else(|| {
+                crate::Error::validation(
+                    "date.month",
+                    format!("Month must be between 1 and {}", self.months.len()),
+                )
+            })?;
+        let month_length = self.month_length(year, &self.months[month_index]);
+        if date.day == 0 || i64::from(date.day) > month_length {
+            return Err(crate::Error::validation(
+                "date.day",
+                format!("Day must be between 1 and {month_length}"),
+            ));
+        }
+        let days = i64::from(self.epoch)
+            + self.days_before_year(year)
+            + self.months[..month_index]
+                .iter()
+                .map(|month| self.month_length(year, month))
+                .sum::<i64>()
+            + i64::from(date.day)
+            - 1;
+        i32::try_from(days).map_err(|_| out_of_range("date"))
+    }
+
+    /// Converts an absolute day to a date in this calendar.
+    pub fn from_days(&self, days: i32) -> crate::Result<CalendarDate> {
+        let (cycle_years, cycle_days) = self.cycle();
+        let since_epoch = i64::from(days) - i64::from(self.epoch);
+        let mut year = since_epoch.div_euclid(cycle_days) * cycle_years;
+        let mut remaining = since_epoch.rem_euclid(cycle_days);
+        loop {
+            let length = self.year_length(year);
+            if remaining < length {
+                break;
+            }
+            remaining -= length;
+            year += 1;
+        }
+
+        let mut month = 0;
+        for (index, candidate) in self.months.iter().enumerate() {
+            let length = self.month_length(year, candidate);
+            if remaining < length {
+                month = index;
+                break;
+            }
+            remaining -= length;
+        }
+        Ok(CalendarDate {
+            year: i32::try_from(year).map_err(|_| out_of_range("days"))?,
+            month: month as u32 + 1,
+            day: remaining as u32 + 1,
+        })
+    }
+
+    pub fn weekday(&self, days: i32) -> Option<String> {
+        if self.weekdays.is_empty() {
+            return None;
+        }
+        let index = (i64::from(days) - i64::from(self.epoch) + i64::from(self.epoch_weekday))
+            .rem_euclid(self.weekdays.len() as i64);
+        self.weekdays.get(index as usize).cloned()
+    }
+
+    /// The era `year` falls in, if any.
+    pub fn era(&self, year: i32) -> Option<&Era> {
+        self.eras.iter().rev().find(|era| era.start_year <= year)
+    }
+
+    pub fn describe(&self, days: i32) -> crate::Result<DescribedDate> {
+        let date = self.from_days(days)?;
+        let month = self.months[date.month as usize - 1].name.clone();
+        let weekday = self.weekday(days);
+        let era = self.era(date.year);
+        let year_of_era = match era {
+            Some(era) => date.year - era.start_year + 1,
+            None => date.year,
+        };
+
+        let mut text = match &weekday {
+            Some(weekday) => format!("{weekday}, "),
+            None => String::new(),
+        };
+        text.push_str(&format!("{} {month} {year_of_era}", date.day));
+        if let Some(era) = era {
+            text.push(' ');
+            text.push_str(era.abbreviation.as_ref().unwrap_or(&era.name));
+        }
+
+        Ok(DescribedDate {
+            days,
+            date,
+            month,
+            weekday,
+            era: era.map(|era| era.name.clone()),
+            year_of_era,
+            text,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn month(name: &str, days: u32, leap_days: u32) -> CalendarMonth {
+        CalendarMonth {
+            name: name.to_string(),
+            days,
+            leap_days,
+        }
+    }
+
+    fn gregorian() -> Calendar {
+        let lengths = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+        Calendar {
+            id: Uuid::nil(),
+            name: String::from("Gregorian"),
+            months: lengths
+                .iter()
+                .enumerate()
+                .map(|(index, days)| month(&format!("M{}", index + 1), *days, (index == 1) as u32))
+                .collect(),
+            weekdays: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+                .map(String::from)
+                .to_vec(),
+            leap_rules: vec![
+                LeapRule {
+                    every: 4,
+                    leap: true,
+                },
+                LeapRule {
+                    every: 100,
+                    leap: false,
+                },
+                LeapRule {
+                    every: 400,
+                    leap: true,
+                },
+            ],
+            eras: Vec::new(),
+            epoch: 0,
+            epoch_weekday: 5,
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> CalendarDate {
+        CalendarDate { year, month, day }
+    }
+
+    #[test]
+    fn applies_later_leap_rules_over_earlier_ones() {
+        let calendar = gregorian();
+        assert!(calendar.is_leap_year(2024));
+        assert!(!calendar.is_leap_year(2023));
+        assert!(!calendar.is_leap_year(1900));
+        assert!(calendar.is_leap_year(2000));
+        assert!(calendar.is_leap_year(-4));
+        assert_eq!(calendar.year_length(1900), 365);
+        assert_eq!(calendar.year_length(2000), 366);
+    }
+
+    #[test]
+    fn round_trips_dates_across_leap_cycles() {
+        let calendar = gregorian();
+        for days in (-400 * 366..400 * 366).step_by(397) {
+            let date = calendar.from_days(days).unwrap();
+            assert_eq!(calendar.to_days(date).unwrap(), days, "{date:?}");
+        }
+        for year in [-401, -1, 0, 1, 1900, 2000, 2024] {
+            for (month, day) in [(1, 1), (2, 28), (12, 31)] {
+                let date = date(year, month, day);
+                assert_eq!(
+                    calendar.from_days(calendar.to_days(date).unwrap()).unwrap(),
+                    date
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn counts_days_from_the_epoch() {
+        let calendar = Calendar {
+            epoch: 10,
+            ..gregorian()
+        };
+        assert_eq!(calendar.to_days(date(0, 1, 1)).unwrap(), 10);
+        assert_eq!(calendar.to_days(date(-1, 12, 31)).unwrap(), 9);
+        // Year 0 is a leap year, so it has a 29th of February.
+        assert_eq!(calendar.to_days(date(0, 3, 1)).unwrap(), 10 + 31 + 29);
+        assert_eq!(calendar.to_days(date(2000, 1, 1)).unwrap(), 10 + 730_485);
+    }
+
+    #[test]
+    fn rejects_days_outside_the_month() {
+        let calendar = gregorian();
+        assert!(calendar.to_days(date(2024, 2, 29)).is_ok());
+        assert!(calendar.to_days(date(2023, 2, 29)).is_err());
+        assert!(calendar.to_days(date(1900, 2, 29)).is_err());
+        assert!(calendar.to_days(date(2024, 13, 1)).is_err());
+        assert!(calendar.to_days(date(2024, 1, 0)).is_err());
+    }
+
+    #[test]
+    fn describes_weekdays_and_eras() {
+        let calendar = Calendar {
+            eras: vec![Era {
+                name: String::from("Common Era"),
+                abbreviation: Some(String::from("CE")),
+                start_year: 1,
+            }],
+            ..gregorian()
+        };
+        let days = calendar.to_days(date(2024, 1, 1)).unwrap();
+        let described = calendar.describe(days).unwrap();
+        assert_eq!(described.weekday.as_deref(), Some("Mon"));
+        assert_eq!(described.year_of_era, 2024);
+        assert_eq!(described.text, "Mon, 1 M1 2024 CE");
+        assert_eq!(
+            calendar.describe(days - 1).unwrap().weekday.as_deref(),
+            Some("Sun")
+        );
+        assert_eq!(calendar.describe(-1).unwrap().era, None);
+    }
+
+    #[test]
+    fn adds_leap_days_to_huge_months_without_overflowing() {
+        let calendar = Calendar {
+            months: vec![month("Long", u32::MAX, u32::MAX)],
+            ..gregorian()
+        };
+        assert_eq!(calendar.year_length(2024), 2 * i64::from(u32::MAX));
+    }
+
+    #[test]
+    fn rejects_leap_cycles_that_are_too_long() {
+        let calendar = Calendar {
+            leap_rules: vec![
+                LeapRule {
+                    every: 9_973,
+                    leap: true,
+                },
+                LeapRule {
+                    every: 2,
+                    leap: true,
+                },
+            ],
+            ..gregorian()
+        };
+        assert!(calendar.validate().is_err());
+        assert!(gregorian().validate().is_ok());
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+/// Records changed in one table by a committed write.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Type)]
+pub struct TableChange {
+    pub table: String,
+    pub multimap: bool,
+
+    /// Keys of the records that were inserted, replaced or removed. Empty if only the table is
+    /// known to have changed, ie. after it was written to as a raw table, cleared or rebuilt, or
+    /// after a savepoint was restored.
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+/// Which changes a subscriber is notified of.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct ChangeSubscription {
+    pub id: Uuid,
+
+    /// Only changes to this database, or to every open database if unset.
+    #[serde(default)]
+    pub database: Option<String>,
+
+    /// Only changes to these tables, or to every table if empty.
+    #[serde(default)]
+    pub tables: Vec<String>,
+}
+
+impl ChangeSubscription {
+    /// The part of `changes` to `database` this subscription is interested in.
+    pub fn matching(&self, database: &str, changes: &[TableChange]) -> Vec<TableChange> {
+        if self
+            .database
+            .as_deref()
+            .is_some_and(|name| name != database)
+        {
+            return Vec::new();
+        }
+        changes
+            .iter()
+            .filter(|change| self.tables.is_empty() || self.tables.contains(&change.table))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+/// How many operations can be undone unless the app settings say otherwise.
+pub const DEFAULT_HISTORY_DEPTH: u32 = 50;
+
+/// Parts of the UI an undone or redone operation may have changed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryView {
+    Entities,
+    Relations,
+    Timeline,
+    Documents,
+    Search,
+    Maps,
+    Assets,
+}
+
+/// A user-level operation that can be undone or redone.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct HistoryItem {
+    pub id: Uuid,
+    pub label: String,
+    pub views: Vec<HistoryView>,
+    pub at: DateTime<Utc>,
+}
+
+/// The undo and redo stacks, most recent operation last.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct History {
+    pub undo: Vec<HistoryItem>,
+    pub redo: Vec<HistoryItem>,
+    pub depth: u32,
+}
@@ -0,0 +1,233 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+use crate::types::Entity;
+
+/// A `[[target]]` or `[[target|label]]` reference in document text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WikiLink {
+    pub target: String,
+    pub label: Option<String>,
+
+    /// Byte range of the whole link, brackets included.
+    pub range: Range<usize>,
+}
+
+impl WikiLink {
+    /// Finds every wiki link in `text`. Unterminated or empty links are skipped.
+    pub fn parse(text: &str) -> Vec<Self> {
+        let mut links = Vec::new();
+        let mut offset = 0;
+        while let Some(start) = text[offset..].find("[[").map(|i| offset + i) {
+            let Some(end) = text[start + 2..].find("]]").map(|i| start + 2 + i) else {
+                break;
+            };
+            let inner = &text[start + 2..end];
+            let (target, label) = match inner.split_once('|') {
+                Some((target, label)) => (target, Some(label.to_string())),
+                None => (inner, None),
+            };
+            if !target.trim().is_empty() && !target.contains("[[") {
+                links.push(Self {
+                    target: target.trim().to_string(),
+                    label,
+                    range: start..end + 2,
+                });
+                offset = end + 2;
+            } else {
+                offset = start + 2;
+            }
+        }
+        links
+    }
+
+    /// The key links are indexed under, so that differently cased references match.
+    pub fn key(target: &str) -> String {
+        target.trim().to_lowercase()
+    }
+
+    /// This link pointed at `target` instead, keeping its label.
+    pub fn retargeted(&self, target: &str) -> String {
+        match &self.label {
+            Some(label) => format!("[[{target}|{label}]]"),
+            None => format!("[[{target}]]"),
+        }
+    }
+
+    /// This link pointed at `target` instead, still showing the text it showed before.
+    pub fn retargeted_as_shown(&self, target: &str) -> String {
+        let label = self.label.as_deref().unwrap_or(&self.target);
+        format!("[[{target}|{label}]]")
+    }
+}
+
+/// A name an entity no longer goes by, and the name links to it should use instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Retarget {
+    pub from: String,
+    pub to: String,
+
+    /// Whether links without a label should keep showing `from`, ie. when an alias was removed
+    /// and its links now point at the title.
+    pub keep_text: bool,
+}
+
+impl Retarget {
+    /// How links to `before` have to change for them to keep pointing at `after`. An alias
+    /// replaced by a new one in the same position counts as renamed, while any other alias that
+    /// went away is pointed at the title.
+    pub fn between(before: &Entity, after: &Entity) -> Vec<Self> {
+        let remaining = after
+            .names()
+            .iter()
+            .map(|name| WikiLink::key(name))
+            .collect::<Vec<_>>();
+        let previous = before
+            .names()
+            .iter()
+            .map(|name| WikiLink::key(name))
+            .collect::<Vec<_>>();
+        let mut retargets = Vec::new();
+        if !remaining.contains(&WikiLink::key(&before.title())) {
+            retargets.push(Self {
+                from: before.title(),
+                to: after.title(),
+                keep_text: false,
+            });
+        }
+        let aliases = after.aliases();
+        for (index, alias) in before.aliases().into_iter().enumerate() {
+            if remaining.contains(&WikiLink::key(&alias)) {
+                continue;
+            }
+            let retarget = match aliases.get(index) {
+                Some(renamed) if !previous.contains(&WikiLink::key(renamed)) => Self {
+                    from: alias,
+                    to: renamed.clone(),
+                    keep_text: false,
+                },
+                _ => Self {
+                    from: alias,
+                    to: after.title(),
+                    keep_text: true,
+                },
+            };
+            retargets.push(retarget);
+        }
+        retargets
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Type)]
+pub struct Backlink {
+    /// The entity whose body contains the link.
+    pub source: Uuid,
+
+    /// The link target as written, ie. a name or an entity ID.
+    pub target: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EntityData;
+
+    fn entity(title: &str, aliases: &[&str]) -> Entity {
+        Entity::new(title, EntityData::Article {})
+            .with_aliases(aliases.iter().map(|alias| alias.to_string()).collect())
+    }
+
+    #[test]
+    fn parses_targets_labels_and_ranges() {
+        let text = "See [[Carcosa]] and [[ Hastur | the King ]].";
+        let links = WikiLink::parse(text);
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].target, "Carcosa");
+        assert_eq!(links[0].label, None);
+        assert_eq!(&text[links[0].range.clone()], "[[Carcosa]]");
+        assert_eq!(links[1].target, "Hastur");
+        assert_eq!(links[1].label.as_deref(), Some(" the King "));
+        assert_eq!(&text[links[1].range.clone()], "[[ Hastur | the King ]]");
+    }
+
+    #[test]
+    fn skips_empty_and_unterminated_links() {
+        assert!(WikiLink::parse("[[]] [[ |label]] [[open").is_empty());
+        let links = WikiLink::parse("[[ [[Inner]]");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].target, "Inner");
+    }
+
+    #[test]
+    fn ranges_are_byte_offsets() {
+        let text = "Ünïcödé [[Yhtill]]";
+        let links = WikiLink::parse(text);
+        assert_eq!(&text[links[0].range.clone()], "[[Yhtill]]");
+    }
+
+    #[test]
+    fn keys_ignore_case_and_surrounding_space() {
+        assert_eq!(WikiLink::key(" The Yellow King "), "the yellow king");
+    }
+
+    #[test]
+    fn retargeting_keeps_labels() {
+        let link = &WikiLink::parse("[[Old|shown]]")[0];
+        assert_eq!(link.retargeted("New"), "[[New|shown]]");
+        let link = &WikiLink::parse("[[Old]]")[0];
+        assert_eq!(link.retargeted("New"), "[[New]]");
+        assert_eq!(link.retargeted_as_shown("New"), "[[New|Old]]");
+    }
+
+    #[test]
+    fn renamed_title_is_retargeted() {
+        let before = entity("Carcosa", &[]);
+        let after = before.clone().with_title(String::from("Lost Carcosa"));
+        assert_eq!(
+            Retarget::between(&before, &after),
+            vec![Retarget {
+                from: String::from("Carcosa"),
+                to: String::from("Lost Carcosa"),
+                keep_text: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn title_kept_as_alias_is_not_retargeted() {
+        let before = entity("Carcosa", &[]);
+        let after = entity("Lost Carcosa", &["carcosa"]);
+        assert!(Retarget::between(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn replaced_alias_is_renamed_and_removed_alias_points_at_title() {
+        let before = entity("Hastur", &["The King", "Him"]);
+        let after = entity("Hastur", &["The Yellow King"]);
+        assert_eq!(
+            Retarget::between(&before, &after),
+            vec![
+                Retarget {
+                    from: String::from("The King"),
+                    to: String::from("The Yellow King"),
+                    keep_text: false,
+                },
+                Retarget {
+                    from: String::from("Him"),
+                    to: String::from("Hastur"),
+                    keep_text: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_names_are_not_retargeted() {
+        let before = entity("Hastur", &["The King"]);
+        let after = before.clone().with_tags(vec![String::from("deity")]);
+        assert!(Retarget::between(&before, &after).is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use log::kv::{self, VisitSource};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::types::LogLevel;
+
+impl From<log::Level> for LogLevel {
+    fn from(value: log::Level) -> Self {
+        match value {
+            log::Level::Error => Self::Error,
+            log::Level::Warn => Self::Warn,
+            log::Level::Info => Self::Info,
+            log::Level::Debug => Self::Debug,
+            log::Level::Trace => Self::Trace,
+        }
+    }
+}
+
+/// A single log record, as written to the log files and kept for the `logs` procedures.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+
+    /// The module the record was logged from.
+    pub target: String,
+
+    /// The name of the project open when the record was logged.
+    #[serde(default)]
+    pub project: Option<String>,
+
+    pub message: String,
+
+    /// Structured key-values attached to the record.
+    #[serde(default)]
+    pub fields: HashMap<String, serde_json::Value>,
+}
+
+struct FieldCollector(HashMap<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for FieldCollector {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = serde_json::to_value(&value).unwrap_or_else(|_| value.to_string().into());
+        let _ = self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+impl LogEntry {
+    pub fn new(record: &log::Record, project: Option<String>) -> Self {
+        let mut fields = FieldCollector(HashMap::new());
+        let _ = record.key_values().visit(&mut fields);
+        Self {
+            timestamp: Utc::now(),
+            level: record.level().into(),
+            target: record.target().to_string(),
+            project,
+            message: record.args().to_string(),
+            fields: fields.0,
+        }
+    }
+}
+
+/// Narrows down the entries returned by the `logs` procedures. Unset fields match everything.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+pub struct LogFilter {
+    /// The least severe level to include.
+    #[serde(default)]
+    pub level: Option<LogLevel>,
+
+    /// Only include records logged from modules starting with this prefix.
+    #[serde(default)]
+    pub target: Option<String>,
+
+    /// Only include records whose message contains this text, ignoring case.
+    #[serde(default)]
+    pub contains: Option<String>,
+
+    /// Return at most this many of the most recent matching entries.
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.level.is_none_or(|level| {
+            log::LevelFilter::from(entry.level) <= log::LevelFilter::from(level)
+        }) && self
+            .target
+            .as_ref()
+            .is_none_or(|target| entry.target.starts_with(target.as_str()))
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|text| entry.message.to_lowercase().contains(&text.to_lowercase()))
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::MetaError;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct TableRows {
+    pub table: String,
+    pub multimap: bool,
+    pub rows: u32,
+}
+
+/// A table that couldn't be copied in full while rebuilding a corrupted database.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct TableLoss {
+    pub table: String,
+    pub error: MetaError,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum IntegrityStatus {
+    Healthy,
+
+    /// redb found problems and fixed them in place.
+    Repaired,
+
+    /// The database couldn't be repaired in place, so whatever was readable was copied into a
+    /// fresh file. The original file was kept at `backup`.
+    Rebuilt {
+        backup: PathBuf,
+        #[serde(default)]
+        losses: Vec<TableLoss>,
+    },
+
+    /// The database couldn't be repaired in place, and there's no way to rebuild it. It was left
+    /// untouched.
+    Corrupted { error: MetaError },
+}
+
+/// The outcome of compacting or checking one open database. Sizes are in bytes, and kept as
+/// floating point so they stay exact in JS.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct MaintenanceReport {
+    pub database: String,
+    pub size_before: f64,
+    pub size_after: f64,
+
+    /// Set when compacting; `false` means there was nothing left to reclaim.
+    #[serde(default)]
+    pub compacted: Option<bool>,
+
+    /// Set when checking integrity.
+    #[serde(default)]
+    pub integrity: Option<IntegrityStatus>,
+    pub tables: Vec<TableRows>,
+}
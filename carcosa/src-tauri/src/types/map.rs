@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct MapLayer {
+    pub id: Uuid,
+    pub name: String,
+
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// An uploaded map image, cut into a pyramid of tiles for display.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct MapInfo {
+    pub id: Uuid,
+    pub name: String,
+
+    /// Hash of the original image in the asset store.
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: u32,
+
+    /// Number of pyramid levels. Level 0 is the full-size image, and every level after it is
+    /// half the size of the one before, down to a single tile.
+    pub levels: u32,
+
+    /// Layers in drawing order, bottom first.
+    #[serde(default)]
+    pub layers: Vec<MapLayer>,
+
+    /// The entity this map depicts. Pins outside of every linked region are located in it.
+    #[serde(default)]
+    pub location: Option<Uuid>,
+    pub created: DateTime<Utc>,
+}
+
+impl MapInfo {
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(crate::Error::validation("name", "Map name cannot be empty"));
+        }
+        for (index, layer) in self.layers.iter().enumerate() {
+            if layer.name.trim().is_empty() {
+                return Err(crate::Error::validation(
+                    format!("layers.{index}.name"),
+                    "Layer name cannot be empty",
+                ));
+            }
+            if self.layers[..index]
+                .iter()
+                .any(|other| other.id == layer.id)
+            {
+                return Err(crate::Error::validation(
+                    format!("layers.{index}.id"),
+                    "Layer IDs must be unique",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn layer(&self, id: Uuid) -> Option<&MapLayer> {
+        self.layers.iter().find(|layer| layer.id == id)
+    }
+
+    /// Number of tiles across and down at `level`.
+    pub fn tiles_at(&self, level: u32) -> (u32, u32) {
+        let width = (self.width >> level).max(1);
+        let height = (self.height >> level).max(1);
+        (
+            width.div_ceil(self.tile_size),
+            height.div_ceil(self.tile_size),
+        )
+    }
+}
+
+/// A position on a map, in pixels of the full-size image.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Type)]
+pub struct MapPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum AnnotationShape {
+    Pin { at: MapPoint },
+    Region { points: Vec<MapPoint> },
+    Path { points: Vec<MapPoint> },
+}
+
+impl AnnotationShape {
+    pub fn points(&self) -> Vec<MapPoint> {
+        match self {
+            Self::Pin { at } => vec![*at],
+            Self::Region { points } | Self::Path { points } => points.clone(),
+        }
+    }
+
+    /// Whether `point` is inside this region, using the even-odd rule. Always `false` for pins
+    /// and paths.
+    pub fn contains(&self, point: MapPoint) -> bool {
+        let Self::Region { points } = self else {
+            return false;
+        };
+        let mut inside = false;
+        for (index, a) in points.iter().enumerate() {
+            let b = points[(index + 1) % points.len()];
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+/// A pin, region or path drawn on a map, optionally linked to an entity.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct MapAnnotation {
+    pub id: Uuid,
+    pub map: Uuid,
+
+    /// The layer this annotation is drawn on, or `None` for the base layer.
+    #[serde(default)]
+    pub layer: Option<Uuid>,
+
+    #[serde(default)]
+    pub label: Option<String>,
+
+    #[serde(default)]
+    pub entity: Option<Uuid>,
+
+    #[serde(flatten)]
+    pub shape: AnnotationShape,
+}
+
+impl MapAnnotation {
+    pub fn validate(&self, map: &MapInfo) -> crate::Result<()> {
+        if let Some(layer) = self.layer {
+            if map.layer(layer).is_none() {
+                return Err(crate::Error::validation(
+                    "layer",
+                    format!("Map has no layer with ID {layer}"),
+                ));
+            }
+        }
+        let (minimum, kind) = match &self.shape {
+            AnnotationShape::Pin { .. } => (1, "Pins"),
+            AnnotationShape::Region { .. } => (3, "Regions"),
+            AnnotationShape::Path { .. } => (2, "Paths"),
+        };
+        let points = self.shape.points();
+        if points.len() < minimum {
+            return Err(crate::Error::validation(
+                "points",
+                format!("{kind} need at least {minimum} points"),
+            ));
+        }
+        let outside = points.iter().position(|point| {
+            !(0.0..=map.width as f64).contains(&point.x)
+                || !(0.0..=map.height as f64).contains(&point.y)
+        });
+        if let Some(index) = outside {
+            return Err(crate::Error::validation(
+                format!("points.{index}"),
+                "Point is outside of the map",
+            ));
+        }
+        Ok(())
+    }
+}
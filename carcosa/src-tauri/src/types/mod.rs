@@ -1,7 +1,37 @@
 pub mod network;
 pub mod project;
 pub mod app;
+pub mod world;
+pub mod template;
+pub mod recent;
+pub mod settings;
+pub mod logging;
+pub mod search;
+pub mod relation;
+pub mod link;
+pub mod calendar;
+pub mod timeline;
+pub mod map;
+pub mod asset;
+pub mod history;
+pub mod maintenance;
+pub mod changes;
 
 pub use network::*;
 pub use project::*;
 pub use app::*;
+pub use world::*;
+pub use template::*;
+pub use recent::*;
+pub use settings::*;
+pub use logging::*;
+pub use search::*;
+pub use relation::*;
+pub use link::*;
+pub use calendar::*;
+pub use timeline::*;
+pub use map::*;
+pub use asset::*;
+pub use history::*;
+pub use maintenance::*;
+pub use changes::*;
@@ -1,5 +1,6 @@
 use base64::prelude::*;
-use iroh::{PublicKey, SecretKey, Signature, SignatureError};
+use chrono::{DateTime, Utc};
+use iroh::{EndpointAddr, PublicKey, SecretKey, Signature, SignatureError};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
@@ -27,9 +28,9 @@ impl TryFrom<String> for NetworkIdentity {
     }
 }
 
-impl Into<String> for NetworkIdentity {
-    fn into(self) -> String {
-        BASE64_URL_SAFE_NO_PAD.encode(self.0.to_bytes())
+impl From<NetworkIdentity> for String {
+    fn from(value: NetworkIdentity) -> Self {
+        BASE64_URL_SAFE_NO_PAD.encode(value.0.to_bytes())
     }
 }
 
@@ -95,9 +96,9 @@ impl TryFrom<String> for PeerIdentity {
     }
 }
 
-impl Into<String> for PeerIdentity {
-    fn into(self) -> String {
-        BASE64_URL_SAFE_NO_PAD.encode(self.0.as_bytes())
+impl From<PeerIdentity> for String {
+    fn from(value: PeerIdentity) -> Self {
+        BASE64_URL_SAFE_NO_PAD.encode(value.0.as_bytes())
     }
 }
 
@@ -112,3 +113,124 @@ impl From<PeerIdentity> for PublicKey {
         value.into_inner()
     }
 }
+
+/// The signed contents of an [`InviteTicket`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InvitePayload {
+    pub host: PeerIdentity,
+    pub address: EndpointAddr,
+    pub project: String,
+    pub nonce: String,
+    pub expires: DateTime<Utc>,
+    pub can_edit: bool,
+}
+
+/// A copy-pasteable invitation to join a hosted project, signed by the host's identity.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InviteTicket {
+    pub payload: InvitePayload,
+    signature: String,
+}
+
+impl InviteTicket {
+    pub fn sign(identity: &NetworkIdentity, payload: InvitePayload) -> crate::Result<Self> {
+        let message = serde_json::to_vec(&payload)?;
+        let signature = BASE64_URL_SAFE_NO_PAD.encode(identity.sign(&message).to_bytes());
+        Ok(Self { payload, signature })
+    }
+
+    /// Checks that the ticket was signed by the host it names, points at that host's address, and
+    /// hasn't expired.
+    pub fn verify(&self) -> crate::Result<()> {
+        if PeerIdentity::from(self.payload.address.id) != self.payload.host {
+            return Err(crate::Error::validation(
+                "ticket",
+                "Invite address doesn't belong to its host",
+            ));
+        }
+        let message = serde_json::to_vec(&self.payload)?;
+        let signature: [u8; 64] = BASE64_URL_SAFE_NO_PAD
+            .decode(&self.signature)?
+            .try_into()
+            .map_err(|_| crate::Error::validation("ticket", "Malformed signature"))?;
+        self.payload
+            .host
+            .verify(&message, &Signature::from_bytes(&signature))?;
+        if self.payload.expires < Utc::now() {
+            return Err(crate::Error::validation("ticket", "Invite has expired"));
+        }
+        Ok(())
+    }
+
+    pub fn encode(&self) -> crate::Result<String> {
+        Ok(BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(self)?))
+    }
+
+    pub fn decode(ticket: impl AsRef<str>) -> crate::Result<Self> {
+        let decoded = BASE64_URL_SAFE_NO_PAD.decode(ticket.as_ref().trim())?;
+        Ok(serde_json::from_slice(&decoded)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn payload(host: &NetworkIdentity, expires: DateTime<Utc>) -> InvitePayload {
+        InvitePayload {
+            host: PeerIdentity::from(host.public_key()),
+            address: EndpointAddr::from(host.public_key()),
+            project: String::from("Carcosa"),
+            nonce: String::from("nonce"),
+            expires,
+            can_edit: true,
+        }
+    }
+
+    #[test]
+    fn signed_ticket_verifies_after_round_trip() {
+        let host = NetworkIdentity::generate();
+        let ticket =
+            InviteTicket::sign(&host, payload(&host, Utc::now() + Duration::hours(1))).unwrap();
+        let decoded = InviteTicket::decode(ticket.encode().unwrap()).unwrap();
+        assert!(decoded.verify().is_ok());
+        assert_eq!(decoded.payload.nonce, "nonce");
+    }
+
+    #[test]
+    fn ticket_signed_by_another_identity_is_rejected() {
+        let host = NetworkIdentity::generate();
+        let forger = NetworkIdentity::generate();
+        let ticket =
+            InviteTicket::sign(&forger, payload(&host, Utc::now() + Duration::hours(1))).unwrap();
+        assert!(ticket.verify().is_err());
+    }
+
+    #[test]
+    fn tampered_ticket_is_rejected() {
+        let host = NetworkIdentity::generate();
+        let mut ticket =
+            InviteTicket::sign(&host, payload(&host, Utc::now() + Duration::hours(1))).unwrap();
+        ticket.payload.can_edit = false;
+        assert!(ticket.verify().is_err());
+    }
+
+    #[test]
+    fn expired_ticket_is_rejected() {
+        let host = NetworkIdentity::generate();
+        let ticket =
+            InviteTicket::sign(&host, payload(&host, Utc::now() - Duration::hours(1))).unwrap();
+        assert!(ticket.verify().is_err());
+    }
+
+    #[test]
+    fn ticket_pointing_at_another_endpoint_is_rejected() {
+        let host = NetworkIdentity::generate();
+        let mut payload = payload(&host, Utc::now() + Duration::hours(1));
+        payload.address = EndpointAddr::from(NetworkIdentity::generate().public_key());
+        let ticket = InviteTicket::sign(&host, payload).unwrap();
+        assert!(ticket.verify().is_err());
+    }
+}
@@ -1,10 +1,22 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use getset::{CloneGetters, WithSetters};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
-use crate::types::{NetworkIdentity, PeerIdentity};
+use uuid::Uuid;
+
+use crate::types::{Calendar, EntityTemplate, NetworkIdentity, PeerIdentity};
+
+/// Directory inside a project folder that holds Carcosa's internal files.
+pub const PROJECT_DATA_DIR: &str = ".carcosa";
+
+/// Name (and file stem) of the main project database.
+pub const PROJECT_DATABASE: &str = "project";
 
 #[derive(Serialize, Deserialize, Clone, Debug, Type)]
 pub struct ProjectCollaborator {
@@ -27,6 +39,14 @@ pub struct ProjectSettings {
 
     #[serde(default)]
     collaborators: HashMap<PeerIdentity, ProjectCollaborator>,
+
+    #[serde(default)]
+    #[getset(set_with = "pub")]
+    templates: HashMap<Uuid, EntityTemplate>,
+
+    #[serde(default)]
+    #[getset(set_with = "pub")]
+    calendars: HashMap<Uuid, Calendar>,
 }
 
 impl ProjectSettings {
@@ -35,6 +55,8 @@ impl ProjectSettings {
             name: name.into(),
             identity: NetworkIdentity::generate(),
             collaborators: HashMap::new(),
+            templates: HashMap::new(),
+            calendars: HashMap::new(),
         }
     }
 
@@ -44,6 +66,14 @@ impl ProjectSettings {
         Ok(serde_json::from_str::<Self>(&settings_content)?)
     }
 
+    pub fn data_dir(project: impl AsRef<Path>) -> PathBuf {
+        project.as_ref().join(PROJECT_DATA_DIR)
+    }
+
+    pub fn database_path(project: impl AsRef<Path>, name: impl AsRef<str>) -> PathBuf {
+        Self::data_dir(project).join(format!("{}.redb", name.as_ref()))
+    }
+
     pub fn with_collaborator(mut self, collaborator: ProjectCollaborator) -> Self {
         let _ = self
             .collaborators
@@ -60,6 +90,34 @@ impl ProjectSettings {
         self.collaborators.get(&collaborator).cloned()
     }
 
+    pub fn with_template(mut self, template: EntityTemplate) -> Self {
+        let _ = self.templates.insert(template.id, template);
+        self
+    }
+
+    pub fn remove_template(mut self, template: Uuid) -> Self {
+        let _ = self.templates.remove(&template);
+        self
+    }
+
+    pub fn template(&self, template: Uuid) -> Option<EntityTemplate> {
+        self.templates.get(&template).cloned()
+    }
+
+    pub fn with_calendar(mut self, calendar: Calendar) -> Self {
+        let _ = self.calendars.insert(calendar.id, calendar);
+        self
+    }
+
+    pub fn remove_calendar(mut self, calendar: Uuid) -> Self {
+        let _ = self.calendars.remove(&calendar);
+        self
+    }
+
+    pub fn calendar(&self, calendar: Uuid) -> Option<Calendar> {
+        self.calendars.get(&calendar).cloned()
+    }
+
     pub fn save(self, project: impl AsRef<Path>) -> crate::Result<Self> {
         let project = project.as_ref().join("project.json");
         let settings_content = serde_json::to_string_pretty(&self.clone())?;
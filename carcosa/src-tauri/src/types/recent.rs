@@ -0,0 +1,146 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::types::{ActiveProject, PeerIdentity};
+
+/// Unpinned entries beyond this many are dropped, oldest first.
+pub const MAX_RECENT_PROJECTS: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct RecentProject {
+    pub path: PathBuf,
+    pub name: String,
+
+    /// The host of a joined remote project, or `None` for a local project.
+    #[serde(default)]
+    pub host: Option<PeerIdentity>,
+
+    pub last_opened: DateTime<Utc>,
+
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// Whether the project no longer exists on disk. Computed when the list is read.
+    #[serde(default, skip_deserializing)]
+    pub missing: bool,
+}
+
+impl RecentProject {
+    fn checked(mut self) -> Self {
+        self.missing = !self.path.join("project.json").is_file();
+        self
+    }
+
+    /// The project this entry reopens.
+    pub fn project(&self) -> ActiveProject {
+        match self.host.clone() {
+            Some(host) => ActiveProject::Remote {
+                host,
+                path: self.path.clone(),
+            },
+            None => ActiveProject::Local {
+                path: self.path.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RecentProjects {
+    #[serde(default)]
+    projects: Vec<RecentProject>,
+}
+
+impl RecentProjects {
+    pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str::<Self>(&content)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Every entry, pinned ones first and then most recently opened first.
+    pub fn projects(&self) -> Vec<RecentProject> {
+        let mut projects = self
+            .projects
+            .iter()
+            .cloned()
+            .map(RecentProject::checked)
+            .collect::<Vec<_>>();
+        projects.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| b.last_opened.cmp(&a.last_opened))
+        });
+        projects
+    }
+
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<RecentProject> {
+        self.projects
+            .iter()
+            .find(|p| p.path == path.as_ref())
+            .cloned()
+            .map(RecentProject::checked)
+    }
+
+    /// Records that `project` was just opened, adding it if it isn't listed yet.
+    pub fn with_opened(mut self, project: &ActiveProject, name: impl Into<String>) -> Self {
+        let (path, host) = match project {
+            ActiveProject::None => return self,
+            ActiveProject::Local { path } => (path.clone(), None),
+            ActiveProject::Remote { host, path } => (path.clone(), Some(host.clone())),
+        };
+        let pinned = self.get(&path).is_some_and(|p| p.pinned);
+        self.projects.retain(|p| p.path != path);
+        self.projects.push(RecentProject {
+            path,
+            name: name.into(),
+            host,
+            last_opened: Utc::now(),
+            pinned,
+            missing: false,
+        });
+
+        let mut unpinned = 0;
+        self.projects
+            .sort_by_key(|project| std::cmp::Reverse(project.last_opened));
+        self.projects.retain(|p| {
+            if p.pinned {
+                return true;
+            }
+            unpinned += 1;
+            unpinned <= MAX_RECENT_PROJECTS
+        });
+        self
+    }
+
+    pub fn with_pinned(mut self, path: impl AsRef<Path>, pinned: bool) -> crate::Result<Self> {
+        let project = self
+            .projects
+            .iter_mut()
+            .find(|p| p.path == path.as_ref())
+            .ok_or_else(|| crate::Error::not_found("recent project", path.as_ref().display()))?;
+        project.pinned = pinned;
+        Ok(self)
+    }
+
+    pub fn without(mut self, path: impl AsRef<Path>) -> Self {
+        self.projects.retain(|p| p.path != path.as_ref());
+        self
+    }
+}
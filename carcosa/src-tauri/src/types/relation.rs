@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RelationKind {
+    ParentOf,
+    MemberOf,
+    LocatedIn,
+    RivalOf,
+    Custom { label: String },
+}
+
+impl RelationKind {
+    /// The string this kind is stored as in the relation tables.
+    pub fn to_key(&self) -> String {
+        match self {
+            Self::ParentOf => "parent_of".to_string(),
+            Self::MemberOf => "member_of".to_string(),
+            Self::LocatedIn => "located_in".to_string(),
+            Self::RivalOf => "rival_of".to_string(),
+            Self::Custom { label } => format!("custom:{label}"),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "parent_of" => Self::ParentOf,
+            "member_of" => Self::MemberOf,
+            "located_in" => Self::LocatedIn,
+            "rival_of" => Self::RivalOf,
+            other => Self::Custom {
+                label: other.strip_prefix("custom:").unwrap_or(other).to_string(),
+            },
+        }
+    }
+}
+
+/// A typed, directed link between two entities. Links can be followed in either direction.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, Type)]
+pub struct Relation {
+    pub source: Uuid,
+    pub target: Uuid,
+    pub kind: RelationKind,
+}
+
+impl Relation {
+    /// The entity on the other end of this relation from `id`.
+    pub fn other(&self, id: Uuid) -> Uuid {
+        if self.source == id {
+            self.target
+        } else {
+            self.source
+        }
+    }
+
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.source == self.target {
+            return Err(crate::Error::validation(
+                "target",
+                "An entity cannot be related to itself",
+            ));
+        }
+        if let RelationKind::Custom { label } = &self.kind {
+            if label.trim().is_empty() {
+                return Err(crate::Error::validation(
+                    "kind.label",
+                    "Custom relations need a label",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct GraphNode {
+    pub id: Uuid,
+
+    /// Number of relations between this entity and the one the graph was fetched for.
+    pub depth: u32,
+}
+
+/// The entities within some number of relations of an entity, and the relations between them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+pub struct Neighbourhood {
+    pub nodes: Vec<GraphNode>,
+    pub relations: Vec<Relation>,
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+use crate::types::EntityKind;
+
+/// Splits text into lowercase alphanumeric terms.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+pub struct SearchQuery {
+    /// Free text. Bare words match as prefixes, `"quoted words"` match as a phrase, and
+    /// `tag:name` or `kind:name` narrow the results like [`SearchQuery::tags`] and
+    /// [`SearchQuery::kinds`].
+    pub text: String,
+
+    /// Only match entities of one of these kinds.
+    #[serde(default)]
+    pub kinds: Vec<EntityKind>,
+
+    /// Only match entities carrying every one of these tags.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// A [`SearchQuery`] with its text broken down into terms, phrases and filters.
+#[derive(Clone, Debug, Default)]
+pub struct ParsedQuery {
+    pub terms: Vec<String>,
+    pub phrases: Vec<Vec<String>>,
+    pub kinds: Vec<EntityKind>,
+    pub tags: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn parse(&self) -> crate::Result<ParsedQuery> {
+        let mut parsed = ParsedQuery {
+            kinds: self.kinds.clone(),
+            tags: self.tags.iter().map(|tag| tag.to_lowercase()).collect(),
+            ..Default::default()
+        };
+        for (index, part) in self.text.split('"').enumerate() {
+            if index % 2 == 1 {
+                let phrase = tokenize(part);
+                match phrase.len() {
+                    0 => {}
+                    1 => parsed.terms.extend(phrase),
+                    _ => parsed.phrases.push(phrase),
+                }
+                continue;
+            }
+            for word in part.split_whitespace() {
+                if let Some(tag) = word.strip_prefix("tag:") {
+                    parsed.tags.push(tag.to_lowercase());
+                } else if let Some(kind) = word.strip_prefix("kind:") {
+                    let kind = serde_json::from_value::<EntityKind>(kind.to_lowercase().into())
+                        .map_err(|_| {
+                            crate::Error::validation("text", format!("Unknown kind {kind:?}"))
+                        })?;
+                    parsed.kinds.push(kind);
+                } else {
+                    parsed.terms.extend(tokenize(word));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl ParsedQuery {
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.phrases.is_empty()
+    }
+
+    /// Every word searched for, whether on its own or as part of a phrase.
+    pub fn words(&self) -> impl Iterator<Item = &String> {
+        self.terms.iter().chain(self.phrases.iter().flatten())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct SearchResult {
+    pub id: Uuid,
+    pub title: String,
+    pub kind: EntityKind,
+    pub score: f64,
+
+    /// An excerpt of the entity's body around the first match, if the body matched.
+    pub snippet: Option<String>,
+}
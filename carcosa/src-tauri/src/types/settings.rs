@@ -0,0 +1,122 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::types::{ActiveProject, DEFAULT_HISTORY_DEPTH};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for log::LevelFilter {
+    fn from(value: LogLevel) -> Self {
+        match value {
+            LogLevel::Error => Self::Error,
+            LogLevel::Warn => Self::Warn,
+            LogLevel::Info => Self::Info,
+            LogLevel::Debug => Self::Debug,
+            LogLevel::Trace => Self::Trace,
+        }
+    }
+}
+
+/// Hints the frontend uses to restore its window the way the user left it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, Type)]
+pub struct WindowLayout {
+    #[serde(default)]
+    pub width: Option<u32>,
+
+    #[serde(default)]
+    pub height: Option<u32>,
+
+    #[serde(default)]
+    pub maximized: bool,
+
+    #[serde(default)]
+    pub sidebar_width: Option<u32>,
+}
+
+/// Global settings shared by every project, persisted in the user's config directory.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+pub struct AppSettings {
+    /// The project open when the app last closed, reopened on the next launch.
+    #[serde(default)]
+    pub last_project: ActiveProject,
+
+    #[serde(default)]
+    pub window: WindowLayout,
+
+    #[serde(default)]
+    pub theme: Theme,
+
+    /// Where new projects are created unless another directory is picked.
+    #[serde(default)]
+    pub default_project_dir: Option<PathBuf>,
+
+    #[serde(default)]
+    pub log_level: LogLevel,
+
+    /// How many operations can be undone. Uses [`DEFAULT_HISTORY_DEPTH`] when unset.
+    #[serde(default)]
+    pub history_depth: Option<u32>,
+}
+
+/// Every on-disk layout of [`AppSettings`]. New versions are added as variants, and older ones
+/// are upgraded on load.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "version")]
+enum VersionedAppSettings {
+    #[serde(rename = "1")]
+    V1(AppSettings),
+}
+
+impl From<VersionedAppSettings> for AppSettings {
+    fn from(value: VersionedAppSettings) -> Self {
+        match value {
+            VersionedAppSettings::V1(settings) => settings,
+        }
+    }
+}
+
+impl AppSettings {
+    pub fn undo_depth(&self) -> u32 {
+        self.history_depth.unwrap_or(DEFAULT_HISTORY_DEPTH)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str::<VersionedAppSettings>(&content)?.into()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(self, path: impl AsRef<Path>) -> crate::Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(&VersionedAppSettings::V1(self.clone()))?;
+        fs::write(path, content)?;
+        Ok(self)
+    }
+}
@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+use crate::types::EntityKind;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldType {
+    Text,
+    RichText,
+    Number {
+        #[serde(default)]
+        min: Option<f64>,
+
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    Date,
+    Enum {
+        options: Vec<String>,
+    },
+    Reference {
+        #[serde(default)]
+        kind: Option<EntityKind>,
+    },
+    List {
+        item: Box<FieldType>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum FieldValue {
+    Text(String),
+    RichText(String),
+    Number(f64),
+    Date(NaiveDate),
+    Enum(String),
+    Reference(Uuid),
+    List(Vec<FieldValue>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct TemplateField {
+    pub name: String,
+
+    #[serde(default)]
+    pub label: Option<String>,
+
+    pub field_type: FieldType,
+
+    #[serde(default)]
+    pub required: bool,
+
+    /// Filled into existing entities when this field is added by a template change.
+    #[serde(default)]
+    pub default: Option<FieldValue>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct EntityTemplate {
+    pub id: Uuid,
+    pub name: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub fields: Vec<TemplateField>,
+}
+
+/// How existing entities should be carried over when a template's fields change.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum FieldChange {
+    Rename { from: String, to: String },
+    Remove { name: String },
+}
+
+impl FieldType {
+    /// Validates `value` against this type. `path` is the location of the value, used in errors,
+    /// and `resolve` looks up the kind of a referenced entity.
+    pub fn validate(
+        &self,
+        path: &str,
+        value: &FieldValue,
+        resolve: &impl Fn(Uuid) -> Option<EntityKind>,
+    ) -> crate::Result<()> {
+        match (self, value) {
+            (Self::Text, FieldValue::Text(_))
+            | (Self::RichText, FieldValue::RichText(_))
+            | (Self::Date, FieldValue::Date(_)) => Ok(()),
+            (Self::Number { min, max }, FieldValue::Number(number)) => {
+                if !number.is_finite() {
+                    Err(crate::Error::validation(path, "Number must be finite"))
+                } else if min.is_some_and(|min| *number < min) {
+                    Err(crate::Error::validation(
+                        path,
+                        format!("Number is below the minimum of {}", min.unwrap()),
+                    ))
+                } else if max.is_some_and(|max| *number > max) {
+                    Err(crate::Error::validation(
+                        path,
+                        format!("Number is above the maximum of {}", max.unwrap()),
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            (Self::Enum { options }, FieldValue::Enum(option)) => {
+                if options.contains(option) {
+                    Ok(())
+                } else {
+                    Err(crate::Error::validation(
+                        path,
+                        format!("{option:?} is not one of the allowed options"),
+                    ))
+                }
+            }
+            (Self::Reference { kind }, FieldValue::Reference(id)) => match (resolve(*id), kind) {
+                (None, _) => Err(crate::Error::validation(
+                    path,
+                    format!("Referenced entity {id} does not exist"),
+                )),
+                (Some(actual), Some(expected)) if actual != *expected => {
+                    Err(crate::Error::validation(
+                        path,
+                        format!("Expected a reference to a {expected}, found a {actual}"),
+                    ))
+                }
+                _ => Ok(()),
+            },
+            (Self::List { item }, FieldValue::List(values)) => {
+                for (index, value) in values.iter().enumerate() {
+                    item.validate(&format!("{path}[{index}]"), value, resolve)?;
+                }
+                Ok(())
+            }
+            (expected, _) => Err(crate::Error::validation(
+                path,
+                format!("Value does not match the field type {expected:?}"),
+            )),
+        }
+    }
+}
+
+impl EntityTemplate {
+    pub fn new(name: impl Into<String>, fields: Vec<TemplateField>) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            name: name.into(),
+            description: None,
+            fields,
+        }
+    }
+
+    pub fn field(&self, name: impl AsRef<str>) -> Option<&TemplateField> {
+        self.fields.iter().find(|f| f.name == name.as_ref())
+    }
+
+    /// Checks the template definition itself, ie. that it is named and its field names are unique.
+    pub fn validate_definition(&self) -> crate::Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(crate::Error::validation("name", "Name cannot be empty"));
+        }
+        for (index, field) in self.fields.iter().enumerate() {
+            let path = format!("fields[{index}]");
+            if field.name.trim().is_empty() {
+                return Err(crate::Error::validation(path, "Field name cannot be empty"));
+            }
+            if self.fields[..index].iter().any(|f| f.name == field.name) {
+                return Err(crate::Error::validation(
+                    path,
+                    format!("Duplicate field name {:?}", field.name),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the fields of an entity using this template.
+    pub fn validate(
+        &self,
+        fields: &HashMap<String, FieldValue>,
+        resolve: impl Fn(Uuid) -> Option<EntityKind>,
+    ) -> crate::Result<()> {
+        if let Some(unknown) = fields.keys().find(|name| self.field(name).is_none()) {
+            return Err(crate::Error::validation(
+                format!("fields.{unknown}"),
+                format!("Template {:?} has no such field", self.name),
+            ));
+        }
+        for field in &self.fields {
+            let path = format!("fields.{}", field.name);
+            match fields.get(&field.name) {
+                Some(value) => field.field_type.validate(&path, value, &resolve)?,
+                None if field.required => {
+                    return Err(crate::Error::validation(path, "Field is required"));
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Carries an entity's fields over to `evolved`, applying `changes` and filling defaults.
+    pub fn migrate_fields(
+        evolved: &EntityTemplate,
+        changes: &[FieldChange],
+        mut fields: HashMap<String, FieldValue>,
+    ) -> HashMap<String, FieldValue> {
+        for change in changes {
+            match change {
+                FieldChange::Rename { from, to } => {
+                    if let Some(value) = fields.remove(from) {
+                        let _ = fields.insert(to.clone(), value);
+                    }
+                }
+                FieldChange::Remove { name } => {
+                    let _ = fields.remove(name);
+                }
+            }
+        }
+        fields.retain(|name, _| evolved.field(name).is_some());
+        for field in &evolved.fields {
+            if let (false, Some(default)) = (fields.contains_key(&field.name), &field.default) {
+                let _ = fields.insert(field.name.clone(), default.clone());
+            }
+        }
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, field_type: FieldType) -> TemplateField {
+        TemplateField {
+            name: name.to_string(),
+            label: None,
+            field_type,
+            required: false,
+            default: None,
+        }
+    }
+
+    fn template() -> EntityTemplate {
+        EntityTemplate::new(
+            "Deity",
+            vec![
+                TemplateField {
+                    required: true,
+                    ..field("domain", FieldType::Text)
+                },
+                field(
+                    "worshippers",
+                    FieldType::Number {
+                        min: Some(0.0),
+                        max: None,
+                    },
+                ),
+                field(
+                    "alignment",
+                    FieldType::Enum {
+                        options: vec![String::from("lawful"), String::from("chaotic")],
+                    },
+                ),
+                field(
+                    "seat",
+                    FieldType::Reference {
+                        kind: Some(EntityKind::Location),
+                    },
+                ),
+                field(
+                    "epithets",
+                    FieldType::List {
+                        item: Box::new(FieldType::Text),
+                    },
+                ),
+            ],
+        )
+    }
+
+    fn fields(values: Vec<(&str, FieldValue)>) -> HashMap<String, FieldValue> {
+        values
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect()
+    }
+
+    fn invalid_input(result: crate::Result<()>) -> String {
+        match result {
+            Err(crate::Error::Validation { input, .. }) => input,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_valid_fields() {
+        let location = Uuid::now_v7();
+        let values = fields(vec![
+            ("domain", FieldValue::Text(String::from("decay"))),
+            ("worshippers", FieldValue::Number(12.0)),
+            ("alignment", FieldValue::Enum(String::from("chaotic"))),
+            ("seat", FieldValue::Reference(location)),
+            (
+                "epithets",
+                FieldValue::List(vec![FieldValue::Text(String::from("the King in Yellow"))]),
+            ),
+        ]);
+        assert!(template()
+            .validate(&values, |id| (id == location)
+                .then_some(EntityKind::Location))
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_required_and_unknown_fields() {
+        let template = template();
+        assert_eq!(
+            invalid_input(template.validate(&HashMap::new(), |_| None)),
+            "fields.domain"
+        );
+        let values = fields(vec![
+            ("domain", FieldValue::Text(String::from("decay"))),
+            ("colour", FieldValue::Text(String::from("yellow"))),
+        ]);
+        assert_eq!(
+            invalid_input(template.validate(&values, |_| None)),
+            "fields.colour"
+        );
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_shape() {
+        let template = template();
+        let domain = ("domain", FieldValue::Text(String::from("decay")));
+        for (name, value) in [
+            ("worshippers", FieldValue::Number(-1.0)),
+            ("worshippers", FieldValue::Number(f64::NAN)),
+            ("worshippers", FieldValue::Text(String::from("many"))),
+            ("alignment", FieldValue::Enum(String::from("neutral"))),
+        ] {
+            let values = fields(vec![domain.clone(), (name, value)]);
+            assert_eq!(
+                invalid_input(template.validate(&values, |_| None)),
+                format!("fields.{name}")
+            );
+        }
+        let values = fields(vec![
+            domain.clone(),
+            (
+                "epithets",
+                FieldValue::List(vec![
+                    FieldValue::Text(String::from("Hastur")),
+                    FieldValue::Number(1.0),
+                ]),
+            ),
+        ]);
+        assert_eq!(
+            invalid_input(template.validate(&values, |_| None)),
+            "fields.epithets[1]"
+        );
+    }
+
+    #[test]
+    fn checks_referenced_entities() {
+        let template = template();
+        let seat = Uuid::now_v7();
+        let values = fields(vec![
+            ("domain", FieldValue::Text(String::from("decay"))),
+            ("seat", FieldValue::Reference(seat)),
+        ]);
+        assert_eq!(
+            invalid_input(template.validate(&values, |_| None)),
+            "fields.seat"
+        );
+        assert_eq!(
+            invalid_input(template.validate(&values, |_| Some(EntityKind::Character))),
+            "fields.seat"
+        );
+    }
+
+    #[test]
+    fn rejects_unnamed_and_duplicate_definitions() {
+        let mut unnamed = template();
+        unnamed.name = String::from(" ");
+        assert_eq!(invalid_input(unnamed.validate_definition()), "name");
+
+        let mut duplicate = template();
+        duplicate.fields.push(field("domain", FieldType::RichText));
+        assert_eq!(invalid_input(duplicate.validate_definition()), "fields[5]");
+        assert!(template().validate_definition().is_ok());
+    }
+
+    #[test]
+    fn migration_renames_removes_and_fills_defaults() {
+        let mut evolved = template();
+        evolved.fields.retain(|field| field.name != "worshippers");
+        evolved.fields[0].name = String::from("portfolio");
+        evolved.fields.push(TemplateField {
+            default: Some(FieldValue::Text(String::from("unknown"))),
+            ..field("origin", FieldType::Text)
+        });
+        let migrated = EntityTemplate::migrate_fields(
+            &evolved,
+            &[
+                FieldChange::Rename {
+                    from: String::from("domain"),
+                    to: String::from("portfolio"),
+                },
+                FieldChange::Remove {
+                    name: String::from("worshippers"),
+                },
+            ],
+            fields(vec![
+                ("domain", FieldValue::Text(String::from("decay"))),
+                ("worshippers", FieldValue::Number(12.0)),
+                ("stale", FieldValue::Text(String::from("dropped"))),
+            ]),
+        );
+        assert_eq!(
+            migrated,
+            fields(vec![
+                ("portfolio", FieldValue::Text(String::from("decay"))),
+                ("origin", FieldValue::Text(String::from("unknown"))),
+            ])
+        );
+        assert!(evolved.validate(&migrated, |_| None).is_ok());
+    }
+
+    #[test]
+    fn migration_keeps_existing_values_over_defaults() {
+        let mut evolved = template();
+        evolved.fields[0].default = Some(FieldValue::Text(String::from("default")));
+        let migrated = EntityTemplate::migrate_fields(
+            &evolved,
+            &[],
+            fields(vec![("domain", FieldValue::Text(String::from("decay")))]),
+        );
+        assert_eq!(
+            migrated.get("domain"),
+            Some(&FieldValue::Text(String::from("decay")))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+use crate::types::DescribedDate;
+
+/// Something that happened in the world, attached to the entities involved in it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+pub struct TimelineEvent {
+    pub id: Uuid,
+    pub title: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// The absolute day the event starts on. See [`crate::types::Calendar::to_days`].
+    pub start: i32,
+
+    /// The absolute day the event ends on, for events lasting more than one day.
+    #[serde(default)]
+    pub end: Option<i32>,
+
+    #[serde(default)]
+    pub entities: Vec<Uuid>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl TimelineEvent {
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.title.trim().is_empty() {
+            return Err(crate::Error::validation(
+                "title",
+                "Event title cannot be empty",
+            ));
+        }
+        if self.end.is_some_and(|end| end < self.start) {
+            return Err(crate::Error::validation(
+                "end",
+                "Events cannot end before they start",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn last_day(&self) -> i32 {
+        self.end.unwrap_or(self.start)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+pub struct TimelineQuery {
+    /// Only include events still ongoing on or after this absolute day.
+    #[serde(default)]
+    pub start: Option<i32>,
+
+    /// Only include events starting on or before this absolute day.
+    #[serde(default)]
+    pub end: Option<i32>,
+
+    #[serde(default)]
+    pub entity: Option<Uuid>,
+
+    #[serde(default)]
+    pub tag: Option<String>,
+
+    /// The calendar to describe event dates in.
+    #[serde(default)]
+    pub calendar: Option<Uuid>,
+}
+
+impl TimelineQuery {
+    pub fn matches(&self, event: &TimelineEvent) -> bool {
+        self.start.is_none_or(|start| event.last_day() >= start)
+            && self.end.is_none_or(|end| event.start <= end)
+            && self
+                .entity
+                .is_none_or(|entity| event.entities.contains(&entity))
+            && self.tag.as_ref().is_none_or(|tag| {
+                event
+                    .tags
+                    .iter()
+                    .any(|candidate| candidate.eq_ignore_ascii_case(tag))
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct TimelineEntry {
+    pub event: TimelineEvent,
+
+    /// Set when the query asked for a calendar.
+    pub start: Option<DescribedDate>,
+    pub end: Option<DescribedDate>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(start: i32, end: Option<i32>) -> TimelineEvent {
+        TimelineEvent {
+            id: Uuid::now_v7(),
+            title: String::from("Fall of the city"),
+            description: None,
+            start,
+            end,
+            entities: Vec::new(),
+            tags: vec![String::from("War")],
+        }
+    }
+
+    fn between(start: Option<i32>, end: Option<i32>) -> TimelineQuery {
+        TimelineQuery {
+            start,
+            end,
+            ..TimelineQuery::default()
+        }
+    }
+
+    #[test]
+    fn matches_events_overlapping_the_range() {
+        let ongoing = event(10, Some(20));
+        assert!(TimelineQuery::default().matches(&ongoing));
+        assert!(between(Some(15), Some(16)).matches(&ongoing));
+        assert!(between(Some(20), None).matches(&ongoing));
+        assert!(between(None, Some(10)).matches(&ongoing));
+        assert!(!between(Some(21), None).matches(&ongoing));
+        assert!(!between(None, Some(9)).matches(&ongoing));
+
+        let single_day = event(10, None);
+        assert!(between(Some(10), Some(10)).matches(&single_day));
+        assert!(!between(Some(11), None).matches(&single_day));
+    }
+
+    #[test]
+    fn matches_entities_and_tags() {
+        let entity = Uuid::now_v7();
+        let event = TimelineEvent {
+            entities: vec![entity],
+            ..event(0, None)
+        };
+        let query = |entity, tag: Option<&str>| TimelineQuery {
+            entity,
+            tag: tag.map(String::from),
+            ..TimelineQuery::default()
+        };
+        assert!(query(Some(entity), None).matches(&event));
+        assert!(!query(Some(Uuid::now_v7()), None).matches(&event));
+        assert!(query(None, Some("war")).matches(&event));
+        assert!(!query(None, Some("peace")).matches(&event));
+        assert!(query(Some(entity), Some("WAR")).matches(&event));
+    }
+
+    #[test]
+    fn rejects_events_ending_before_they_start() {
+        assert!(event(10, Some(10)).validate().is_ok());
+        assert!(event(10, Some(9)).validate().is_err());
+        let untitled = TimelineEvent {
+            title: String::from("  "),
+            ..event(0, None)
+        };
+        assert!(untitled.validate().is_err());
+    }
+}
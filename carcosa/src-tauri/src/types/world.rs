@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use getset::{CloneGetters, WithSetters};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+use crate::types::FieldValue;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Type, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum EntityKind {
+    Article,
+    Character,
+    Location,
+    Faction,
+    Item,
+    Event,
+    Custom,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EntityData {
+    Article {},
+    Character {
+        #[serde(default)]
+        species: Option<String>,
+
+        #[serde(default)]
+        occupation: Option<String>,
+    },
+    Location {
+        #[serde(default)]
+        parent: Option<Uuid>,
+    },
+    Faction {
+        #[serde(default)]
+        leader: Option<Uuid>,
+    },
+    Item {
+        #[serde(default)]
+        owner: Option<Uuid>,
+    },
+    Event {
+        #[serde(default)]
+        location: Option<Uuid>,
+    },
+    Custom {
+        template: Uuid,
+
+        #[serde(default)]
+        fields: HashMap<String, FieldValue>,
+    },
+}
+
+impl EntityData {
+    pub fn template(&self) -> Option<Uuid> {
+        match self {
+            Self::Custom { template, .. } => Some(*template),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> EntityKind {
+        match self {
+            Self::Article { .. } => EntityKind::Article,
+            Self::Character { .. } => EntityKind::Character,
+            Self::Location { .. } => EntityKind::Location,
+            Self::Faction { .. } => EntityKind::Faction,
+            Self::Item { .. } => EntityKind::Item,
+            Self::Event { .. } => EntityKind::Event,
+            Self::Custom { .. } => EntityKind::Custom,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, CloneGetters, WithSetters, Type)]
+#[getset(get_clone = "pub")]
+pub struct Entity {
+    id: Uuid,
+
+    #[getset(set_with = "pub")]
+    title: String,
+
+    #[serde(default)]
+    #[getset(set_with = "pub")]
+    aliases: Vec<String>,
+
+    #[serde(default)]
+    #[getset(set_with = "pub")]
+    tags: Vec<String>,
+
+    created: DateTime<Utc>,
+    updated: DateTime<Utc>,
+
+    #[getset(set_with = "pub")]
+    data: EntityData,
+}
+
+impl Entity {
+    pub fn new(title: impl Into<String>, data: EntityData) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::now_v7(),
+            title: title.into(),
+            aliases: Vec::new(),
+            tags: Vec::new(),
+            created: now,
+            updated: now,
+            data,
+        }
+    }
+
+    pub fn kind(&self) -> EntityKind {
+        self.data.kind()
+    }
+
+    /// Every name this entity can be referred to by, starting with its title.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![self.title.clone()];
+        names.extend(self.aliases.iter().cloned());
+        names
+    }
+
+    pub fn touch(mut self) -> Self {
+        self.updated = Utc::now();
+        self
+    }
+
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.title.trim().is_empty() {
+            return Err(crate::Error::validation("title", "Title cannot be empty"));
+        }
+        if self.aliases.iter().any(|a| a.trim().is_empty()) {
+            return Err(crate::Error::validation(
+                "aliases",
+                "Aliases cannot be empty",
+            ));
+        }
+        if self.tags.iter().any(|t| t.trim().is_empty()) {
+            return Err(crate::Error::validation("tags", "Tags cannot be empty"));
+        }
+        Ok(())
+    }
+}
+
+/// A partial update to an [`Entity`]. Fields left as `None` are unchanged.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Type)]
+pub struct EntityUpdate {
+    #[serde(default)]
+    pub title: Option<String>,
+
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub data: Option<EntityData>,
+}
+
+impl EntityUpdate {
+    pub fn apply(self, entity: Entity) -> Entity {
+        let mut entity = entity;
+        if let Some(title) = self.title {
+            entity = entity.with_title(title);
+        }
+        if let Some(aliases) = self.aliases {
+            entity = entity.with_aliases(aliases);
+        }
+        if let Some(tags) = self.tags {
+            entity = entity.with_tags(tags);
+        }
+        if let Some(data) = self.data {
+            entity = entity.with_data(data);
+        }
+        entity.touch()
+    }
+}
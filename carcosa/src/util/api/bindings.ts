@@ -4,21 +4,498 @@ import { createTauRPCProxy as createProxy, type InferCommandOutput } from 'taurp
 type TAURI_CHANNEL<T> = (response: T) => void
 
 
-export type ActiveProject = { kind: "none" } | { kind: "local"; path: string }
+export type ActiveProject = { kind: "none" } | { kind: "local"; path: string } | 
+/**
+ * A project hosted by another peer, mirrored into a local cache directory.
+ */
+{ kind: "remote"; host: string; path: string }
 
-export type AppEvent = { event: "activated_project"; project: ActiveProject }
+export type AppEvent = { event: "activated_project"; project: ActiveProject } | { event: "migration_progress"; database: string; version: number; description: string; step: number; total: number } | { event: "migration_failed"; database: string; error: MetaError } | { event: "peer_connected"; peer: string } | { event: "peer_disconnected"; peer: string } | { event: "remote_document_update"; document: string; peer: string } | { event: "rejected_peer_updates"; peer: string; documents: string[] } | { event: "updates_rejected_by_peer"; peer: string; documents: string[] } | { event: "asset_transfer_progress"; hash: string; peer: string; received: number; total: number } | { event: "asset_transferred"; hash: string; peer: string } | { event: "asset_transfer_failed"; hash: string; error: MetaError } | { event: "history_applied"; item: HistoryItem; undone: boolean } | { event: "tables_changed"; subscription: string; database: string; changes: TableChange[] } | { event: "changes_missed"; subscription: string; database: string } | { event: "project_settings_changed" }
+
+/**
+ * Global settings shared by every project, persisted in the user's config directory.
+ */
+export type AppSettings = { 
+/**
+ * The project open when the app last closed, reopened on the next launch.
+ */
+last_project?: ActiveProject; window?: WindowLayout; theme?: Theme; 
+/**
+ * Where new projects are created unless another directory is picked.
+ */
+default_project_dir?: string | null; log_level?: LogLevel; 
+/**
+ * How many operations can be undone. Uses [`DEFAULT_HISTORY_DEPTH`] when unset.
+ */
+history_depth?: number | null }
+
+export type AssetDimensions = { width: number; height: number }
+
+/**
+ * A file imported into a project, identified by the BLAKE3 hash of its contents.
+ */
+export type AssetInfo = { 
+/**
+ * Hex-encoded BLAKE3 hash of the file's contents.
+ */
+hash: string; 
+/**
+ * File name the asset was first imported under.
+ */
+name: string; mime: string; size: number; 
+/**
+ * Set for images in a format the backend can read.
+ */
+dimensions?: AssetDimensions | null; uploaded_by: string; uploaded: string }
+
+/**
+ * An asset along with every entity referencing it.
+ */
+export type AssetUsage = { asset: AssetInfo; entities: string[] }
+
+export type Backlink = { 
+/**
+ * The entity whose body contains the link.
+ */
+source: string; 
+/**
+ * The link target as written, ie. a name or an entity ID.
+ */
+target: string }
+
+/**
+ * A project-defined calendar. Every calendar maps its dates onto the same absolute day count,
+ * which is what events are stored as.
+ */
+export type Calendar = { id: string; name: string; months: CalendarMonth[]; weekdays?: string[]; leap_rules?: LeapRule[]; 
+/**
+ * Eras in order of their start year. Years before the first era have no era.
+ */
+eras?: Era[]; 
+/**
+ * The absolute day on which year 0 of this calendar starts.
+ */
+epoch?: number; 
+/**
+ * The weekday of the epoch, as an index into `weekdays`.
+ */
+epoch_weekday?: number }
+
+/**
+ * A day in a specific calendar. `month` and `day` count from 1.
+ */
+export type CalendarDate = { year: number; month: number; day: number }
+
+export type CalendarMonth = { name: string; days: number; 
+/**
+ * Days added to this month in leap years.
+ */
+leap_days?: number }
+
+/**
+ * Which changes a subscriber is notified of.
+ */
+export type ChangeSubscription = { id: string; 
+/**
+ * Only changes to this database, or to every open database if unset.
+ */
+database?: string | null; 
+/**
+ * Only changes to these tables, or to every table if empty.
+ */
+tables?: string[] }
+
+/**
+ * Whether the open project is currently reachable by, or connected to, other peers.
+ */
+export type ConnectionStatus = 
+/**
+ * Sync is not running, or the host of a remote project can't be reached. Edits are kept
+ * locally and reconciled once a connection is made.
+ */
+{ status: "offline" } | 
+/**
+ * A local project is being shared with `peers` connected collaborators.
+ */
+{ status: "hosting"; peers: number } | 
+/**
+ * Connected to the host of a remote project.
+ */
+{ status: "connected" }
+
+export type CurrentProject = { project: ActiveProject; settings: ProjectSettings; connection: ConnectionStatus }
+
+/**
+ * An absolute day described in a calendar, ready for display.
+ */
+export type DescribedDate = { days: number; date: CalendarDate; month: string; weekday: string | null; era: string | null; 
+/**
+ * The year counted from the start of `era`, starting at 1.
+ */
+year_of_era: number; text: string }
+
+export type Entity = { id: string; title: string; aliases?: string[]; tags?: string[]; created: string; updated: string; data: EntityData }
+
+export type EntityData = { kind: "article" } | { kind: "character"; species?: string | null; occupation?: string | null } | { kind: "location"; parent?: string | null } | { kind: "faction"; leader?: string | null } | { kind: "item"; owner?: string | null } | { kind: "event"; location?: string | null } | { kind: "custom"; template: string; fields?: Partial<{ [key in string]: FieldValue }> }
+
+export type EntityKind = "article" | "character" | "location" | "faction" | "item" | "event" | "custom"
+
+export type EntityTemplate = { id: string; name: string; description?: string | null; fields?: TemplateField[] }
+
+/**
+ * A partial update to an [`Entity`]. Fields left as `None` are unchanged.
+ */
+export type EntityUpdate = { title?: string | null; aliases?: string[] | null; tags?: string[] | null; data?: EntityData | null }
+
+/**
+ * A named span of years, lasting until the next era starts.
+ */
+export type Era = { name: string; abbreviation?: string | null; 
+/**
+ * The calendar year this era's first year falls on.
+ */
+start_year: number }
+
+/**
+ * How existing entities should be carried over when a template's fields change.
+ */
+export type FieldChange = { change: "rename"; from: string; to: string } | { change: "remove"; name: string }
+
+export type FieldType = { type: "text" } | { type: "rich_text" } | { type: "number"; min?: number | null; max?: number | null } | { type: "date" } | { type: "enum"; options: string[] } | { type: "reference"; kind?: EntityKind | null } | { type: "list"; item: FieldType }
+
+export type FieldValue = { type: "text"; value: string } | { type: "rich_text"; value: string } | { type: "number"; value: number } | { type: "date"; value: string } | { type: "enum"; value: string } | { type: "reference"; value: string } | { type: "list"; value: FieldValue[] }
+
+export type GraphNode = { id: string; 
+/**
+ * Number of relations between this entity and the one the graph was fetched for.
+ */
+depth: number }
+
+/**
+ * The undo and redo stacks, most recent operation last.
+ */
+export type History = { undo: HistoryItem[]; redo: HistoryItem[]; depth: number }
+
+/**
+ * A user-level operation that can be undone or redone.
+ */
+export type HistoryItem = { id: string; label: string; views: HistoryView[]; at: string }
+
+/**
+ * Parts of the UI an undone or redone operation may have changed.
+ */
+export type HistoryView = "entities" | "relations" | "timeline" | "documents" | "search" | "maps" | "assets"
+
+export type IntegrityStatus = { status: "healthy" } | 
+/**
+ * redb found problems and fixed them in place.
+ */
+{ status: "repaired" } | 
+/**
+ * The database couldn't be repaired in place, so whatever was readable was copied into a
+ * fresh file. The original file was kept at `backup`.
+ */
+{ status: "rebuilt"; backup: string; losses?: TableLoss[] } | 
+/**
+ * The database couldn't be repaired in place, and there's no way to rebuild it. It was left
+ * untouched.
+ */
+{ status: "corrupted"; error: MetaError }
+
+export type JsonValue = null | boolean | number | string | JsonValue[] | Partial<{ [key in string]: JsonValue }>
+
+/**
+ * Years divisible by `every` are leap years if `leap` is set, or common years otherwise. Later
+ * rules override earlier ones, so the Gregorian rules are `4: leap, 100: common, 400: leap`.
+ */
+export type LeapRule = { every: number; leap: boolean }
+
+/**
+ * A single log record, as written to the log files and kept for the `logs` procedures.
+ */
+export type LogEntry = { timestamp: string; level: LogLevel; 
+/**
+ * The module the record was logged from.
+ */
+target: string; 
+/**
+ * The name of the project open when the record was logged.
+ */
+project?: string | null; message: string; 
+/**
+ * Structured key-values attached to the record.
+ */
+fields?: Partial<{ [key in string]: JsonValue }> }
+
+/**
+ * Narrows down the entries returned by the `logs` procedures. Unset fields match everything.
+ */
+export type LogFilter = { 
+/**
+ * The least severe level to include.
+ */
+level?: LogLevel | null; 
+/**
+ * Only include records logged from modules starting with this prefix.
+ */
+target?: string | null; 
+/**
+ * Only include records whose message contains this text, ignoring case.
+ */
+contains?: string | null; 
+/**
+ * Return at most this many of the most recent matching entries.
+ */
+limit?: number | null }
+
+export type LogLevel = "error" | "warn" | "info" | "debug" | "trace"
+
+/**
+ * The outcome of compacting or checking one open database. Sizes are in bytes, and kept as
+ * floating point so they stay exact in JS.
+ */
+export type MaintenanceReport = { database: string; size_before: number; size_after: number; 
+/**
+ * Set when compacting; `false` means there was nothing left to reclaim.
+ */
+compacted?: boolean | null; 
+/**
+ * Set when checking integrity.
+ */
+integrity?: IntegrityStatus | null; tables: TableRows[] }
+
+/**
+ * A pin, region or path drawn on a map, optionally linked to an entity.
+ */
+export type MapAnnotation = ({ shape: "pin"; at: MapPoint } | { shape: "region"; points: MapPoint[] } | { shape: "path"; points: MapPoint[] }) & { id: string; map: string; 
+/**
+ * The layer this annotation is drawn on, or `None` for the base layer.
+ */
+layer?: string | null; label?: string | null; entity?: string | null }
+
+/**
+ * An uploaded map image, cut into a pyramid of tiles for display.
+ */
+export type MapInfo = { id: string; name: string; 
+/**
+ * Hash of the original image in the asset store.
+ */
+image: string; width: number; height: number; tile_size: number; 
+/**
+ * Number of pyramid levels. Level 0 is the full-size image, and every level after it is
+ * half the size of the one before, down to a single tile.
+ */
+levels: number; 
+/**
+ * Layers in drawing order, bottom first.
+ */
+layers?: MapLayer[]; 
+/**
+ * The entity this map depicts. Pins outside of every linked region are located in it.
+ */
+location?: string | null; created: string }
+
+export type MapLayer = { id: string; name: string; hidden?: boolean }
+
+/**
+ * A position on a map, in pixels of the full-size image.
+ */
+export type MapPoint = { x: number; y: number }
 
 export type MetaError = { code: string; message: string }
 
+/**
+ * The entities within some number of relations of an entity, and the relations between them.
+ */
+export type Neighbourhood = { nodes: GraphNode[]; relations: Relation[] }
+
 export type ProjectCollaborator = { identity: string; name: string; can_edit?: boolean }
 
-export type ProjectSettings = { name: string; identity?: string; collaborators?: Partial<{ [key in string]: ProjectCollaborator }> }
+export type ProjectSettings = { name: string; identity?: string; collaborators?: Partial<{ [key in string]: ProjectCollaborator }>; templates?: Partial<{ [key in string]: EntityTemplate }>; calendars?: Partial<{ [key in string]: Calendar }> }
+
+export type RecentProject = { path: string; name: string; 
+/**
+ * The host of a joined remote project, or `None` for a local project.
+ */
+host?: string | null; last_opened: string; pinned?: boolean }
+
+/**
+ * A typed, directed link between two entities. Links can be followed in either direction.
+ */
+export type Relation = { source: string; target: string; kind: RelationKind }
+
+export type RelationKind = { kind: "parent_of" } | { kind: "member_of" } | { kind: "located_in" } | { kind: "rival_of" } | { kind: "custom"; label: string }
+
+export type SearchQuery = { 
+/**
+ * Free text. Bare words match as prefixes, `"quoted words"` match as a phrase, and
+ * `tag:name` or `kind:name` narrow the results like [`SearchQuery::tags`] and
+ * [`SearchQuery::kinds`].
+ */
+text: string; 
+/**
+ * Only match entities of one of these kinds.
+ */
+kinds?: EntityKind[]; 
+/**
+ * Only match entities carrying every one of these tags.
+ */
+tags?: string[]; limit?: number | null }
+
+export type SearchResult = { id: string; title: string; kind: EntityKind; score: number; 
+/**
+ * An excerpt of the entity's body around the first match, if the body matched.
+ */
+snippet: string | null }
+
+export type SyncStatus = { running: boolean; identity: string | null; peers: string[] }
+
+/**
+ * Records changed in one table by a committed write.
+ */
+export type TableChange = { table: string; multimap: boolean; 
+/**
+ * Keys of the records that were inserted, replaced or removed. Empty if only the table is
+ * known to have changed, ie. after it was written to as a raw table, cleared or rebuilt, or
+ * after a savepoint was restored.
+ */
+keys?: string[] }
+
+/**
+ * A table that couldn't be copied in full while rebuilding a corrupted database.
+ */
+export type TableLoss = { table: string; error: MetaError }
+
+export type TableRows = { table: string; multimap: boolean; rows: number }
+
+export type TemplateField = { name: string; label?: string | null; field_type: FieldType; required?: boolean; 
+/**
+ * Filled into existing entities when this field is added by a template change.
+ */
+default?: FieldValue | null }
+
+export type Theme = "system" | "light" | "dark"
+
+export type TimelineEntry = { event: TimelineEvent; 
+/**
+ * Set when the query asked for a calendar.
+ */
+start: DescribedDate | null; end: DescribedDate | null }
+
+/**
+ * Something that happened in the world, attached to the entities involved in it.
+ */
+export type TimelineEvent = { id: string; title: string; description?: string | null; 
+/**
+ * The absolute day the event starts on. See [`crate::types::Calendar::to_days`].
+ */
+start: number; 
+/**
+ * The absolute day the event ends on, for events lasting more than one day.
+ */
+end?: number | null; entities?: string[]; tags?: string[] }
+
+export type TimelineQuery = { 
+/**
+ * Only include events still ongoing on or after this absolute day.
+ */
+start?: number | null; 
+/**
+ * Only include events starting on or before this absolute day.
+ */
+end?: number | null; entity?: string | null; tag?: string | null; 
+/**
+ * The calendar to describe event dates in.
+ */
+calendar?: string | null }
+
+/**
+ * Hints the frontend uses to restore its window the way the user left it.
+ */
+export type WindowLayout = { width?: number | null; height?: number | null; maximized?: boolean; sidebar_width?: number | null }
 
-const ARGS_MAP = { '':'{"app_event":["id","event"]}', 'projects':'{"create_project":["name","path"],"current_project":[],"open_local_project":["path"]}' }
+const ARGS_MAP = { '':'{"app_event":["id","event"]}', 'assets':'{"attach":["entity","hash"],"collect_garbage":[],"detach":["entity","hash"],"directory":[],"entity_assets":["entity"],"fetch":["hash"],"get_asset":["hash"],"import_asset":["source"],"list_assets":[],"read_asset":["hash"],"usage":["hash"]}', 'calendars':'{"create_calendar":["calendar"],"delete_calendar":["id"],"from_days":["calendar","days"],"get_calendar":["id"],"list_calendars":[],"to_days":["calendar","date"],"update_calendar":["calendar"]}', 'changes':'{"subscribe":["database","tables"],"unsubscribe":["id"]}', 'documents':'{"apply_update":["id","update"],"diff":["id","state_vector"],"state_vector":["id"]}', 'history':'{"history":[],"redo":[],"undo":[]}', 'links':'{"broken_links":[],"what_links_here":["id"]}', 'logs':'{"directory":[],"recent":["filter"]}', 'maintenance':'{"check_integrity":[],"compact":[]}', 'maps':'{"add_annotation":["annotation"],"annotations":["map"],"delete_map":["id"],"get_map":["id"],"import_map":["name","source"],"list_maps":[],"remove_annotation":["id"],"tile":["id","level","x","y"],"update_annotation":["annotation"],"update_map":["map"]}', 'projects':'{"create_project":["name","path"],"current_project":[],"join_remote_project":["ticket","name"],"open_local_project":["path"],"open_remote_project":["host"]}', 'recent':'{"forget":["path"],"list":[],"pin":["path","pinned"],"reopen":["path"]}', 'relations':'{"add_relation":["relation"],"neighbours":["id","depth"],"relations_of":["id"],"remove_relation":["relation"],"shortest_path":["from","to"]}', 'search':'{"rebuild_index":[],"search":["query"]}', 'settings':'{"get_settings":[],"update_settings":["settings"]}', 'sync':'{"connect":["peer"],"create_invite":["can_edit","valid_hours"],"start":[],"status":[],"stop":[]}', 'templates':'{"create_template":["template"],"delete_template":["id"],"evolve_template":["template","changes"],"get_template":["id"],"list_templates":[]}', 'timeline':'{"create_event":["event"],"delete_event":["id"],"get_event":["id"],"query":["query"],"update_event":["event"]}', 'world':'{"create_entity":["title","data"],"delete_entity":["id"],"get_entity":["id"],"list_entities":["kind"],"update_entity":["id","update"]}' }
 export type Router = { "": {app_event: (id: string, event: AppEvent) => Promise<void>},
+"assets": {attach: (entity: string, hash: string) => Promise<null>, 
+collect_garbage: () => Promise<AssetInfo[]>, 
+detach: (entity: string, hash: string) => Promise<boolean>, 
+directory: () => Promise<string>, 
+entity_assets: (entity: string) => Promise<AssetInfo[]>, 
+fetch: (hash: string) => Promise<AssetInfo>, 
+get_asset: (hash: string) => Promise<AssetInfo>, 
+import_asset: (source: string) => Promise<AssetInfo>, 
+list_assets: () => Promise<AssetInfo[]>, 
+read_asset: (hash: string) => Promise<number[]>, 
+usage: (hash: string) => Promise<AssetUsage>},
+"calendars": {create_calendar: (calendar: Calendar) => Promise<Calendar>, 
+delete_calendar: (id: string) => Promise<Calendar>, 
+from_days: (calendar: string, days: number) => Promise<DescribedDate>, 
+get_calendar: (id: string) => Promise<Calendar>, 
+list_calendars: () => Promise<Calendar[]>, 
+to_days: (calendar: string, date: CalendarDate) => Promise<number>, 
+update_calendar: (calendar: Calendar) => Promise<Calendar>},
+"changes": {subscribe: (database: string | null, tables: string[]) => Promise<ChangeSubscription>, 
+unsubscribe: (id: string) => Promise<boolean>},
+"documents": {apply_update: (id: string, update: number[]) => Promise<null>, 
+diff: (id: string, stateVector: number[]) => Promise<number[]>, 
+state_vector: (id: string) => Promise<number[]>},
+"history": {history: () => Promise<History>, 
+redo: () => Promise<HistoryItem | null>, 
+undo: () => Promise<HistoryItem | null>},
+"links": {broken_links: () => Promise<Backlink[]>, 
+what_links_here: (id: string) => Promise<Backlink[]>},
+"logs": {directory: () => Promise<string>, 
+recent: (filter: LogFilter) => Promise<LogEntry[]>},
+"maintenance": {check_integrity: () => Promise<MaintenanceReport[]>, 
+compact: () => Promise<MaintenanceReport[]>},
+"maps": {add_annotation: (annotation: MapAnnotation) => Promise<MapAnnotation>, 
+annotations: (map: string) => Promise<MapAnnotation[]>, 
+delete_map: (id: string) => Promise<MapInfo>, 
+get_map: (id: string) => Promise<MapInfo>, 
+import_map: (name: string, source: string) => Promise<MapInfo>, 
+list_maps: () => Promise<MapInfo[]>, 
+remove_annotation: (id: string) => Promise<MapAnnotation>, 
+tile: (id: string, level: number, x: number, y: number) => Promise<number[]>, 
+update_annotation: (annotation: MapAnnotation) => Promise<MapAnnotation>, 
+update_map: (map: MapInfo) => Promise<MapInfo>},
 "projects": {create_project: (name: string, path: string) => Promise<string>, 
-current_project: () => Promise<[ActiveProject, ProjectSettings] | null>, 
-open_local_project: (path: string) => Promise<ProjectSettings>} };
+current_project: () => Promise<CurrentProject | null>, 
+join_remote_project: (ticket: string, name: string) => Promise<ProjectSettings>, 
+open_local_project: (path: string) => Promise<ProjectSettings>, 
+open_remote_project: (host: string) => Promise<ProjectSettings>},
+"recent": {forget: (path: string) => Promise<RecentProject[]>, 
+list: () => Promise<RecentProject[]>, 
+pin: (path: string, pinned: boolean) => Promise<RecentProject[]>, 
+reopen: (path: string) => Promise<ProjectSettings>},
+"relations": {add_relation: (relation: Relation) => Promise<Relation>, 
+neighbours: (id: string, depth: number) => Promise<Neighbourhood>, 
+relations_of: (id: string) => Promise<Relation[]>, 
+remove_relation: (relation: Relation) => Promise<boolean>, 
+shortest_path: (from: string, to: string) => Promise<Relation[] | null>},
+"search": {rebuild_index: () => Promise<number>, 
+search: (query: SearchQuery) => Promise<SearchResult[]>},
+"settings": {get_settings: () => Promise<AppSettings>, 
+update_settings: (settings: AppSettings) => Promise<AppSettings>},
+"sync": {connect: (peer: string) => Promise<SyncStatus>, 
+create_invite: (canEdit: boolean, validHours: number) => Promise<string>, 
+start: () => Promise<SyncStatus>, 
+status: () => Promise<SyncStatus>, 
+stop: () => Promise<SyncStatus>},
+"templates": {create_template: (template: EntityTemplate) => Promise<EntityTemplate>, 
+delete_template: (id: string) => Promise<EntityTemplate>, 
+evolve_template: (template: EntityTemplate, changes: FieldChange[]) => Promise<EntityTemplate>, 
+get_template: (id: string) => Promise<EntityTemplate>, 
+list_templates: () => Promise<EntityTemplate[]>},
+"timeline": {create_event: (event: TimelineEvent) => Promise<TimelineEvent>, 
+delete_event: (id: string) => Promise<TimelineEvent>, 
+get_event: (id: string) => Promise<TimelineEvent>, 
+query: (query: TimelineQuery) => Promise<TimelineEntry[]>, 
+update_event: (event: TimelineEvent) => Promise<TimelineEvent>},
+"world": {create_entity: (title: string, data: EntityData) => Promise<Entity>, 
+delete_entity: (id: string) => Promise<Entity>, 
+get_entity: (id: string) => Promise<Entity>, 
+list_entities: (kind: EntityKind | null) => Promise<Entity[]>, 
+update_entity: (id: string, update: EntityUpdate) => Promise<Entity>} };
 
 
 export const createTauRPCProxy = () => createProxy<Router>(ARGS_MAP)